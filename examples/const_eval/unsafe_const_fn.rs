@@ -0,0 +1,11 @@
+// analyzed only with `--const-eval=1`
+const unsafe fn second(p: *const u8) -> u8 {
+    *p.add(1)
+}
+
+static BYTES: [u8; 2] = [1, 2];
+static SECOND: u8 = unsafe { second(BYTES.as_ptr()) };
+
+fn main() {
+    println!("{}", SECOND);
+}
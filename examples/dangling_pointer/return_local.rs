@@ -0,0 +1,29 @@
+// pointers to locals escape the fn after the locals are dropped
+fn return_local_ptr() -> *const String {
+    let x = String::from("local");
+    &x as *const String
+}
+
+fn return_local_ref() -> &'static String {
+    let x = String::from("local");
+    unsafe { &*(&x as *const String) }
+}
+
+fn store_to_out(out: &mut *const Vec<i32>) {
+    let v = vec![1, 2, 3];
+    *out = &v as *const Vec<i32>;
+}
+
+// the ownership moves to the caller, nothing dangles
+fn return_box() -> Box<String> {
+    let b = Box::new(String::from("boxed"));
+    b
+}
+
+fn main() {
+    let _p = return_local_ptr();
+    let _r = return_local_ref();
+    let mut out = std::ptr::null();
+    store_to_out(&mut out);
+    let _b = return_box();
+}
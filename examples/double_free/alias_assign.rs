@@ -0,0 +1,19 @@
+fn main() {
+    let mut x = Box::new(1);
+    let pa = &mut x as *mut Box<i32>;
+    let pb = &x as *const Box<i32>;
+    unsafe {
+        // `*pa` and `*pb` are the same box, it is dropped before being written back
+        *pa = std::ptr::read(pb);
+    }
+    println!("{}", x);
+}
+
+fn assign_new_value() {
+    let mut x = Box::new(1);
+    let pa = &mut x as *mut Box<i32>;
+    unsafe {
+        *pa = Box::new(2);
+    }
+    println!("{}", x);
+}
@@ -0,0 +1,60 @@
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+
+struct Pair {
+    a: String,
+    b: String,
+}
+
+// both pointers are copied from the same pointer
+fn copied_pointer() {
+    let mut s = ManuallyDrop::new(String::from("a"));
+    let p1: *mut String = &mut *s;
+    let p2 = p1;
+    unsafe {
+        ptr::drop_in_place(p1);
+        ptr::drop_in_place(p2);
+    }
+}
+
+// both pointers borrow the same stack slot
+fn two_borrows() {
+    let mut s = String::from("a");
+    let p1 = &mut s as *mut String;
+    let p2 = &mut s as *mut String;
+    unsafe {
+        ptr::drop_in_place(p1);
+        ptr::drop_in_place(p2);
+    }
+    mem::forget(s);
+}
+
+// different values, no bug
+fn two_values() {
+    let mut s = ManuallyDrop::new(String::from("a"));
+    let mut t = ManuallyDrop::new(String::from("b"));
+    let p1: *mut String = &mut *s;
+    let p2: *mut String = &mut *t;
+    unsafe {
+        ptr::drop_in_place(p1);
+        ptr::drop_in_place(p2);
+    }
+}
+
+// different fields of the same value, no bug
+fn two_fields() {
+    let mut pair = ManuallyDrop::new(Pair { a: String::new(), b: String::new() });
+    let p1: *mut String = &mut pair.a;
+    let p2: *mut String = &mut pair.b;
+    unsafe {
+        ptr::drop_in_place(p1);
+        ptr::drop_in_place(p2);
+    }
+}
+
+fn main() {
+    copied_pointer();
+    two_borrows();
+    two_values();
+    two_fields();
+}
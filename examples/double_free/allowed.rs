@@ -0,0 +1,27 @@
+// `#[mc::allow(..)]` silences the bugs with a drop or dereference in the fn, `cfg_attr` keeps normal builds working.
+// The box is freed twice in each fn, only `reported` is reported.
+#[cfg_attr(mc, mc::allow(double_free))]
+fn allowed() {
+    let p = Box::into_raw(Box::new(1));
+    unsafe {
+        let b1 = Box::from_raw(p);
+        let b2 = Box::from_raw(p);
+        println!("{} {}", b1, b2);
+    }
+}
+
+// only the use after free is allowed, the double free is still reported
+#[cfg_attr(mc, mc::allow(use_after_free))]
+fn reported() {
+    let p = Box::into_raw(Box::new(1));
+    unsafe {
+        let b1 = Box::from_raw(p);
+        let b2 = Box::from_raw(p);
+        println!("{} {}", b1, b2);
+    }
+}
+
+fn main() {
+    allowed();
+    reported();
+}
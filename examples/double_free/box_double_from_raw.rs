@@ -0,0 +1,11 @@
+// `Box::from_raw` is called twice on the pointer returned by `Box::into_raw`,
+// both boxes free the same object when they are dropped.
+fn main() {
+    let b = Box::new(1);
+    let p = Box::into_raw(b);
+    unsafe {
+        let b1 = Box::from_raw(p);
+        let b2 = Box::from_raw(p);
+        println!("{} {}", b1, b2);
+    }
+}
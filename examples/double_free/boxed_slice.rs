@@ -0,0 +1,37 @@
+use std::ptr;
+
+// the slice box is reclaimed again from its raw pointer
+fn from_raw_of_ref() {
+    let b: Box<[i32]> = vec![1, 2, 3].into_boxed_slice();
+    let p = &*b as *const [i32] as *mut [i32];
+    let _c = unsafe { Box::from_raw(p) };
+}
+
+// the slice box is rebuilt from the pointer of its elements
+fn from_raw_of_elements() {
+    let mut b: Box<[i32]> = Box::new([1, 2, 3]);
+    let p = ptr::slice_from_raw_parts_mut(b.as_mut_ptr(), b.len());
+    let _c = unsafe { Box::from_raw(p) };
+}
+
+// the released slice is reclaimed twice
+fn from_raw_twice() {
+    let b: Box<[i32]> = vec![1, 2, 3].into_boxed_slice();
+    let p = Box::into_raw(b);
+    let _c1 = unsafe { Box::from_raw(p) };
+    let _c2 = unsafe { Box::from_raw(p) };
+}
+
+// a vec and the slice box share the buffer
+fn vec_from_slice_buffer() {
+    let b: Box<[String]> = vec!["a".to_string()].into_boxed_slice();
+    let p = b.as_ptr() as *mut String;
+    let _v = unsafe { Vec::from_raw_parts(p, b.len(), b.len()) };
+}
+
+fn main() {
+    from_raw_of_ref();
+    from_raw_of_elements();
+    from_raw_twice();
+    vec_from_slice_buffer();
+}
@@ -0,0 +1,45 @@
+use std::mem;
+use std::ptr;
+
+// the same element is dropped twice
+fn same_constant_index() {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[0]);
+        ptr::drop_in_place(&mut boxes[0]);
+    }
+    mem::forget(boxes);
+}
+
+// each element is dropped once, no bug
+fn distinct_constant_indices() {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[0]);
+        ptr::drop_in_place(&mut boxes[1]);
+    }
+    mem::forget(boxes);
+}
+
+// a dynamic index may be the element at the constant index
+fn dynamic_and_constant_index(i: usize) {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[i]);
+        ptr::drop_in_place(&mut boxes[1]);
+    }
+    mem::forget(boxes);
+}
+
+// the element read out is owned by the array too
+fn read_element() {
+    let boxes = [Box::new(1), Box::new(2)];
+    let _first = unsafe { ptr::read(&boxes[0]) };
+}
+
+fn main() {
+    same_constant_index();
+    distinct_constant_indices();
+    dynamic_and_constant_index(0);
+    read_element();
+}
@@ -0,0 +1,21 @@
+#![crate_type = "lib"]
+
+mod a {
+    // the box is rebuilt from the pointer and dropped at the end
+    pub fn release(p: *mut Vec<i32>) {
+        let _v = unsafe { Box::from_raw(p) };
+    }
+}
+
+mod b {
+    // the same as `a::release`, only the fn tells them apart
+    pub fn release(p: *mut Vec<i32>) {
+        let _v = unsafe { Box::from_raw(p) };
+    }
+}
+
+pub fn release_twice() {
+    let p = Box::into_raw(Box::new(vec![1]));
+    a::release(p);
+    b::release(p);
+}
@@ -0,0 +1,29 @@
+#![crate_type = "lib"]
+use std::os::raw::c_void;
+
+pub struct Data {
+    pub buf: Vec<u8>,
+}
+
+extern "C" {
+    // frees the value behind `p`, registered by `--drop-fns=my_free`
+    fn my_free(p: *mut c_void);
+    // frees the value behind `p`, registered by `--drop-fns=pool_free:1`
+    fn pool_free(pool: *mut c_void, p: *mut c_void);
+}
+
+// `data` is freed by `my_free`, then dropped again on return
+pub fn free_then_drop() {
+    let mut data = Data { buf: vec![1] };
+    unsafe {
+        my_free(&mut data as *mut Data as *mut c_void);
+    }
+}
+
+// the same with the freed value as the second argument
+pub fn pool_free_then_drop(pool: *mut c_void) {
+    let mut data = Data { buf: vec![1] };
+    unsafe {
+        pool_free(pool, &mut data as *mut Data as *mut c_void);
+    }
+}
@@ -0,0 +1,40 @@
+use std::mem;
+use std::ptr;
+
+// the indices may be equal, a dynamic index may be any element of the array
+fn two_dynamic_indices(i: usize, j: usize) {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[i]);
+        ptr::drop_in_place(&mut boxes[j]);
+    }
+    mem::forget(boxes);
+}
+
+// the same element is dropped twice
+fn same_dynamic_index(i: usize) {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[i]);
+        ptr::drop_in_place(&mut boxes[i]);
+    }
+    mem::forget(boxes);
+}
+
+// elements of different arrays, no bug
+fn two_arrays(i: usize) {
+    let mut boxes = [Box::new(1), Box::new(2)];
+    let mut others = [Box::new(3), Box::new(4)];
+    unsafe {
+        ptr::drop_in_place(&mut boxes[i]);
+        ptr::drop_in_place(&mut others[i]);
+    }
+    mem::forget(boxes);
+    mem::forget(others);
+}
+
+fn main() {
+    two_dynamic_indices(0, 1);
+    same_dynamic_index(0);
+    two_arrays(0);
+}
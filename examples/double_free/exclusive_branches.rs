@@ -0,0 +1,22 @@
+#![crate_type = "lib"]
+
+fn free(b: Box<i32>) {
+    let _b = b;
+}
+
+// only one of the branches runs, the box is freed once
+pub fn free_on_either_branch(c: bool) {
+    let p = Box::into_raw(Box::new(1));
+    if c {
+        free(unsafe { Box::from_raw(p) });
+    } else {
+        free(unsafe { Box::from_raw(p) });
+    }
+}
+
+// both calls run, the box is freed twice
+pub fn free_twice() {
+    let p = Box::into_raw(Box::new(2));
+    free(unsafe { Box::from_raw(p) });
+    free(unsafe { Box::from_raw(p) });
+}
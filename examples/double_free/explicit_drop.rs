@@ -0,0 +1,34 @@
+#![crate_type = "lib"]
+use std::{mem, ptr};
+
+// the vector read out of `x` is dropped by both explicit calls
+pub fn drop_both() {
+    let x = vec![1];
+    let y = unsafe { ptr::read(&x) };
+    drop(x);
+    drop(y);
+}
+
+// dropped by the call, then again at the end of the scope
+pub fn drop_then_scope() -> usize {
+    let x = vec![1];
+    let y = unsafe { ptr::read(&x) };
+    drop(x);
+    y.len()
+}
+
+// the forgotten vector is only dropped through `y`
+pub fn forget_then_drop() {
+    let x = vec![1];
+    let y = unsafe { ptr::read(&x) };
+    mem::forget(x);
+    drop(y);
+}
+
+// the same when `y` is dropped at the end of the scope
+pub fn forget_then_scope() -> usize {
+    let x = vec![1];
+    let y = unsafe { ptr::read(&x) };
+    mem::forget(x);
+    y.len()
+}
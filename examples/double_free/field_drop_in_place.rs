@@ -0,0 +1,42 @@
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+
+struct Pair {
+    name: String,
+    data: Vec<i32>,
+}
+
+struct Holder {
+    name: ManuallyDrop<String>,
+    data: Vec<i32>,
+}
+
+// the drop glue of `pair` frees `name` again
+fn drop_field(mut pair: Pair) {
+    unsafe {
+        ptr::drop_in_place(&mut pair.name);
+    }
+    println!("{:?}", pair.data);
+}
+
+// `pair` is forgotten, no drop glue
+fn drop_field_then_forget(mut pair: Pair) {
+    unsafe {
+        ptr::drop_in_place(&mut pair.name);
+    }
+    mem::forget(pair);
+}
+
+// the drop glue of `holder` skips the `ManuallyDrop` field
+fn drop_manually_drop_field(mut holder: Holder) {
+    unsafe {
+        ptr::drop_in_place(&mut *holder.name as *mut String);
+    }
+    println!("{:?}", holder.data);
+}
+
+fn main() {
+    drop_field(Pair { name: "a".to_string(), data: vec![1] });
+    drop_field_then_forget(Pair { name: "b".to_string(), data: vec![2] });
+    drop_manually_drop_field(Holder { name: ManuallyDrop::new("c".to_string()), data: vec![3] });
+}
@@ -0,0 +1,32 @@
+use std::ptr;
+
+// the moved-in vec is read out and dropped again in every iteration
+fn consume_in_loop(v: Vec<i32>) {
+    let p = &v as *const Vec<i32>;
+    std::mem::forget(v);
+    for _ in 0..2 {
+        let _owned = unsafe { ptr::read(p) };
+    }
+}
+
+// every iteration reads a different element, no double free
+fn drop_each_element(v: Vec<String>) {
+    let mut v = std::mem::ManuallyDrop::new(v);
+    let p = v.as_mut_ptr();
+    for i in 0..v.len() {
+        let _owned = unsafe { ptr::read(p.add(i)) };
+    }
+}
+
+// a new box in every iteration, no double free
+fn fresh_in_loop() {
+    for i in 0..2 {
+        let _b = Box::new(i);
+    }
+}
+
+fn main() {
+    consume_in_loop(vec![1, 2]);
+    drop_each_element(vec!["a".to_string()]);
+    fresh_in_loop();
+}
@@ -0,0 +1,18 @@
+struct Token(i32);
+
+// each box is dropped once, under `--object-granularity=type` the boxes of both fns are one
+// object, dropped in `first` and again in `second`
+fn first() {
+    let token = Box::new(Token(1));
+    let _ = token;
+}
+
+fn second() {
+    let token = Box::new(Token(2));
+    let _ = token;
+}
+
+fn main() {
+    first();
+    second();
+}
@@ -0,0 +1,37 @@
+use std::mem;
+use std::ptr;
+
+struct Pair {
+    a: Box<i32>,
+    b: Box<i32>,
+}
+
+// `b` is moved out, so the drop glue frees `a` on its own, the second time
+fn drop_a_then_move_b(mut pair: Pair) {
+    unsafe {
+        ptr::drop_in_place(&mut pair.a);
+    }
+    mem::forget(pair.b);
+}
+
+// the same for `b`, reported apart from `a`
+fn drop_b_then_move_a(mut pair: Pair) {
+    unsafe {
+        ptr::drop_in_place(&mut pair.b);
+    }
+    mem::forget(pair.a);
+}
+
+// the dropped `a` is moved out, only `b` is freed by the drop glue
+fn drop_a_then_move_a(mut pair: Pair) {
+    unsafe {
+        ptr::drop_in_place(&mut pair.a);
+    }
+    mem::forget(pair.a);
+}
+
+fn main() {
+    drop_a_then_move_b(Pair { a: Box::new(1), b: Box::new(2) });
+    drop_b_then_move_a(Pair { a: Box::new(3), b: Box::new(4) });
+    drop_a_then_move_a(Pair { a: Box::new(5), b: Box::new(6) });
+}
@@ -0,0 +1,34 @@
+#![crate_type = "lib"]
+use std::{mem, ptr};
+
+// the vector is owned by both `x` and `y` after the read
+pub fn read_then_drop_both() {
+    let x = vec![1];
+    let y = unsafe { ptr::read(&x) };
+    drop(x);
+    drop(y);
+}
+
+// the vector read out of `a` is moved into `b`, it is owned by both
+pub fn replace_with_read() -> usize {
+    let a = vec![1];
+    let mut b = vec![2];
+    let old = mem::replace(&mut b, unsafe { ptr::read(&a) });
+    old.len() + b.len()
+}
+
+// the vector is written into after it is dropped at the end of its scope
+pub fn write_after_free() {
+    let p;
+    {
+        let mut v = vec![1];
+        p = &mut v as *mut Vec<i32>;
+    }
+    unsafe { ptr::write(p, vec![2]) };
+}
+
+// the value of a reference is copied out, the read is only a copy of an integer
+pub fn read_copy() -> i32 {
+    let x = 1;
+    unsafe { ptr::read(&x) }
+}
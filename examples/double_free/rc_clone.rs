@@ -0,0 +1,22 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+// clones share the value, it is only freed when the last clone is dropped
+fn drop_clones() {
+    let a = Rc::new(Box::new(1));
+    let b = a.clone();
+    drop(a);
+    drop(b);
+}
+
+fn deref_clone_after_drop() {
+    let a = Arc::new(String::from("shared"));
+    let b = Arc::clone(&a);
+    drop(a);
+    println!("{}", b);
+}
+
+fn main() {
+    drop_clones();
+    deref_clone_after_drop();
+}
@@ -0,0 +1,88 @@
+#![crate_type = "lib"]
+use std::cell::{Cell, RefCell};
+use std::mem;
+
+// the box is moved out of the option, the option is dropped empty
+pub fn option_take() {
+    let mut opt = Some(Box::new(1));
+    let v = opt.take().unwrap();
+    drop(v);
+    drop(opt);
+}
+
+// the option keeps the new box
+pub fn option_replace() {
+    let mut opt = Some(Box::new(1));
+    let v = opt.replace(Box::new(2));
+    drop(v);
+    drop(opt);
+}
+
+pub fn mem_take() {
+    let mut opt = Some(Box::new(1));
+    let v = mem::take(&mut opt);
+    drop(v);
+    drop(opt);
+}
+
+pub fn mem_replace() {
+    let mut opt = Some(Box::new(1));
+    let v = mem::replace(&mut opt, None);
+    drop(v);
+    drop(opt);
+}
+
+pub fn cell_take() {
+    let cell = Cell::new(Some(Box::new(1)));
+    let v = cell.take();
+    drop(v);
+    drop(cell);
+}
+
+pub fn cell_replace() {
+    let cell = Cell::new(Some(Box::new(1)));
+    let v = cell.replace(None);
+    drop(v);
+    drop(cell);
+}
+
+pub fn refcell_take() {
+    let cell = RefCell::new(Some(Box::new(1)));
+    let v = cell.take();
+    drop(v);
+    drop(cell);
+}
+
+pub fn refcell_replace() {
+    let cell = RefCell::new(Box::new(1));
+    let v = cell.replace(Box::new(2));
+    drop(v);
+    drop(cell);
+}
+
+pub fn refcell_into_inner() {
+    let cell = RefCell::new(Box::new(1));
+    let v = cell.into_inner();
+    drop(v);
+}
+
+// the taken box is still freed twice
+pub fn take_then_double_from_raw() {
+    let mut opt = Some(Box::new(1));
+    let raw = Box::into_raw(opt.take().unwrap());
+    unsafe {
+        drop(Box::from_raw(raw));
+        drop(Box::from_raw(raw));
+    }
+    drop(opt);
+}
+
+// the taken box is read after it is dropped, not after the option is dropped
+pub fn take_then_read() -> i32 {
+    let mut opt = Some(Box::new(1));
+    let v = opt.take().unwrap();
+    let p = &*v as *const i32;
+    drop(v);
+    drop(opt);
+    unsafe { *p }
+}
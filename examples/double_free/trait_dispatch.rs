@@ -0,0 +1,46 @@
+trait Sink {
+    fn take(&self, p: *mut String);
+}
+
+struct Dropper;
+
+impl Sink for Dropper {
+    // the string behind the pointer is freed here
+    fn take(&self, p: *mut String) {
+        let _s = unsafe { Box::from_raw(p) };
+    }
+}
+
+struct Keeper;
+
+impl Sink for Keeper {
+    fn take(&self, _p: *mut String) {}
+}
+
+// the sink may be a `Dropper`, the string is then freed twice
+fn give_dyn(sink: &dyn Sink) {
+    let p = Box::into_raw(Box::new(String::from("dyn")));
+    sink.take(p);
+    let _s = unsafe { Box::from_raw(p) };
+}
+
+// the same through a type parameter
+fn give_generic<S: Sink>(sink: &S) {
+    let p = Box::into_raw(Box::new(String::from("generic")));
+    sink.take(p);
+    let _s = unsafe { Box::from_raw(p) };
+}
+
+// the impl is known, a `Keeper` never frees the string, no bug
+fn give_keeper(sink: &Keeper) {
+    let p = Box::into_raw(Box::new(String::from("keeper")));
+    sink.take(p);
+    let _s = unsafe { Box::from_raw(p) };
+}
+
+fn main() {
+    give_dyn(&Keeper);
+    give_generic(&Keeper);
+    give_keeper(&Keeper);
+    let _ = Dropper;
+}
@@ -0,0 +1,10 @@
+// `q` is transmuted from `p`, both boxes rebuilt from them free the same object
+fn main() {
+    let p = Box::into_raw(Box::new(1));
+    let q: *mut i32 = unsafe { std::mem::transmute(p) };
+    unsafe {
+        let b1 = Box::from_raw(p);
+        let b2 = Box::from_raw(q);
+        println!("{} {}", b1, b2);
+    }
+}
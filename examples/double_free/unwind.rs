@@ -0,0 +1,16 @@
+#![crate_type = "lib"]
+use std::{mem, ptr};
+
+fn might_panic(n: usize) {
+    if n > 1 {
+        panic!("too many");
+    }
+}
+
+// on the normal path `w` is forgotten, only unwinding from the panic drops both `w` and `v`
+pub fn double_free_on_unwind(n: usize) {
+    let v = vec![1];
+    let w = unsafe { ptr::read(&v) };
+    might_panic(n);
+    mem::forget(w);
+}
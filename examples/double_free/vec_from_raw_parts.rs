@@ -0,0 +1,30 @@
+use std::mem::ManuallyDrop;
+
+// both vecs own the buffer of `v`, it is freed again by the second drop
+fn two_vecs_from_one_buffer() {
+    let mut v = ManuallyDrop::new(vec![1, 2, 3]);
+    let (ptr, len, cap) = (v.as_mut_ptr(), v.len(), v.capacity());
+    let _a = unsafe { Vec::from_raw_parts(ptr, len, cap) };
+    let _b = unsafe { Vec::from_raw_parts(ptr, len, cap) };
+}
+
+// the buffer is given back to one vec only, no bug
+fn one_vec_from_buffer() {
+    let mut v = ManuallyDrop::new(vec![1, 2, 3]);
+    let (ptr, len, cap) = (v.as_mut_ptr(), v.len(), v.capacity());
+    let _a = unsafe { Vec::from_raw_parts(ptr, len, cap) };
+}
+
+// each vec owns its own buffer, no bug
+fn two_buffers() {
+    let mut v = ManuallyDrop::new(vec![1, 2, 3]);
+    let mut w = ManuallyDrop::new(vec![4, 5, 6]);
+    let _a = unsafe { Vec::from_raw_parts(v.as_mut_ptr(), v.len(), v.capacity()) };
+    let _b = unsafe { Vec::from_raw_parts(w.as_mut_ptr(), w.len(), w.capacity()) };
+}
+
+fn main() {
+    two_vecs_from_one_buffer();
+    one_vec_from_buffer();
+    two_buffers();
+}
@@ -0,0 +1,23 @@
+// the ownership given up by `Box::into_raw` is never reclaimed by `Box::from_raw`.
+fn leak() {
+    let b = Box::new(1);
+    let p = Box::into_raw(b);
+    unsafe {
+        println!("{}", *p);
+    }
+}
+
+// reclaimed exactly once, no bug
+fn reclaim() {
+    let b = Box::new(1);
+    let p = Box::into_raw(b);
+    unsafe {
+        let b1 = Box::from_raw(p);
+        println!("{}", b1);
+    }
+}
+
+fn main() {
+    leak();
+    reclaim();
+}
@@ -0,0 +1,40 @@
+// the box is dropped in one arm and forgotten in the other
+fn leak_in_one_arm(c: bool) {
+    let b = Box::new(1);
+    if c {
+        let _x = b;
+    } else {
+        std::mem::forget(b);
+    }
+}
+
+fn leak_always() {
+    let b = Box::new(String::from("forgotten"));
+    std::mem::forget(b);
+}
+
+// the drop flag of `b` guards its drop at the end, no leak
+fn conditional_move(c: bool) {
+    let b = Box::new(1);
+    if c {
+        let _x = b;
+    }
+}
+
+// the ownership leaves the fn, no leak here
+fn moved_out() -> Box<i32> {
+    Box::new(1)
+}
+
+fn moved_into_vec(v: &mut Vec<Box<i32>>) {
+    v.push(Box::new(1));
+}
+
+fn main() {
+    leak_in_one_arm(true);
+    leak_always();
+    conditional_move(false);
+    let _b = moved_out();
+    let mut v = vec![];
+    moved_into_vec(&mut v);
+}
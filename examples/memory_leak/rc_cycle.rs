@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+struct Node {
+    value: i32,
+    parent: RefCell<Option<Rc<Node>>>,
+    children: RefCell<Vec<Rc<Node>>>,
+}
+
+struct WeakNode {
+    value: i32,
+    parent: RefCell<Weak<WeakNode>>,
+    children: RefCell<Vec<Rc<WeakNode>>>,
+}
+
+// the parent owns the child and the child owns the parent, neither is freed
+fn parent_child_cycle() {
+    let parent = Rc::new(Node {
+        value: 1,
+        parent: RefCell::new(None),
+        children: RefCell::new(vec![]),
+    });
+    let child = Rc::new(Node {
+        value: 2,
+        parent: RefCell::new(Some(Rc::clone(&parent))),
+        children: RefCell::new(vec![]),
+    });
+    parent.children.borrow_mut().push(Rc::clone(&child));
+    println!("{} {}", parent.value, child.value);
+}
+
+// a node owning itself
+fn self_cycle() {
+    let node = Rc::new(Node {
+        value: 3,
+        parent: RefCell::new(None),
+        children: RefCell::new(vec![]),
+    });
+    *node.parent.borrow_mut() = Some(node.clone());
+}
+
+// the child only refers to the parent weakly, no bug
+fn weak_parent() {
+    let parent = Rc::new(WeakNode {
+        value: 4,
+        parent: RefCell::new(Weak::new()),
+        children: RefCell::new(vec![]),
+    });
+    let child = Rc::new(WeakNode {
+        value: 5,
+        parent: RefCell::new(Weak::new()),
+        children: RefCell::new(vec![]),
+    });
+    *child.parent.borrow_mut() = Rc::downgrade(&parent);
+    parent.children.borrow_mut().push(child.clone());
+    println!("{} {}", parent.value, child.value);
+}
+
+// a chain without a back edge, no bug
+fn chain() {
+    let first = Rc::new(Node {
+        value: 6,
+        parent: RefCell::new(None),
+        children: RefCell::new(vec![]),
+    });
+    let second = Rc::new(Node {
+        value: 7,
+        parent: RefCell::new(Some(first.clone())),
+        children: RefCell::new(vec![]),
+    });
+    let third = Rc::new(Node {
+        value: 8,
+        parent: RefCell::new(None),
+        children: RefCell::new(vec![]),
+    });
+    *third.parent.borrow_mut() = Some(second.clone());
+    println!("{}", first.value + second.value + third.value);
+}
+
+fn main() {
+    parent_child_cycle();
+    self_cycle();
+    weak_parent();
+    chain();
+}
@@ -0,0 +1,22 @@
+// `b` is only dropped on the unwind path of the failed assertion,
+// which is dead under panic=abort, so it never frees the returned box, no bug
+fn check_positive(b: Box<i32>) -> Box<i32> {
+    assert!(*b > 0);
+    b
+}
+
+fn dangling() -> *const i32 {
+    let b = Box::new(1);
+    &*b as *const i32
+}
+
+fn main() {
+    let b = check_positive(Box::new(1));
+    println!("{}", b);
+
+    // the dereference happens inside the `assert_eq!` desugaring, it is reported at the assertion
+    let p = dangling();
+    unsafe {
+        assert_eq!(*p, 1);
+    }
+}
@@ -0,0 +1,9 @@
+// 中文注释：释放之后仍然通过指针读取
+fn main() {
+    let p;
+    {
+        let s = String::from("释放");
+        p = &s as *const String;
+    }
+    let 名字 = "读取"; unsafe { println!("{}{}", 名字, *p); }
+}
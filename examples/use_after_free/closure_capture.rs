@@ -0,0 +1,32 @@
+// the closures are analyzed in the context of their calls, so the pointers they
+// capture to the dropped boxes are dereferenced in their bodies
+fn deref_in_fn() {
+    let p = {
+        let x = Box::new(1);
+        &*x as *const i32
+    };
+    let read = || unsafe {
+        println!("{}", *p);
+    };
+    read();
+}
+
+fn deref_in_fn_once() {
+    let p = {
+        let x = Box::new(1);
+        &*x as *const i32
+    };
+    let b = Box::new(2);
+    let consume = move || {
+        let _b = b;
+        unsafe {
+            println!("{}", *p);
+        }
+    };
+    consume();
+}
+
+fn main() {
+    deref_in_fn();
+    deref_in_fn_once();
+}
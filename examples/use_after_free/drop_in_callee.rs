@@ -0,0 +1,16 @@
+// `b` is dropped at the end, the returned pointer dangles
+fn as_ptr(b: Box<i32>) -> *const i32 {
+    &*b as *const i32
+}
+
+fn new_ptr(value: i32) -> *const i32 {
+    as_ptr(Box::new(value))
+}
+
+// the box is freed inside `as_ptr`, reached through `new_ptr`
+fn main() {
+    let p = new_ptr(1);
+    unsafe {
+        println!("{}", *p);
+    }
+}
@@ -0,0 +1,16 @@
+#![crate_type = "lib"]
+
+#[path = "exclude_path/generated.rs"]
+mod generated;
+
+pub use generated::noisy;
+
+// the vector is dropped here, then read in the generated code
+pub fn read_after_free() -> usize {
+    let p;
+    {
+        let v = vec![1];
+        p = &v as *const Vec<i32>;
+    }
+    generated::read(p)
+}
@@ -0,0 +1,14 @@
+// a use after free entirely in the generated code
+pub fn noisy() -> usize {
+    let p;
+    {
+        let v = vec![1];
+        p = &v as *const Vec<i32>;
+    }
+    unsafe { (*p).len() }
+}
+
+// reads through the pointer given by the caller
+pub fn read(p: *const Vec<i32>) -> usize {
+    unsafe { (*p).len() }
+}
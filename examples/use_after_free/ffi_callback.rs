@@ -0,0 +1,49 @@
+#![crate_type = "lib"]
+use std::os::raw::c_void;
+
+struct State {
+    count: Box<i32>,
+}
+
+extern "C" {
+    fn register_callback(cb: extern "C" fn(*mut c_void), ctx: *mut c_void);
+    fn register_optional_callback(cb: Option<extern "C" fn(*mut c_void)>, ctx: *mut c_void);
+    fn run_once(ctx: *mut c_void);
+}
+
+extern "C" fn on_event(ctx: *mut c_void) {
+    let state = unsafe { &mut *(ctx as *mut State) };
+    *state.count += 1;
+}
+
+// the state is dropped at the end of the fn, but the foreign side still holds the pointer
+pub fn dropped_while_registered() {
+    let mut state = State { count: Box::new(0) };
+    unsafe {
+        register_callback(on_event, &mut state as *mut State as *mut c_void);
+    }
+}
+
+// the same with a nullable callback
+pub fn dropped_while_optional_registered() {
+    let mut state = Box::new(State { count: Box::new(0) });
+    unsafe {
+        register_optional_callback(Some(on_event), &mut *state as *mut State as *mut c_void);
+    }
+}
+
+// the state is leaked to the foreign side, no bug
+pub fn leaked_to_foreign() {
+    let state = Box::new(State { count: Box::new(0) });
+    unsafe {
+        register_callback(on_event, Box::into_raw(state) as *mut c_void);
+    }
+}
+
+// no callback is registered, no bug
+pub fn no_callback() {
+    let mut state = State { count: Box::new(0) };
+    unsafe {
+        run_once(&mut state as *mut State as *mut c_void);
+    }
+}
@@ -0,0 +1,44 @@
+use std::cell::{Ref, RefCell, RefMut};
+use std::mem;
+use std::sync::{Mutex, MutexGuard};
+
+// the guard is transmuted to outlive the cell dropped at the end of the block
+fn escaped_ref() {
+    let guard: Ref<'static, Vec<i32>> = {
+        let cell = RefCell::new(vec![1]);
+        unsafe { mem::transmute(cell.borrow()) }
+    };
+    println!("{}", guard[0]);
+}
+
+// the same with a mutable borrow
+fn escaped_ref_mut() {
+    let mut guard: RefMut<'static, Vec<i32>> = {
+        let cell = RefCell::new(vec![1]);
+        unsafe { mem::transmute(cell.borrow_mut()) }
+    };
+    guard.push(2);
+}
+
+// the guard of a mutex dropped at the end of the block
+fn escaped_mutex_guard() {
+    let guard: MutexGuard<'static, Vec<i32>> = {
+        let mutex = Mutex::new(vec![1]);
+        unsafe { mem::transmute(mutex.lock().unwrap()) }
+    };
+    println!("{}", guard.len());
+}
+
+// the container outlives the guard, no bug
+fn guard_in_scope() {
+    let cell = RefCell::new(vec![1]);
+    let guard = cell.borrow();
+    println!("{}", guard[0]);
+}
+
+fn main() {
+    escaped_ref();
+    escaped_ref_mut();
+    escaped_mutex_guard();
+    guard_in_scope();
+}
@@ -0,0 +1,46 @@
+use std::iter::Map;
+use std::slice::Iter;
+
+struct Item {
+    field: Box<i32>,
+}
+
+// the lifetime of the iterator is extended beyond `items`, which is dropped on return
+fn escaped_map() -> Map<Iter<'static, Item>, fn(&Item) -> &i32> {
+    let items = vec![Item { field: Box::new(1) }];
+    let iter: Iter<'static, Item> = unsafe { std::mem::transmute(items.iter()) };
+    iter.map(|item| &*item.field)
+}
+
+// the yielded references point into the dropped `items`
+fn consume_escaped() {
+    let mut it = escaped_map();
+    if let Some(x) = it.next() {
+        let y = *x;
+        println!("{}", y);
+    }
+}
+
+// the same through a `for` loop
+fn consume_escaped_in_loop() {
+    for x in escaped_map() {
+        let y = *x;
+        println!("{}", y);
+    }
+}
+
+// `items` outlives the iterator, no bug
+fn alive() {
+    let items = vec![Item { field: Box::new(1) }];
+    let mut it = items.iter().map(|item| &*item.field);
+    if let Some(x) = it.next() {
+        let y = *x;
+        println!("{}", y);
+    }
+}
+
+fn main() {
+    consume_escaped();
+    consume_escaped_in_loop();
+    alive();
+}
@@ -0,0 +1,17 @@
+#![crate_type = "lib"]
+
+// the foreign callee has no MIR, so it has no cfg,
+// the pointer is assumed to flow from its args to its return value
+extern "C" {
+    fn forward(ptr: *const i32) -> *const i32;
+}
+
+pub fn read_after_forward() {
+    let ptr = {
+        let value = Box::new(1);
+        unsafe { forward(&*value as *const i32) }
+    };
+    unsafe {
+        println!("{}", *ptr);
+    }
+}
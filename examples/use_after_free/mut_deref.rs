@@ -0,0 +1,23 @@
+#![crate_type = "lib"]
+
+// the vector is written through the pointer after it is dropped at the end of its scope
+pub fn write_after_free() {
+    let p;
+    {
+        let mut v = vec![1];
+        p = &mut v as *mut Vec<i32>;
+    }
+    unsafe {
+        (*p).push(2);
+    }
+}
+
+// the vector is only read through the pointer after it is dropped
+pub fn read_after_free() -> usize {
+    let p;
+    {
+        let v = vec![1];
+        p = &v as *const Vec<i32>;
+    }
+    unsafe { (*p).len() }
+}
@@ -0,0 +1,25 @@
+// each recursive call has three call sites, without bounding the contexts along the cycle the
+// number of contexts grows exponentially with `--context-depth`
+fn even(n: u32, p: *const i32) -> bool {
+    if n == 0 {
+        unsafe { *p == 1 }
+    } else {
+        odd(n - 1, p) || odd(n - 1, p) || odd(n - 1, p)
+    }
+}
+
+fn odd(n: u32, p: *const i32) -> bool {
+    if n == 0 {
+        false
+    } else {
+        even(n - 1, p) || even(n - 1, p) || even(n - 1, p)
+    }
+}
+
+fn main() {
+    let p = {
+        let x = Box::new(1);
+        &*x as *const i32
+    };
+    even(4, p);
+}
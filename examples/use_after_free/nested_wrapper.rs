@@ -0,0 +1,22 @@
+fn id(p: *const i32) -> *const i32 {
+    p
+}
+
+fn wrap(p: *const i32) -> *const i32 {
+    id(p)
+}
+
+// `id` is called at the same call site of `wrap` for both pointers, with `--context-depth=1`
+// the two calls share one context, so `py` is also reported, `--context-depth=2` tells them apart
+fn main() {
+    let y = Box::new(2);
+    let py = wrap(&*y);
+    let px = {
+        let x = Box::new(1);
+        wrap(&*x)
+    };
+    unsafe {
+        println!("{}", *py);
+        println!("{}", *px);
+    }
+}
@@ -0,0 +1,27 @@
+// the pointers computed from the address of a dropped box still point to the box
+fn address_offset() {
+    let p = {
+        let x = Box::new([1, 2]);
+        &*x as *const [i32; 2] as *const i32
+    };
+    let addr = p as usize + 4;
+    unsafe {
+        println!("{}", *(addr as *const i32));
+    }
+}
+
+fn tagged_pointer() {
+    let tagged = {
+        let x = Box::new(1);
+        &*x as *const i32 as usize | 1
+    };
+    let p = (tagged & !1) as *const i32;
+    unsafe {
+        println!("{}", *p);
+    }
+}
+
+fn main() {
+    address_offset();
+    tagged_pointer();
+}
@@ -0,0 +1,31 @@
+use std::rc::Rc;
+
+// the rc keeps the value alive after its clone is dropped
+fn deref_after_clone_dropped() {
+    let a = Rc::new(String::from("shared"));
+    let p;
+    {
+        let b = a.clone();
+        p = &*b as *const String;
+    }
+    unsafe {
+        println!("{}", *p);
+    }
+}
+
+fn deref_after_all_dropped() {
+    let p;
+    {
+        let a = Rc::new(String::from("shared"));
+        let b = Rc::clone(&a);
+        p = &*b as *const String;
+    }
+    unsafe {
+        println!("{}", *p);
+    }
+}
+
+fn main() {
+    deref_after_clone_dropped();
+    deref_after_all_dropped();
+}
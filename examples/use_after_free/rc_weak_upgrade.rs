@@ -0,0 +1,37 @@
+use std::rc::{Rc, Weak};
+
+// the upgrade is checked before use, no bug
+fn checked() {
+    let weak: Weak<i32> = {
+        let rc = Rc::new(1);
+        Rc::downgrade(&rc)
+    };
+    let upgraded = weak.upgrade();
+    if let Some(v) = &upgraded {
+        println!("{}", **v);
+    }
+}
+
+// the strong rc is dropped at the end of the block, but the upgrade is assumed to succeed
+fn unchecked() {
+    let weak = {
+        let rc = Rc::new(1);
+        Rc::downgrade(&rc)
+    };
+    let v = unsafe { weak.upgrade().unwrap_unchecked() };
+    println!("{}", *v);
+}
+
+// the strong rc is still alive, no bug
+fn alive() {
+    let rc = Rc::new(1);
+    let weak = Rc::downgrade(&rc);
+    let v = unsafe { weak.upgrade().unwrap_unchecked() };
+    println!("{} {}", *v, *rc);
+}
+
+fn main() {
+    checked();
+    unchecked();
+    alive();
+}
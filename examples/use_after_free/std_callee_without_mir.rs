@@ -0,0 +1,12 @@
+// `process::id` is selected by `--analyze-std=process::id`, but std is built without its MIR,
+// so it has no cfg, its call is skipped and the rest of `main` is still analyzed
+fn main() {
+    let p = {
+        let b = Box::new(1);
+        &*b as *const i32
+    };
+    let id = std::process::id();
+    unsafe {
+        println!("{} {}", id, *p);
+    }
+}
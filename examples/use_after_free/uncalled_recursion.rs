@@ -0,0 +1,23 @@
+#![crate_type = "lib"]
+#![allow(dead_code)]
+
+// `ping` and `pong` call each other but nothing else calls them, the cycle still gets an entry
+fn ping(n: u32) -> i32 {
+    let p = {
+        let x = Box::new(1);
+        &*x as *const i32
+    };
+    if n == 0 {
+        unsafe { *p }
+    } else {
+        pong(n - 1)
+    }
+}
+
+fn pong(n: u32) -> i32 {
+    if n == 0 {
+        0
+    } else {
+        ping(n - 1)
+    }
+}
@@ -0,0 +1,82 @@
+#![crate_type = "lib"]
+
+fn consume(s: String) -> usize {
+    s.len()
+}
+
+// the string is moved into `t`, but read again through the pointer taken before
+pub fn read_through_pointer_after_move() {
+    let s = String::from("moved");
+    let p = &s as *const String;
+    let t = s;
+    unsafe {
+        println!("{}", *p);
+    }
+    drop(t);
+}
+
+// the same when the string is moved into a call
+pub fn read_after_move_into_call() -> usize {
+    let s = String::from("moved");
+    let p = &s as *const String;
+    let len = consume(s);
+    len + unsafe { (*p).len() }
+}
+
+// reassigned before the read, the pointer sees the new string
+pub fn reinit_before_read() -> usize {
+    let mut s = String::from("first");
+    let p = &mut s as *mut String;
+    std::mem::forget(s);
+    s = String::from("second");
+    let len = unsafe { (*p).len() };
+    len + s.len()
+}
+
+// moved on both branches, the read is reached from each move
+pub fn moved_on_both_branches(flag: bool) {
+    let s = String::from("maybe moved");
+    let p = &s as *const String;
+    if flag {
+        consume(s);
+    } else {
+        drop(s);
+    }
+    unsafe {
+        println!("{}", (*p).len());
+    }
+}
+
+// a fresh string in each iteration is moved at its end, the pointer of the next one is read
+pub fn moved_in_loop() -> usize {
+    let mut total = 0;
+    for i in 0..2 {
+        let s = i.to_string();
+        let p = &s as *const String;
+        total += unsafe { (*p).len() };
+        std::mem::forget(s);
+    }
+    total
+}
+
+// the box is moved, but its heap value is still owned by the new box
+pub fn read_heap_after_box_move() -> i32 {
+    let b = Box::new(1);
+    let p = &*b as *const i32;
+    let c = b;
+    let value = unsafe { *p };
+    drop(c);
+    value
+}
+
+// the string is moved from `s` to `t`, then from `t` to `u`, the pointer taken from `t` is read
+pub fn read_after_move_chain() {
+    let s = String::from("moved");
+    let t = s;
+    let p = &t as *const String;
+    let u = t;
+    unsafe {
+        println!("{}", *p);
+    }
+    drop(u);
+}
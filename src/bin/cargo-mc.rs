@@ -1,14 +1,62 @@
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
 use serde_json;
 use std::ffi::OsString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
 
 const CARGO_MEMORY_CHECK_HELP: &str = r#" rust memory check for UAF(use after free), DP(dangling pointer), DF(double free)
 
 Usage:
-    cargo mc
+    cargo mc [--watch] [--crate-filter=GLOB[,GLOB...]] [--include-dependencies] [--tests] [--examples] [--benches] [CARGO_ARGS...] [-- MC_ARGS...]
+
+Options:
+    -p, --package GLOB      analyze the workspace members whose name matches the glob,
+                            can be given multiple times
+    --workspace             analyze all workspace members, the default in the root of
+                            a virtual workspace
+    --exclude GLOB          skip the workspace members whose name matches the glob
+    --tests                 also analyze the integration tests of the packages
+    --examples              also analyze the examples of the packages
+    --benches               also analyze the benchmarks of the packages
+    --all-targets           analyze all of the above besides the binaries and the library,
+                            which are the only targets analyzed by default
+    --watch                 re-run the analysis whenever a source file of the crate changes
+    --crate-filter=GLOB     also analyze the dependencies whose crate name matches the glob,
+                            `*` matches any characters and `?` matches one character
+    --include-dependencies  also analyze the path dependencies, eg. the other workspace
+                            members, the dependencies from crates.io or git are only compiled
+    --message-format=json   emit the findings as cargo `compiler-message` json lines,
+                            other `json*` formats of cargo are supported too
+
+Exit status:
+    0    no findings
+    1    tool or usage error, including compilation errors of the crate
+    2    findings reported
+    3    the analysis timed out (`--timeout`) before reporting any finding
 "#;
 
+// Exit codes, the same as `mc`
+const EXIT_ERROR: i32 = 1;
+const EXIT_FINDINGS: i32 = 2;
+const EXIT_ABORTED: i32 = 3;
+
+// The result of `mc` over all the analyzed crates
+enum McResult {
+    Clean,
+    Findings,
+    TimedOut,
+}
+
+// Flags selecting the target kinds analyzed besides `bin` and `lib`, with the kind they select
+const TARGET_KIND_FLAGS: [(&str, &str); 3] = [("--tests", "test"), ("--examples", "example"), ("--benches", "bench")];
+
+// Changes in a short period are merged into one re-run
+const WATCH_DEBOUNCE_MS: u64 = 500;
+
 fn show_help() {
     println!("{}", CARGO_MEMORY_CHECK_HELP);
 }
@@ -55,51 +103,90 @@ fn get_arg_flag_value(name: &str) -> Option<String> {
     }
 }
 
-// Get the top level crate that we need to analyze
-fn current_crate() -> cargo_metadata::Package {
-    // We need to get the manifest, and then the metadata, to enumerate targets.
+// The output format given to `mc`, after `--` too, `--output-format` wins over its alias `--format` like in `mc`
+fn mc_output_format() -> Option<String> {
+    let value = |name: &str| {
+        let prefix = format!("{}=", name);
+        std::env::args().find_map(|arg| arg.strip_prefix(&prefix).map(str::to_owned))
+    };
+    value("--output-format").or_else(|| value("--format"))
+}
+
+// Gets the values of a `name` given multiple times.
+// For example, get_arg_flag_values("--package") of `-p a --package b --package=c` is `["b", "c"]`
+fn get_arg_flag_values(name: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut args = std::env::args().take_while(|val| val != "--");
+    while let Some(arg) = args.next() {
+        if arg == name {
+            values.extend(args.next());
+        } else if let Some(value) = arg.strip_prefix(name).and_then(|suffix| suffix.strip_prefix('=')) {
+            values.push(value.to_owned());
+        }
+    }
+    values
+}
 
-    // Path to the `Cargo.toml` file
-    let manifest_path =
-        get_arg_flag_value("--manifest-path").map(|m| Path::new(&m).canonicalize().unwrap());
+fn manifest_path() -> Option<PathBuf> {
+    get_arg_flag_value("--manifest-path").map(|m| Path::new(&m).canonicalize().unwrap())
+}
 
+fn cargo_metadata() -> cargo_metadata::Metadata {
     let mut cmd = cargo_metadata::MetadataCommand::new();
-    if let Some(ref manifest_path) = manifest_path {
+    if let Some(manifest_path) = manifest_path() {
         cmd.manifest_path(manifest_path);
     }
-    let mut metadata = if let Ok(metadata) = cmd.exec() {
-        metadata
-    } else {
-        show_error("Could not obtain Cargo metadata; likely an ill-formed manifest".to_string());
+    cmd.exec().unwrap_or_else(|_| {
+        show_error("Could not obtain Cargo metadata; likely an ill-formed manifest".to_string())
+    })
+}
+
+// Get the workspace members that we need to analyze, selected as cargo does:
+// the members of `-p`/`--package`, all members with `--workspace` or in the root of a virtual workspace,
+// otherwise the package of the manifest (or of the current directory), except the members of `--exclude`
+fn selected_packages(metadata: cargo_metadata::Metadata) -> Vec<cargo_metadata::Package> {
+    let manifest_path = manifest_path();
+    let current_dir = std::env::current_dir().expect("could not read current directory");
+    let is_current = |package: &cargo_metadata::Package| match manifest_path {
+        Some(ref manifest_path) => package.manifest_path == *manifest_path,
+        None => package.manifest_path.parent() == Some(current_dir.as_path()),
     };
 
-    let current_dir = std::env::current_dir();
+    let workspace_members = metadata.workspace_members;
+    let mut packages = metadata
+        .packages
+        .into_iter()
+        .filter(|package| workspace_members.contains(&package.id))
+        .collect::<Vec<_>>();
+
+    let package_globs = [get_arg_flag_values("-p"), get_arg_flag_values("--package")].concat();
+    if !has_arg_flag("--workspace") && !has_arg_flag("--all") {
+        if !package_globs.is_empty() {
+            packages.retain(|package| package_globs.iter().any(|glob| glob_match(glob, &package.name)));
+        } else if packages.iter().any(is_current) {
+            packages.retain(is_current);
+        }
+    }
+    let exclude_globs = get_arg_flag_values("--exclude");
+    packages.retain(|package| !exclude_globs.iter().any(|glob| glob_match(glob, &package.name)));
 
-    let package_index = metadata
+    if packages.is_empty() {
+        show_error("no workspace member is selected to analyze".to_string());
+    }
+    packages
+}
+
+// Get the crate names of the libraries of the path packages, those without a source, eg. workspace members.
+// The dependencies from a registry or git have a source and are left out
+fn local_crate_names(metadata: &cargo_metadata::Metadata) -> Vec<String> {
+    metadata
         .packages
         .iter()
-        .position(|package| {
-            let package_manifest_path = Path::new(&package.manifest_path);
-            if let Some(ref manifest_path) = manifest_path {
-                package_manifest_path == manifest_path
-            } else {
-                let current_dir = current_dir
-                    .as_ref()
-                    .expect("could not read current directory");
-                let package_manifest_directory = package_manifest_path
-                    .parent()
-                    .expect("could not find parent directory of package manifest");
-                package_manifest_directory == current_dir
-            }
-        })
-        .unwrap_or_else(|| {
-            show_error(
-                "This seems to be a workspace, which is not supported by cargo-mc".to_string(),
-            )
-        });
-    let package = metadata.packages.remove(package_index);
-
-    package
+        .filter(|package| package.source.is_none())
+        .flat_map(|package| package.targets.iter())
+        .filter(|target| target.kind.iter().any(|kind| kind.ends_with("lib")))
+        .map(|target| target.name.replace("-", "_"))
+        .collect()
 }
 
 fn mc() -> Command {
@@ -141,39 +228,169 @@ fn main() {
     }
 }
 
+fn in_cargo_mc() {
+    if has_arg_flag("--watch") {
+        watch_cargo_mc();
+    } else {
+        match run_cargo_mc() {
+            Ok(McResult::Clean) => {}
+            Ok(McResult::Findings) => std::process::exit(EXIT_FINDINGS),
+            Ok(McResult::TimedOut) => std::process::exit(EXIT_ABORTED),
+            // the failure has been reported by cargo
+            Err(_) => std::process::exit(EXIT_ERROR),
+        }
+    }
+}
+
+// Runs the analysis, then waits for changes of the `.rs` files (or `Cargo.toml`)
+// under the crate directory and runs it again, until Ctrl-C is pressed.
+fn watch_cargo_mc() {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_interrupted = interrupted.clone();
+    ctrlc::set_handler(move || handler_interrupted.store(true, Ordering::SeqCst))
+        .unwrap_or_else(|e| show_error(format!("failed to set Ctrl-C handler: {}", e)));
+
+    let metadata = cargo_metadata();
+    let target_dir = metadata.target_directory.clone();
+    let crate_dirs = selected_packages(metadata)
+        .iter()
+        .map(|package| {
+            package
+                .manifest_path
+                .parent()
+                .expect("could not find parent directory of package manifest")
+                .to_path_buf()
+        })
+        .collect::<Vec<_>>();
+
+    let (tx, rx) = channel();
+    let mut watcher = notify::watcher(tx, Duration::from_millis(WATCH_DEBOUNCE_MS))
+        .unwrap_or_else(|e| show_error(format!("failed to create file watcher: {}", e)));
+    for crate_dir in crate_dirs.iter() {
+        watcher
+            .watch(crate_dir, RecursiveMode::Recursive)
+            .unwrap_or_else(|e| show_error(format!("failed to watch {}: {}", crate_dir.display(), e)));
+    }
+    let watched_dirs = crate_dirs.iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>();
+
+    let is_source_change = |event: &DebouncedEvent| {
+        let paths = match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Remove(path) => vec![path],
+            DebouncedEvent::Rename(from, to) => vec![from, to],
+            _ => vec![],
+        };
+        paths.into_iter().any(|path| {
+            !path.starts_with(&target_dir)
+                && (path.extension().map_or(false, |ext| ext == "rs")
+                    || path.file_name().map_or(false, |name| name == "Cargo.toml"))
+        })
+    };
+
+    // no escape sequence at all in plain text
+    let plain_text = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty())
+        || mc_output_format().as_deref() == Some("text-no-color")
+        || std::env::args().any(|arg| arg == "--no-color=1" || arg.starts_with("--message-format=json"));
+
+    loop {
+        // clear the terminal, then print findings of this run
+        if !plain_text {
+            print!("\x1B[2J\x1B[1;1H");
+        }
+        // the failure has been reported by cargo, keep watching
+        let _ = run_cargo_mc();
+        eprintln!(
+            "[cargo-mc] watching {} for changes, press Ctrl-C to stop",
+            watched_dirs.join(", ")
+        );
+
+        loop {
+            if interrupted.load(Ordering::SeqCst) {
+                eprintln!("[cargo-mc] stop watching");
+                return;
+            }
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) if is_source_change(&event) => break,
+                Ok(_) | Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => {
+                    show_error("file watcher disconnected".to_string())
+                }
+            }
+        }
+    }
+}
+
 // This will construct command line like:
-// `cargo rustc --bin some_crate_name -v -- cargo-mc-marker-begin --top_crate_name some_top_crate_name --domain interval -v cargo-mc-marker-end`
+// `cargo check -p some_package --lib --bin some_bin -v`, one command for all targets of each selected package
 // And set the following environment variables:
 // `RUSTC_WRAPPER` is set to `cargo-mc` itself so the execution will come back to the second branch as described above
 // `MEMORY_CHECK_ARGS` is set to the user-provided arguments for `mc`
-// `MEMORY_CHECK_TOP_CRATE_NAME` is set to the name of the crate being analyzed
+// `MEMORY_CHECK_TOP_CRATE_NAME` is set to the crate names of the targets being analyzed, separated by commas,
+// the targets of each selected workspace member in turn
+// `MEMORY_CHECK_CRATE_FILTER` is set to the globs of the dependencies analyzed besides the top crate
+// `MEMORY_CHECK_LOCAL_CRATES` is set to the crate names of the path dependencies if `--include-dependencies` is provided
 // `MEMORY_CHECK_VERBOSE` is set if `-v` is provided
-fn in_cargo_mc() {
+// `MEMORY_CHECK_FINDINGS_MARKER` is set to a file created by `inside_cargo_rustc` if `mc` reports findings
+// `MEMORY_CHECK_TIMED_OUT_MARKER` is set to a file created by `inside_cargo_rustc` if `mc` times out without findings
+// Returns the merged result of the crates, or the exit code of the failed `cargo check` if there is one.
+fn run_cargo_mc() -> Result<McResult, i32> {
     let verbose = has_arg_flag("-v");
 
-    let current_crate = current_crate();
+    let findings_marker =
+        std::env::temp_dir().join(format!("cargo-mc-findings-{}", std::process::id()));
+    let _ = std::fs::remove_file(&findings_marker);
+    let timed_out_marker =
+        std::env::temp_dir().join(format!("cargo-mc-timed-out-{}", std::process::id()));
+    let _ = std::fs::remove_file(&timed_out_marker);
+
+    let selected_kind = |kind: &str| {
+        TARGET_KIND_FLAGS
+            .iter()
+            .any(|(flag, flag_kind)| *flag_kind == kind && (has_arg_flag(flag) || has_arg_flag("--all-targets")))
+    };
 
-    // Now run the command.
-    for target in current_crate.targets.into_iter() {
+    let metadata = cargo_metadata();
+    let local_crates = if has_arg_flag("--include-dependencies") {
+        Some(local_crate_names(&metadata).join(","))
+    } else {
+        None
+    };
+
+    // Now run the command for each package, all of its targets are checked at once
+    // so that the shared dependencies are compiled only once.
+    for package in selected_packages(metadata).into_iter() {
         let mut args = std::env::args().skip(2);
-        let kind = target
-            .kind
-            .get(0)
-            .expect("badly formatted cargo metadata: target::kind is an empty array");
 
         // Now we run `cargo rustc $FLAGS $ARGS`, giving the user the
         // chance to add additional arguments. `FLAGS` is set to identify
-        // this target.  The user gets to control what gets actually passed to mc.
+        // the targets.  The user gets to control what gets actually passed to mc.
         let mut cmd = cargo();
         cmd.arg("check"); // using `check` may speed up the analysis than using `rustc`
-        match kind.as_str() {
-            "bin" => {
-                cmd.arg("--bin").arg(target.name);
+        cmd.arg("-p").arg(&package.name);
+        // the crate of a target is named after the target, eg. a bin named differently from its package
+        let mut top_crate_names = vec![];
+        for target in package.targets.iter() {
+            let kind = target
+                .kind
+                .get(0)
+                .expect("badly formatted cargo metadata: target::kind is an empty array");
+            match kind.as_str() {
+                "bin" => {
+                    cmd.arg("--bin").arg(&target.name);
+                }
+                "lib" => {
+                    cmd.arg("--lib");
+                }
+                "test" | "example" | "bench" if selected_kind(kind) => {
+                    cmd.arg(format!("--{}", kind)).arg(&target.name);
+                }
+                _ => continue,
             }
-            "lib" => {
-                cmd.arg("--lib");
-            }
-            _ => continue,
+            top_crate_names.push(target.name.clone());
+        }
+        if top_crate_names.is_empty() {
+            continue;
         }
 
         // Add cargo args until first `--`.
@@ -181,6 +398,22 @@ fn in_cargo_mc() {
             if arg == "--" {
                 break;
             }
+            // handled by `cargo-mc` itself, the package is selected by `-p` above
+            // and the target is selected by the target flag above
+            if arg == "--watch"
+                || arg == "--all-targets"
+                || TARGET_KIND_FLAGS.iter().any(|(flag, _)| arg == *flag)
+                || arg == "--workspace"
+                || arg == "--all"
+                || arg == "--include-dependencies"
+                || ["--crate-filter=", "--package=", "--exclude="].iter().any(|flag| arg.starts_with(flag))
+            {
+                continue;
+            }
+            if ["--crate-filter", "-p", "--package", "--exclude"].contains(&arg.as_str()) {
+                args.next();
+                continue;
+            }
             cmd.arg(arg);
         }
 
@@ -190,14 +423,22 @@ fn in_cargo_mc() {
         // Since we're using "cargo check", we have no other way of passing
         // these arguments.
         // We also add `MEMORY_CHECK_TOP_CRATE_NAME` to specify the top-level
-        // crate name that we want to analyze, by doing this we can dispatch
-        // dependencies to the real `rustc` and top-level crate to `mc`
+        // crate names that we want to analyze, by doing this we can dispatch
+        // dependencies to the real `rustc` and top-level crates to `mc`
         let args_vec: Vec<String> = args.collect();
         cmd.env(
             "MEMORY_CHECK_ARGS",
             serde_json::to_string(&args_vec).expect("failed to serialize args"),
         );
-        cmd.env("MEMORY_CHECK_TOP_CRATE_NAME", current_crate.name.clone());
+        cmd.env("MEMORY_CHECK_TOP_CRATE_NAME", top_crate_names.join(","));
+        cmd.env("MEMORY_CHECK_FINDINGS_MARKER", &findings_marker);
+        cmd.env("MEMORY_CHECK_TIMED_OUT_MARKER", &timed_out_marker);
+        if let Some(crate_filter) = get_arg_flag_value("--crate-filter") {
+            cmd.env("MEMORY_CHECK_CRATE_FILTER", crate_filter);
+        }
+        if let Some(ref local_crates) = local_crates {
+            cmd.env("MEMORY_CHECK_LOCAL_CRATES", local_crates);
+        }
 
         // Replace the rustc executable through RUSTC_WRAPPER environment variable
         let path = std::env::current_exe().expect("current executable path invalid");
@@ -216,15 +457,31 @@ fn in_cargo_mc() {
             .expect("failed to wait for cargo?");
 
         if !exit_status.success() {
-            std::process::exit(exit_status.code().unwrap_or(-1))
+            let _ = std::fs::remove_file(&findings_marker);
+            let _ = std::fs::remove_file(&timed_out_marker);
+            return Err(exit_status.code().unwrap_or(-1));
         }
     }
+
+    // the findings of one crate outweigh the timeout of another
+    let result = if findings_marker.exists() {
+        McResult::Findings
+    } else if timed_out_marker.exists() {
+        McResult::TimedOut
+    } else {
+        McResult::Clean
+    };
+    let _ = std::fs::remove_file(&findings_marker);
+    let _ = std::fs::remove_file(&timed_out_marker);
+    Ok(result)
 }
 
 // This will construct command line like:
 // `mc --crate-name some_crate_name --edition=2018 src/lib.rs --crate-type lib --domain interval`
 // And sets the environment variable `MEMORY_CHECK_BE_RUSTC`
-// if `mc` is going to analyze crates that are dependencies
+// if `mc` is going to compile crates that are dependencies.
+// The top crate is always analyzed, the crates matching `MEMORY_CHECK_CRATE_FILTER` or listed in
+// `MEMORY_CHECK_LOCAL_CRATES` are analyzed too.
 fn inside_cargo_rustc() {
     let mut cmd = mc();
     cmd.args(std::env::args().skip(2)); // skip `cargo-mc rustc`
@@ -234,11 +491,27 @@ fn inside_cargo_rustc() {
     cmd.arg("--sysroot");
     cmd.arg(sysroot);
 
-    let top_crate_name =
+    let top_crate_names =
         std::env::var("MEMORY_CHECK_TOP_CRATE_NAME").expect("missing MEMORY_CHECK_TOP_CRATE_NAME");
-    let top_crate_name = top_crate_name.replace("-", "_"); // Cargo seems to rename hyphens to underscores
+    let top_crate_names = top_crate_names.replace("-", "_"); // Cargo seems to rename hyphens to underscores
 
-    if get_arg_flag_value("--crate-name").as_deref() == Some(&top_crate_name) {
+    let crate_name = get_arg_flag_value("--crate-name");
+    let is_top_crate = match &crate_name {
+        Some(crate_name) => top_crate_names.split(',').any(|name| name == crate_name),
+        None => false,
+    };
+    let in_crate_filter = match (std::env::var("MEMORY_CHECK_CRATE_FILTER"), &crate_name) {
+        (Ok(crate_filter), Some(crate_name)) => crate_filter
+            .split(',')
+            .any(|pattern| glob_match(&pattern.replace("-", "_"), crate_name)),
+        _ => false,
+    };
+    let is_local_crate = match (std::env::var("MEMORY_CHECK_LOCAL_CRATES"), &crate_name) {
+        (Ok(local_crates), Some(crate_name)) => local_crates.split(',').any(|name| name == crate_name),
+        _ => false,
+    };
+
+    if is_top_crate || in_crate_filter || is_local_crate {
         // If we are analyzing the crate that we want to analyze, add args for `mc`
         let magic = std::env::var("MEMORY_CHECK_ARGS").expect("missing MEMORY_CHECK_ARGS");
         let mc_args: Vec<String> =
@@ -258,7 +531,16 @@ fn inside_cargo_rustc() {
 
     match cmd.status() {
         Ok(exit) => {
-            if !exit.success() {
+            // findings are not a compilation failure for cargo, leave the marker instead
+            if exit.code() == Some(EXIT_FINDINGS) {
+                if let Some(findings_marker) = std::env::var_os("MEMORY_CHECK_FINDINGS_MARKER") {
+                    let _ = std::fs::File::create(findings_marker);
+                }
+            } else if exit.code() == Some(EXIT_ABORTED) {
+                if let Some(timed_out_marker) = std::env::var_os("MEMORY_CHECK_TIMED_OUT_MARKER") {
+                    let _ = std::fs::File::create(timed_out_marker);
+                }
+            } else if !exit.success() {
                 std::process::exit(exit.code().unwrap_or(42));
             }
         }
@@ -266,6 +548,15 @@ fn inside_cargo_rustc() {
     }
 }
 
+// Matches `name` against a glob `pattern` as cargo does for `-p`/`--exclude`, eg. `*`, `?` and `[abc]`,
+// an invalid pattern only matches the same name
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match globset::Glob::new(pattern) {
+        Ok(glob) => glob.compile_matcher().is_match(name),
+        Err(_) => pattern == name,
+    }
+}
+
 fn compile_time_sysroot() -> Option<String> {
     if option_env!("RUST_STAGE").is_some() {
         return None;
@@ -280,4 +571,22 @@ fn compile_time_sysroot() -> Option<String> {
             )
             .to_owned(),
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("mc-*", "mc-core"));
+        assert!(glob_match("mc-?ore", "mc-core"));
+        assert!(glob_match("mc-[cd]ore", "mc-dore"));
+        assert!(glob_match("core", "core"));
+        assert!(!glob_match("mc-*", "core"));
+        assert!(!glob_match("core", "mc-core"));
+        // an unclosed class is not a valid glob
+        assert!(glob_match("[core", "[core"));
+        assert!(!glob_match("[core", "core"));
+    }
+}
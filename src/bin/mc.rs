@@ -1,3 +1,84 @@
+use rust_memory_check::{AnalysisError, AnalysisOutcome};
+use std::io::Read;
+
 fn main() {
-    std::process::exit(rust_memory_check::analysis_then_check().is_err() as i32);
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).map(|arg| arg.as_str()) == Some("explain-finding") {
+        std::process::exit(explain_finding(args.get(2)) as i32);
+    }
+    if let Some(i) = args.iter().position(|arg| arg == "--lsp") {
+        let mut args = args;
+        args.remove(i);
+        std::process::exit(lsp(args));
+    }
+    std::process::exit(exit_code(std::panic::catch_unwind(
+        rust_memory_check::analysis_then_check,
+    )));
+}
+
+// a panic is a tool error too, the message is printed by the panic hook
+fn exit_code(
+    result: std::thread::Result<Result<AnalysisOutcome, AnalysisError>>,
+) -> i32 {
+    match result {
+        Ok(Ok(outcome)) => outcome.exit_code(),
+        // the compile errors are already reported by rustc
+        Ok(Err(AnalysisError::CompileError)) | Err(_) => rust_memory_check::EXIT_ERROR,
+        // the timeout is already warned about with the results
+        Ok(Err(AnalysisError::TimedOut)) => rust_memory_check::EXIT_ABORTED,
+        Ok(Err(err)) => {
+            eprintln!("fatal error: {}", err);
+            err.exit_code()
+        }
+    }
+}
+
+// `mc explain-finding FINDING_JSON_PATH`, reads stdin without a path or with `-`
+fn explain_finding(path: Option<&String>) -> bool {
+    let json = match path.map(|path| path.as_str()) {
+        None | Some("-") => {
+            let mut json = String::new();
+            std::io::stdin().read_to_string(&mut json).map(|_| json)
+        }
+        Some(path) => std::fs::read_to_string(path),
+    };
+    let result = json
+        .map_err(|e| format!("cannot read the finding: {}", e))
+        .and_then(|json| rust_memory_check::core::check::explain_finding(&json));
+    if let Err(err) = &result {
+        eprintln!("fatal error: {}", err);
+    }
+    result.is_err()
+}
+
+// `mc --lsp [OPTIONS]`, analyzes the file of the lsp request read from stdin and prints the
+// `textDocument/publishDiagnostics` notifications to stdout
+fn lsp(mut args: Vec<String>) -> i32 {
+    let mut request = String::new();
+    let request = std::io::stdin()
+        .read_to_string(&mut request)
+        .map_err(|e| format!("cannot read the lsp request: {}", e))
+        .and_then(|_| rust_memory_check::core::utils::parse_lsp_request(&request));
+    let (root, file) = match request {
+        Ok(request) => request,
+        Err(err) => {
+            eprintln!("fatal error: {}", err);
+            return rust_memory_check::EXIT_ERROR;
+        }
+    };
+    // the relative paths of the spans are resolved against the workspace root
+    if let Some(root) = root {
+        if let Err(e) = std::env::set_current_dir(&root) {
+            eprintln!("fatal error: cannot enter {}: {}", root.display(), e);
+            return rust_memory_check::EXIT_ERROR;
+        }
+    }
+
+    // the notifications are the only output the editor reads
+    args.retain(|arg| !arg.starts_with("--output-format=") && !arg.starts_with("--format="));
+    args.insert(1, file.to_string_lossy().into_owned());
+    args.push("--output-format=lsp".to_owned());
+    exit_code(std::panic::catch_unwind(|| {
+        rust_memory_check::analysis_then_check_with_args(args, |options| options)
+    }))
 }
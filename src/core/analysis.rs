@@ -1,7 +1,8 @@
 use super::pfg::{DerefEdgeInfo, PointerFlowGraph};
 use super::{
-    cfg, AnalysisOptions, CallerContext, CtxtSenCallId, CtxtSenSpanInfo, DropObjectId,
-    GlobalBasicBlockId, GlobalProjectionId, LocalId, RvalKind,
+    cfg, AnalysisOptions, BasicBlockId, BasicBlockInfo, CallerContext, CtxtSenCallId, CtxtSenSpanInfo,
+    DropObjectId, GlobalBasicBlockId, GlobalProjectionId, LocalId, ModelKind, ObjectGranularity,
+    RvalKind,
 };
 use crate::core::cfg::ControlFlowGraph;
 use crate::core::utils;
@@ -9,15 +10,21 @@ use crate::core::CallInfo;
 use crate::core::OpKind;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::Operand;
+use rustc_middle::ty::subst::GenericArgKind;
 use rustc_middle::mir::TerminatorKind;
-use rustc_middle::mir::{Place, PlaceElem};
+use rustc_middle::mir::{Place, PlaceElem, RETURN_PLACE};
 use std::collections::VecDeque;
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 pub fn alias_analysis(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext {
+    let entry_def_id = entry.def_id;
     let mut ctxt = process_calls(ctxt, entry);
 
     while !ctxt.worklist.is_empty() {
+        if ctxt.is_timed_out() {
+            break;
+        }
         let pts = ctxt.worklist.pop_front().unwrap();
         let proj_node = ctxt.pfg.get_projection_node(pts.g_proj_id);
         let ptn = &proj_node.points_to;
@@ -53,6 +60,19 @@ pub fn alias_analysis(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisCo
             .debug_paths(("main".to_owned(), LocalId::from_usize(1), 0));
     }
 
+    // the file is truncated before the first entry, each entry is one digraph
+    if let Some(path) = utils::dump_pfg_path(&ctxt.options) {
+        let dot = format!("// entry: {}\n{}", utils::parse_def_id(entry_def_id).join("::"), ctxt.pfg.to_dot());
+        let result = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| std::io::Write::write_all(&mut file, dot.as_bytes()));
+        if let Err(err) = result {
+            eprintln!("warning: failed to dump the pfg to `{}`: {}", path, err);
+        }
+    }
+
     ctxt
 }
 
@@ -64,6 +84,21 @@ pub struct AnalysisContext<'tcx> {
     pub pfg: PointerFlowGraph<'tcx>,
     pub cs_reachable_calls: HashSet<CtxtSenCallId>,
     pub worklist: VecDeque<PointsTo>,
+    // under `--timeout`, shared by all entries
+    pub deadline: Option<Instant>,
+    pub timed_out: bool,
+    // under `--profile`, the time to create the cfg of each fn
+    pub cfg_times: HashMap<DefId, Duration>,
+}
+
+impl<'tcx> AnalysisContext<'tcx> {
+    // the analysis stops at the deadline, the points-to sets computed so far are still checked
+    fn is_timed_out(&mut self) -> bool {
+        if !self.timed_out && self.deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+            self.timed_out = true;
+        }
+        self.timed_out
+    }
 }
 
 #[derive(Debug)]
@@ -87,32 +122,72 @@ fn add_reachable(ctxt: AnalysisContext, call_id: CtxtSenCallId) -> AnalysisConte
     } else {
         let mut ctxt = ctxt;
 
-        let cfg = ctxt.cfgs.get(&call_id.def_id).unwrap();
+        let cfg = match ctxt.cfgs.get(&call_id.def_id) {
+            Some(cfg) => cfg,
+            // eg. a fn without optimized MIR, the rest of the crate is still analyzed
+            None => {
+                log::debug!("no cfg for reachable call: {:?}", call_id.def_id);
+                return ctxt;
+            }
+        };
 
         ctxt.cs_reachable_calls.insert(call_id.clone());
 
-        for (bb_id, bb_info) in cfg.basic_block_infos.iter() {
-            // handle drop object
-            if let TerminatorKind::Drop { ref place, .. } = bb_info.terminator.kind {
-                let span = bb_info.terminator.source_info.span;
-
-                let cs_drop_span = CtxtSenSpanInfo::new(
-                    call_id.def_id,
-                    *bb_id,
-                    span,
-                    call_id.caller_context.clone(),
-                );
-
-                let g_proj_id = ctxt
-                    .pfg
-                    .add_or_update_node(&call_id, place, Some(cs_drop_span));
+        let consumed_locals = get_consumed_locals(cfg);
+        // in the order of the blocks, the first drop of a type under `--object-granularity=type` is the same each run
+        let mut bb_infos = cfg.basic_block_infos.iter().collect::<Vec<_>>();
+        bb_infos.sort_by_key(|(bb_id, _)| **bb_id);
+        for (bb_id, bb_info) in bb_infos {
+            // handle drop object, the drops after a failed assertion never run under panic=abort,
+            // `DropAndReplace` drops the old value too, the new one is one of the assignments below
+            if let TerminatorKind::Drop { ref place, .. } | TerminatorKind::DropAndReplace { ref place, .. } =
+                bb_info.terminator.kind
+            {
+                if cfg.assert_failure_bbs.contains(bb_id) {
+                    log::debug!("ignored drop after failed assertion: {:?}", bb_info.terminator);
+                } else {
+                    if let Some(consume_bb_id) = consumed_locals.get(&place.local).filter(|_| bb_info.is_cleanup) {
+                        ctxt.pfg.consumed_drops.insert(
+                            GlobalBasicBlockId::new(call_id.def_id, *bb_id),
+                            GlobalBasicBlockId::new(call_id.def_id, *consume_bb_id),
+                        );
+                    }
+                    let drop_obj_id = add_drop_object(
+                        &mut ctxt.pfg,
+                        &mut ctxt.worklist,
+                        &call_id,
+                        *bb_id,
+                        place,
+                        bb_info.terminator.source_info.span,
+                        get_object_ty(&ctxt.options, ctxt.tcx, call_id.def_id, place),
+                    );
+                    if utils::is_rc_handle_ty(utils::get_ty_from_place(ctxt.tcx, call_id.def_id, place)) {
+                        ctxt.pfg.rc_handle_objects.insert(drop_obj_id);
+                    }
+                }
+            }
 
-                // we assume that all drops of this place **in this context** refer to the same object, so we only add <c: x, {c: oi}> to WL once
-                if ctxt.pfg.get_projection_node(g_proj_id).cs_drop_spans.len() == 1 {
-                    let drop_object_id: DropObjectId = g_proj_id.into();
-                    let points_to =
-                        PointsTo::new(g_proj_id, Some(drop_object_id).into_iter().collect());
-                    ctxt.worklist.push_back(points_to);
+            // handle call which is modeled as a drop
+            if let Some(call_info) = cfg.call_infos.get(bb_id) {
+                let def_path = utils::parse_def_id(call_info.callee_def_id);
+                if let Some(arg) = utils::get_dropped_arg(&ctxt.options, &def_path) {
+                    if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) =
+                        call_info.args.get(arg)
+                    {
+                        let place = &get_moved_source(bb_info, place);
+                        let drop_obj_id = add_drop_object(
+                            &mut ctxt.pfg,
+                            &mut ctxt.worklist,
+                            &call_id,
+                            *bb_id,
+                            place,
+                            call_info.span,
+                            get_object_ty(&ctxt.options, ctxt.tcx, call_id.def_id, place),
+                        );
+                        if utils::is_rc_handle_ty(utils::get_ty_from_place(ctxt.tcx, call_id.def_id, place)) {
+                            ctxt.pfg.rc_handle_objects.insert(drop_obj_id);
+                        }
+                    }
                 }
             }
 
@@ -122,7 +197,9 @@ fn add_reachable(ctxt: AnalysisContext, call_id: CtxtSenCallId) -> AnalysisConte
                     let need_add_edge = match assignment.op {
                         OpKind::Move | OpKind::Ref | OpKind::AddressOf => true,
                         OpKind::Copy => {
-                            if is_ptr_copy(ctxt.tcx, call_id.def_id, place) {
+                            if is_ptr_copy(ctxt.tcx, call_id.def_id, place)
+                                || cfg.address_locals.contains(&place.local)
+                            {
                                 true
                             } else {
                                 log::debug!("ignored copy edge at: {:?} with op, lval, rval: {:?} {:?} {:?}", assignment.stat_span, assignment.op, assignment.lvalue, assignment.rvalue);
@@ -147,7 +224,55 @@ fn add_reachable(ctxt: AnalysisContext, call_id: CtxtSenCallId) -> AnalysisConte
                                 assignment.stat_span,
                                 CallerContext::new(vec![]),
                             ),
+                            assignment.is_mut,
                         );
+
+                        // the old value behind the pointer is dropped by the assignment
+                        if utils::check_alias_assign(&ctxt.options)
+                            && matches!(assignment.op, OpKind::Move)
+                            && is_owned_place_behind_ptr(ctxt.tcx, call_id.def_id, &assignment.lvalue)
+                        {
+                            let ptr_g_proj_id = add_ptr_node(&mut ctxt.pfg, &call_id, &assignment.lvalue);
+                            let ptr_assign = (
+                                ptr_g_proj_id,
+                                right_g_proj_id,
+                                CtxtSenSpanInfo::new(
+                                    call_id.def_id,
+                                    *bb_id,
+                                    assignment.stat_span,
+                                    call_id.caller_context.clone(),
+                                ),
+                            );
+                            if !ctxt.pfg.ptr_assigns.contains(&ptr_assign) {
+                                ctxt.pfg.ptr_assigns.push(ptr_assign);
+                            }
+                        }
+
+                        // an rc stored behind a pointer, eg. `*cell.borrow_mut() = Some(rc)`
+                        if utils::check_rc_cycle(&ctxt.options)
+                            && matches!(assignment.op, OpKind::Move)
+                            && assignment.lvalue.projection.contains(&PlaceElem::Deref)
+                            && utils::contains_rc_ty(utils::get_ty_from_place(
+                                ctxt.tcx,
+                                call_id.def_id,
+                                &assignment.lvalue,
+                            ))
+                        {
+                            let ptr_g_proj_id = add_ptr_node(&mut ctxt.pfg, &call_id, &assignment.lvalue);
+                            let rc_store = (
+                                ptr_g_proj_id,
+                                right_g_proj_id,
+                                CtxtSenSpanInfo::new(
+                                    call_id.def_id,
+                                    *bb_id,
+                                    assignment.stat_span,
+                                    call_id.caller_context.clone(),
+                                ),
+                            );
+                            if !ctxt.pfg.rc_stores.contains(&rc_store) {
+                                ctxt.pfg.rc_stores.push(rc_store);
+                            }
+                        }
                     }
                 }
             }
@@ -156,6 +281,79 @@ fn add_reachable(ctxt: AnalysisContext, call_id: CtxtSenCallId) -> AnalysisConte
     }
 }
 
+// the type whose drops share one object, only under `--object-granularity=type`
+fn get_object_ty<'tcx>(
+    opts: &AnalysisOptions,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    def_id: DefId,
+    place: &Place<'tcx>,
+) -> Option<rustc_middle::ty::Ty<'tcx>> {
+    match utils::object_granularity(opts) {
+        ObjectGranularity::Local => None,
+        ObjectGranularity::Type => Some(utils::get_ty_from_place(tcx, def_id, place)),
+    }
+}
+
+// the place moved into the temp of an arg in the same block, eg. `x` of `drop(x)` lowered to
+// `_2 = move _1; drop(move _2)`, the objects aliased with `x` before the call flow from it
+fn get_moved_source<'tcx>(bb_info: &BasicBlockInfo<'tcx>, place: &Place<'tcx>) -> Place<'tcx> {
+    bb_info
+        .assignment_infos
+        .iter()
+        .rev()
+        .find(|assignment| assignment.lvalue == *place)
+        .and_then(|assignment| match assignment.rvalue {
+            RvalKind::Addressed(source) if matches!(assignment.op, OpKind::Move) => Some(source),
+            _ => None,
+        })
+        .unwrap_or(*place)
+}
+
+// the locals moved into `mem::drop` or `mem::forget` with the blocks of the calls
+fn get_consumed_locals(cfg: &ControlFlowGraph) -> HashMap<LocalId, BasicBlockId> {
+    cfg.call_infos
+        .values()
+        .filter(|call_info| {
+            let def_path = utils::parse_def_id(call_info.callee_def_id);
+            utils::is_mem_drop(&def_path) || utils::is_mem_forget(&def_path)
+        })
+        .filter_map(|call_info| match call_info.args.get(0) {
+            Some(Operand::Move(place)) => {
+                let bb_info = &cfg.basic_block_infos[&call_info.caller_bb_id];
+                Some((get_moved_source(bb_info, place).local, call_info.caller_bb_id))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn add_drop_object<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    call_id: &CtxtSenCallId,
+    bb_id: BasicBlockId,
+    place: &Place<'tcx>,
+    span: rustc_span::Span,
+    object_ty: Option<rustc_middle::ty::Ty<'tcx>>,
+) -> DropObjectId {
+    let cs_drop_span =
+        CtxtSenSpanInfo::new(call_id.def_id, bb_id, span, call_id.caller_context.clone());
+
+    let g_proj_id = pfg.add_or_update_node(call_id, place, Some(cs_drop_span));
+    let drop_object_id: DropObjectId = match object_ty {
+        // the first dropped place of the type stands for all of them
+        Some(ty) => *pfg.type_objects.entry(ty).or_insert_with(|| g_proj_id.into()),
+        None => g_proj_id.into(),
+    };
+
+    // we assume that all drops of this place **in this context** refer to the same object, so we only add <c: x, {c: oi}> to WL once
+    if pfg.get_projection_node(g_proj_id).cs_drop_spans.len() == 1 {
+        let points_to = PointsTo::new(g_proj_id, Some(drop_object_id).into_iter().collect());
+        worklist.push_back(points_to);
+    }
+    drop_object_id
+}
+
 /// we don't care about the caller context, so this should be vec![]
 fn add_edge(
     pfg: &mut PointerFlowGraph,
@@ -163,12 +361,13 @@ fn add_edge(
     from: GlobalProjectionId,
     to: GlobalProjectionId,
     span_info: CtxtSenSpanInfo,
+    is_mut_borrow: bool,
 ) {
     assert!(span_info.caller_context.g_bb_ids.is_empty());
     if pfg.has_edge(from, to) {
         return;
     } else {
-        pfg.add_edge(from, to, span_info);
+        pfg.add_edge(from, to, span_info, is_mut_borrow);
         let from_node = pfg.get_projection_node(from);
 
         if !from_node.points_to.is_empty() {
@@ -189,18 +388,34 @@ fn process_calls(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext
     let mut ctxt = ctxt;
 
     while !call_work_list.is_empty() {
+        if ctxt.is_timed_out() {
+            break;
+        }
         let caller = call_work_list.pop_front().unwrap();
 
+        // the cfg of a queued call may be missing, eg. the fn has no optimized MIR, skip it
+        if !ctxt.cfgs.contains_key(&caller.def_id) {
+            log::debug!("no cfg for caller: {:?}, skip its calls", caller.def_id);
+            continue;
+        }
+
         if !ctxt.cs_reachable_calls.contains(&caller) {
             // add caller with context to reachable calls
             ctxt = add_reachable(ctxt, caller.clone());
 
             // TODO: ensure all callee cfgs are in cfgs
             let mut new_cfg_list = vec![];
-            for (_, call_info) in ctxt.cfgs.get(&caller.def_id).unwrap().call_infos.iter() {
+            let callee_def_ids = ctxt.cfgs[&caller.def_id].call_infos.values().flat_map(|call_info| call_info.callees());
+            for callee_def_id in callee_def_ids {
                 // if callee is not in cfgs, we need to create it
-                if !ctxt.cfgs.contains_key(&call_info.callee_def_id) {
-                    let def_name = utils::parse_def_id(call_info.callee_def_id).join("::");
+                if !ctxt.cfgs.contains_key(&callee_def_id) {
+                    // the registered model replaces the body of callee
+                    let def_path = utils::parse_def_id(callee_def_id);
+                    if utils::get_model(&ctxt.options, &def_path).is_some() || utils::is_mem_drop(&def_path) {
+                        continue;
+                    }
+
+                    let def_name = def_path.join("::");
                     // we ignore the CHA of some common pointer related functions
                     // if ARG_TO_RET_DEF_NAMES.iter().any(|&s| def_name.ends_with(s)) {
                     //     continue;
@@ -210,7 +425,7 @@ fn process_calls(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext
                         continue;
                     }
 
-                    if !utils::check_std(&ctxt.options) {
+                    if !utils::check_std(&ctxt.options) && !utils::should_analyze_std(&ctxt.options, ctxt.tcx, callee_def_id) {
                         // we ignore all standard library functions
                         if def_name.starts_with("std::") {
                             continue;
@@ -225,9 +440,10 @@ fn process_calls(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext
                         }
                     }
 
-                    if let Some(callee_cfg) =
-                        cfg::try_create_cfg(&ctxt.options, ctxt.tcx, call_info.callee_def_id, false)
-                    {
+                    let start = utils::profile(&ctxt.options).map(|_| Instant::now());
+                    let callee_cfg = cfg::try_create_cfg(&ctxt.options, ctxt.tcx, callee_def_id, false);
+                    utils::add_elapsed(&mut ctxt.cfg_times, callee_def_id, start);
+                    if let Some(callee_cfg) = callee_cfg {
                         cfg::add_called_info(&ctxt.options, &mut ctxt.called_infos, &callee_cfg);
                         new_cfg_list.push(callee_cfg);
                     }
@@ -237,51 +453,145 @@ fn process_calls(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext
                 .extend(new_cfg_list.into_iter().map(|cfg| (cfg.def_id, cfg)));
 
             // add edges from caller args to callee params
-            let caller_cfg = ctxt.cfgs.get(&caller.def_id).unwrap();
-            for (bb_id, call_info) in caller_cfg.call_infos.iter() {
-                if ctxt.cfgs.contains_key(&call_info.callee_def_id) {
-                    // select target context
-                    let target_context =
-                        CallerContext::new(vec![GlobalBasicBlockId::new(caller.def_id, *bb_id)]);
-                    let callee_id =
-                        CtxtSenCallId::new(call_info.callee_def_id, target_context.clone());
-
-                    // add callee to worklist
-                    call_work_list.push_back(callee_id.clone());
-
-                    add_args_and_ret_edge(
-                        &ctxt.options,
-                        &mut ctxt.pfg,
-                        &mut ctxt.worklist,
-                        ctxt.tcx,
-                        caller_cfg,
-                        &caller,
-                        call_info,
-                        &target_context,
-                    );
-                } else {
-                    log::debug!(
-                        "external unsolved crate call: {:?} in caller {:?} bb {:?}",
-                        call_info.callee_def_id,
-                        caller.def_id,
-                        bb_id
-                    );
+            let caller_cfg = &ctxt.cfgs[&caller.def_id];
+            // the callees are queued in the order of the calls, like the blocks in `add_reachable`
+            let mut call_infos = caller_cfg.call_infos.iter().collect::<Vec<_>>();
+            call_infos.sort_by_key(|(bb_id, _)| **bb_id);
+            for (bb_id, call_info) in call_infos {
+                // the models match the callee by its path
+                let def_path = utils::parse_def_id(call_info.callee_def_id);
+                let def_name = def_path.join("::");
+                add_box_ownership_model(&mut ctxt.pfg, &mut ctxt.worklist, &caller, call_info, &def_name);
+                add_manual_drop_model(&mut ctxt.pfg, &caller, call_info, &def_name);
+                add_raw_parts_model(&mut ctxt.pfg, &caller, call_info, &def_name);
+                add_alloc_model(&mut ctxt.pfg, &caller, call_info, &def_name);
+                if utils::check_ffi_callback(&ctxt.options) {
+                    add_ffi_callback_model(&mut ctxt.pfg, ctxt.tcx, &caller, call_info);
+                }
+                if utils::check_rc_cycle(&ctxt.options) {
+                    add_rc_cycle_model(&mut ctxt.pfg, ctxt.tcx, &caller, call_info, &def_name);
+                }
 
-                    let def_name = utils::parse_def_id(call_info.callee_def_id).join("::");
-                    // we ignore the edge of some clone functions
-                    if IGNORE_DEF_NAMES.iter().any(|&s| def_name.ends_with(s)) {
+                if add_rc_clone_model(&mut ctxt.pfg, &mut ctxt.worklist, ctxt.tcx, &caller, call_info, &def_name) {
+                    continue;
+                }
+
+                if add_ptr_read_write_model(&mut ctxt.pfg, &mut ctxt.worklist, ctxt.tcx, &caller, call_info, &def_name) {
+                    continue;
+                }
+
+                if add_transmute_model(&mut ctxt.pfg, &mut ctxt.worklist, &caller, call_info, &def_name) {
+                    continue;
+                }
+
+                if add_take_model(&mut ctxt.pfg, &mut ctxt.worklist, ctxt.tcx, &caller, call_info, &def_name) {
+                    continue;
+                }
+
+                if utils::model_rc_weak(&ctxt.options)
+                    && add_rc_weak_model(&mut ctxt.pfg, &mut ctxt.worklist, &caller, call_info, &def_name)
+                {
+                    continue;
+                }
+
+                if utils::check_guard_escape(&ctxt.options)
+                    && add_guard_model(&mut ctxt.pfg, &mut ctxt.worklist, ctxt.tcx, &caller, call_info, &def_name)
+                {
+                    continue;
+                }
+
+                if utils::check_iter_escape(&ctxt.options) {
+                    add_iter_next_model(&mut ctxt.pfg, &caller, call_info, &def_name);
+                }
+
+                match utils::get_model(&ctxt.options, &def_path) {
+                    Some(ModelKind::ArgToRet) => {
+                        add_args_to_ret_edge(
+                            &ctxt.options,
+                            &mut ctxt.pfg,
+                            &mut ctxt.worklist,
+                            ctxt.tcx,
+                            caller_cfg,
+                            &caller,
+                            call_info,
+                        );
+                        continue;
+                    }
+                    Some(ModelKind::Transfer { from_arg, to_arg }) => {
+                        add_transfer_model(
+                            &mut ctxt.pfg,
+                            &mut ctxt.worklist,
+                            ctxt.tcx,
+                            &caller,
+                            call_info,
+                            *from_arg,
+                            *to_arg,
+                        );
                         continue;
                     }
+                    // drop model is handled when the caller becomes reachable, so is `mem::drop`
+                    Some(ModelKind::Ignore) | Some(ModelKind::Drop { .. }) => continue,
+                    None if utils::is_mem_drop(&def_path) => continue,
+                    None => {}
+                }
 
-                    add_args_to_ret_edge(
-                        &ctxt.options,
-                        &mut ctxt.pfg,
-                        &mut ctxt.worklist,
-                        ctxt.tcx,
-                        caller_cfg,
-                        &caller,
-                        call_info,
-                    );
+                // a trait method call on an unknown receiver goes to the method of each impl
+                for call_info in call_info.impl_calls().iter() {
+                    if let Some(callee_cfg) = ctxt.cfgs.get(&call_info.callee_def_id) {
+                        // select target context, the last call sites up to the context depth, a recursive
+                        // call goes back to the context-insensitive callee so the cycle adds no contexts
+                        let is_recursive = call_info.callee_def_id == caller.def_id
+                            || caller.caller_context.has_caller(call_info.callee_def_id);
+                        let target_context = if is_recursive {
+                            log::debug!("recursive call of {:?} in caller {:?}", call_info.callee_def_id, caller);
+                            CallerContext::new(vec![])
+                        } else {
+                            caller.caller_context.push(
+                                GlobalBasicBlockId::new(caller.def_id, *bb_id),
+                                utils::context_depth(&ctxt.options),
+                            )
+                        };
+                        let callee_id =
+                            CtxtSenCallId::new(call_info.callee_def_id, target_context.clone());
+
+                        // add callee to worklist
+                        call_work_list.push_back(callee_id.clone());
+
+                        add_args_and_ret_edge(
+                            &ctxt.options,
+                            &mut ctxt.pfg,
+                            &mut ctxt.worklist,
+                            ctxt.tcx,
+                            caller_cfg,
+                            callee_cfg,
+                            &caller,
+                            call_info,
+                            &target_context,
+                        );
+                    } else {
+                        log::debug!(
+                            "external unsolved crate call: {:?} in caller {:?} bb {:?}",
+                            call_info.callee_def_id,
+                            caller.def_id,
+                            bb_id
+                        );
+
+                        let def_name = utils::parse_def_id(call_info.callee_def_id).join("::");
+                        // we ignore the edge of some clone functions
+                        if IGNORE_DEF_NAMES.iter().any(|&s| def_name.ends_with(s)) {
+                            continue;
+                        }
+
+                        add_args_to_ret_edge(
+                            &ctxt.options,
+                            &mut ctxt.pfg,
+                            &mut ctxt.worklist,
+                            ctxt.tcx,
+                            caller_cfg,
+                            &caller,
+                            call_info,
+                        );
+                    }
                 }
             }
         }
@@ -295,9 +605,581 @@ fn process_calls(ctxt: AnalysisContext, entry: CtxtSenCallId) -> AnalysisContext
         pfg: ctxt.pfg,
         cs_reachable_calls: ctxt.cs_reachable_calls,
         worklist: ctxt.worklist,
+        deadline: ctxt.deadline,
+        timed_out: ctxt.timed_out,
+        cfg_times: ctxt.cfg_times,
+    }
+}
+
+/// `Box::into_raw` gives up the ownership of the box, so the returned raw pointer
+/// carries a released object which should be reclaimed by `Box::from_raw` exactly once
+fn add_box_ownership_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    if !utils::is_def_named(def_name, &BOX_INTO_RAW_DEF_NAMES) {
+        return;
+    }
+
+    let ret_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+    let released_obj_id: DropObjectId = ret_id.into();
+    if let Entry::Vacant(entry) =
+        pfg.raw_released_objects.entry(released_obj_id)
+    {
+        entry.insert(CtxtSenSpanInfo::new(
+            caller.def_id,
+            call_info.caller_bb_id,
+            call_info.span,
+            caller.caller_context.clone(),
+        ));
+        worklist.push_back(PointsTo::new(
+            ret_id,
+            Some(released_obj_id).into_iter().collect(),
+        ));
+    }
+}
+
+/// `ptr::drop_in_place` drops the value behind the pointer, record the pointer to check
+/// whether the value is also freed by the drop glue of its owner
+fn add_manual_drop_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    if !utils::is_def_named(def_name, &DROP_IN_PLACE_DEF_NAMES) {
+        return;
+    }
+
+    if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) = call_info.args.get(0) {
+        let ptr_id = pfg.add_or_update_node(caller, place, None);
+        pfg.manual_drops.entry(ptr_id).or_insert_with(|| {
+            CtxtSenSpanInfo::new(
+                caller.def_id,
+                call_info.caller_bb_id,
+                call_info.span,
+                caller.caller_context.clone(),
+            )
+        });
+    }
+}
+
+/// `Vec::from_raw_parts` takes the ownership of the buffer behind the pointer, record the pointer
+/// to check whether the buffer is given to more than one owner
+fn add_raw_parts_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    if !utils::is_def_named(def_name, &RAW_PARTS_DEF_NAMES) {
+        return;
+    }
+
+    if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) = call_info.args.get(0) {
+        let ptr_id = pfg.add_or_update_node(caller, place, None);
+        let owner_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        pfg.raw_parts_owners.push((
+            ptr_id,
+            owner_id,
+            CtxtSenSpanInfo::new(
+                caller.def_id,
+                call_info.caller_bb_id,
+                call_info.span,
+                caller.caller_context.clone(),
+            ),
+        ));
+    }
+}
+
+/// Record the boxes allocated by the call, and the calls to `mem::forget` which take the
+/// ownership without dropping, to check whether every allocated box is dropped on all paths
+fn add_alloc_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    if utils::is_def_named(def_name, &ALLOC_DEF_NAMES) {
+        let box_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        pfg.allocations.push((
+            box_id,
+            CtxtSenSpanInfo::new(
+                caller.def_id,
+                call_info.caller_bb_id,
+                call_info.span,
+                caller.caller_context.clone(),
+            ),
+        ));
+    } else if utils::is_def_named(def_name, &FORGET_DEF_NAMES) {
+        pfg.forget_calls
+            .insert(GlobalBasicBlockId::new(caller.def_id, call_info.caller_bb_id));
+    }
+}
+
+/// A foreign function receiving a callback may call it back with the other pointer arguments
+/// later, so these pointers must outlive the registration
+fn add_ffi_callback_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+) {
+    if !tcx.is_foreign_item(call_info.callee_def_id) {
+        return;
+    }
+
+    let body = utils::get_mir_body(tcx, caller.def_id);
+    let arg_tys = call_info
+        .args
+        .iter()
+        .map(|arg| arg.ty(&body.local_decls, tcx))
+        .collect::<Vec<_>>();
+    // eg. `extern "C" fn(*mut c_void)` or `Option<extern "C" fn(*mut c_void)>`
+    let is_callback = |ty: rustc_middle::ty::Ty<'tcx>| {
+        ty.walk().any(|arg| match arg.unpack() {
+            GenericArgKind::Type(ty) => ty.is_fn_ptr(),
+            _ => false,
+        })
+    };
+    if !arg_tys.iter().any(|ty| is_callback(*ty)) {
+        return;
+    }
+
+    for (arg, ty) in call_info.args.iter().zip(arg_tys.iter()) {
+        if let Operand::Move(place) | Operand::Copy(place) = arg {
+            if (ty.is_unsafe_ptr() || ty.is_ref()) && !is_callback(*ty) {
+                let ctx_id = pfg.add_or_update_node(caller, place, None);
+                pfg.ffi_registrations.push((
+                    ctx_id,
+                    CtxtSenSpanInfo::new(
+                        caller.def_id,
+                        call_info.caller_bb_id,
+                        call_info.span,
+                        caller.caller_context.clone(),
+                    ),
+                ));
+            }
+        }
     }
 }
 
+/// `Rc::new` creates an rc owning the moved value, and an external call taking an rc together
+/// with a pointer may store the rc behind the pointer, eg. `Vec::push`
+fn add_rc_cycle_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    let span_info = CtxtSenSpanInfo::new(
+        caller.def_id,
+        call_info.caller_bb_id,
+        call_info.span,
+        caller.caller_context.clone(),
+    );
+    let body = utils::get_mir_body(tcx, caller.def_id);
+
+    // `Weak::new` is in the same modules
+    if utils::is_def_named(def_name, &RC_NEW_DEF_NAMES)
+        && utils::is_rc_ty(call_info.destination.ty(&body.local_decls, tcx).ty)
+    {
+        let rc_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        let value_id = match call_info.args.get(0) {
+            Some(Operand::Move(place)) | Some(Operand::Copy(place)) => {
+                Some(pfg.add_or_update_node(caller, place, None))
+            }
+            _ => None,
+        };
+        let rc_creation = (rc_id, value_id, span_info);
+        if !pfg.rc_creations.contains(&rc_creation) {
+            pfg.rc_creations.push(rc_creation);
+        }
+        return;
+    }
+
+    // the bodies of local functions are analyzed
+    if call_info.callee_def_id.is_local() {
+        return;
+    }
+    let arg_tys = call_info
+        .args
+        .iter()
+        .map(|arg| arg.ty(&body.local_decls, tcx))
+        .collect::<Vec<_>>();
+    let is_ptr = |ty: rustc_middle::ty::Ty<'tcx>| ty.is_ref() || ty.is_unsafe_ptr();
+    for (ptr_arg, ptr_ty) in call_info.args.iter().zip(arg_tys.iter()) {
+        let ptr_place = match ptr_arg {
+            Operand::Move(place) | Operand::Copy(place) if is_ptr(*ptr_ty) => place,
+            _ => continue,
+        };
+        for (rc_arg, rc_ty) in call_info.args.iter().zip(arg_tys.iter()) {
+            if let Operand::Move(rc_place) = rc_arg {
+                if !is_ptr(*rc_ty) && utils::contains_rc_ty(*rc_ty) {
+                    let ptr_id = pfg.add_or_update_node(caller, ptr_place, None);
+                    let rc_id = pfg.add_or_update_node(caller, rc_place, None);
+                    let rc_store = (ptr_id, rc_id, span_info.clone());
+                    if !pfg.rc_stores.contains(&rc_store) {
+                        pfg.rc_stores.push(rc_store);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `Rc::clone` creates a new handle sharing the value of the borrowed one, so the clone points to
+/// the same objects, but the drop of a handle alone never frees the value.
+/// Returns true if the call is fully modeled and the common edges should be skipped
+fn add_rc_clone_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    let cloned_ty = utils::get_ty_from_place(tcx, caller.def_id, &call_info.destination);
+    if !utils::is_def_named(def_name, &RC_CLONE_DEF_NAMES) || !utils::is_rc_handle_ty(cloned_ty) {
+        return false;
+    }
+    let rc_ref_place = match call_info.args.get(0) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => *place,
+        _ => return false,
+    };
+
+    let rc_ref_id = pfg.add_or_update_node(caller, &rc_ref_place, None);
+    let rc_id = pfg.add_or_update_node(caller, &tcx.mk_place_deref(rc_ref_place), None);
+    let cloned_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+    add_edge(
+        pfg,
+        worklist,
+        rc_id,
+        cloned_id,
+        CtxtSenSpanInfo::new(
+            caller.def_id,
+            call_info.caller_bb_id,
+            call_info.span,
+            CallerContext::new(vec![]),
+        ),
+        false,
+    );
+    // a weak clone never keeps the value alive
+    if utils::is_rc_ty(cloned_ty) {
+        pfg.rc_clones.insert((rc_ref_id, cloned_id));
+    }
+    true
+}
+
+/// `Rc::downgrade` creates a weak object which does not own the value, and `Weak::upgrade`
+/// gives back an rc only if the strong rc is still alive, so neither aliases the drop of the value.
+/// Returns true if the call is fully modeled and the common edges should be skipped
+fn add_rc_weak_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    let arg_place = match call_info.args.get(0) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => place,
+        _ => return false,
+    };
+
+    if utils::is_def_named(def_name, &WEAK_DOWNGRADE_DEF_NAMES) {
+        let weak_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        let strong_id = pfg.add_or_update_node(caller, arg_place, None);
+        let weak_obj_id: DropObjectId = weak_id.into();
+        if let Entry::Vacant(entry) = pfg.downgraded_weaks.entry(weak_obj_id) {
+            entry.insert(strong_id);
+            worklist.push_back(PointsTo::new(
+                weak_id,
+                Some(weak_obj_id).into_iter().collect(),
+            ));
+        }
+        true
+    } else if utils::is_def_named(def_name, &WEAK_UPGRADE_DEF_NAMES) {
+        let upgraded_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        let weak_id = pfg.add_or_update_node(caller, arg_place, None);
+        pfg.upgraded_weaks.insert(upgraded_id, weak_id);
+        true
+    } else {
+        if utils::is_def_named(def_name, &UNCHECKED_UNWRAP_DEF_NAMES) {
+            let option_id = pfg.add_or_update_node(caller, arg_place, None);
+            pfg.unchecked_unwraps.insert(
+                option_id,
+                CtxtSenSpanInfo::new(
+                    caller.def_id,
+                    call_info.caller_bb_id,
+                    call_info.span,
+                    caller.caller_context.clone(),
+                ),
+            );
+        }
+        false
+    }
+}
+
+/// A guard like `Ref` or `MutexGuard` is a new object borrowing its container instead of
+/// an alias of it, dropping the guard only releases the borrow
+fn add_guard_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    let arg_place = match call_info.args.get(0) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => place,
+        _ => return false,
+    };
+
+    if utils::is_def_named(def_name, &GUARD_BORROW_DEF_NAMES)
+        && utils::contains_guard_ty(utils::get_ty_from_place(tcx, caller.def_id, &call_info.destination))
+    {
+        let guard_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        let container_id = pfg.add_or_update_node(caller, arg_place, None);
+        let guard_obj_id: DropObjectId = guard_id.into();
+        if let Entry::Vacant(entry) = pfg.borrowed_guards.entry(guard_obj_id) {
+            entry.insert(container_id);
+            worklist.push_back(PointsTo::new(
+                guard_id,
+                Some(guard_obj_id).into_iter().collect(),
+            ));
+        }
+        true
+    } else {
+        let arg_ty = utils::get_ty_from_place(tcx, caller.def_id, arg_place);
+        if utils::is_def_named(def_name, &GUARD_DEREF_DEF_NAMES)
+            && arg_ty.builtin_deref(true).map_or(false, |pointee| utils::is_guard_ty(pointee.ty))
+        {
+            let guard_ptr_id = pfg.add_or_update_node(caller, arg_place, None);
+            pfg.guard_derefs.push((
+                guard_ptr_id,
+                CtxtSenSpanInfo::new(
+                    caller.def_id,
+                    call_info.caller_bb_id,
+                    call_info.span,
+                    caller.caller_context.clone(),
+                ),
+            ));
+        }
+        false
+    }
+}
+
+/// `Iterator::next` consumes the iterator, the references it yields point to what the iterator
+/// points to, eg. the collection an adaptor like `iter().map(..)` borrows
+fn add_iter_next_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) {
+    let is_next = utils::is_def_named(def_name, &ITER_NEXT_DEF_NAMES);
+    let iter_place = match call_info.args.get(0) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) if is_next => place,
+        _ => return,
+    };
+
+    let iter_ptr_id = pfg.add_or_update_node(caller, iter_place, None);
+    let span_info = CtxtSenSpanInfo::new(
+        caller.def_id,
+        call_info.caller_bb_id,
+        call_info.span,
+        caller.caller_context.clone(),
+    );
+    if !pfg.iter_nexts.contains(&(iter_ptr_id, span_info.clone())) {
+        pfg.iter_nexts.push((iter_ptr_id, span_info));
+    }
+}
+
+/// the objects of `from_arg` are moved into `to_arg`, or into the place it points to
+/// `ptr::read` duplicates the value behind the pointer into the return value, `ptr::write` moves
+/// the value into what the pointer points to without dropping the old one, `mem::replace` does both.
+/// `mem::swap` is left out, the exchanged values would be seen as owned twice by both places
+fn add_ptr_read_write_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    let is_read = utils::is_def_named(def_name, &PTR_READ_DEF_NAMES);
+    let is_write = utils::is_def_named(def_name, &PTR_WRITE_DEF_NAMES);
+    let is_replace = utils::is_def_named(def_name, &REPLACE_DEF_NAMES);
+    if !is_read && !is_write && !is_replace {
+        return false;
+    }
+
+    if is_read || is_replace {
+        if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) = call_info.args.get(0) {
+            let value_id = pfg.add_or_update_node(caller, &tcx.mk_place_deref(*place), None);
+            let ret_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+            add_edge(
+                pfg,
+                worklist,
+                value_id,
+                ret_id,
+                CtxtSenSpanInfo::new(
+                    caller.def_id,
+                    call_info.caller_bb_id,
+                    call_info.span,
+                    CallerContext::new(vec![]),
+                ),
+                false,
+            );
+            // the old value is moved out like `Option::take`, a read leaves it owned by both
+            if is_replace {
+                let new_value_id = get_arg_node(pfg, caller, call_info, 1);
+                pfg.taken_values.insert((value_id, ret_id, new_value_id));
+            }
+        }
+    }
+    if is_write || is_replace {
+        add_transfer_model(pfg, worklist, tcx, caller, call_info, 1, 0);
+    }
+    true
+}
+
+/// the return value of `mem::transmute` is the arg with another type, it points to the same objects
+fn add_transmute_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    if !utils::is_def_named(def_name, &TRANSMUTE_DEF_NAMES) {
+        return false;
+    }
+
+    if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) = call_info.args.get(0) {
+        let arg_id = pfg.add_or_update_node(caller, place, None);
+        let ret_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        add_edge(
+            pfg,
+            worklist,
+            arg_id,
+            ret_id,
+            CtxtSenSpanInfo::new(
+                caller.def_id,
+                call_info.caller_bb_id,
+                call_info.span,
+                CallerContext::new(vec![]),
+            ),
+            false,
+        );
+    }
+    true
+}
+
+/// `Option::take`, `Cell::replace` and the like move the value of the container into the return value
+/// and leave another one in it, the container no longer owns the taken value
+fn add_take_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    def_name: &str,
+) -> bool {
+    if !utils::is_def_named(def_name, &TAKE_DEF_NAMES) {
+        return false;
+    }
+
+    if let Some(Operand::Move(place)) | Some(Operand::Copy(place)) = call_info.args.get(0) {
+        // `into_inner` takes the container by value
+        let container_ty = utils::get_ty_from_place(tcx, caller.def_id, place);
+        let container_place = if container_ty.is_unsafe_ptr() || container_ty.is_ref() {
+            tcx.mk_place_deref(*place)
+        } else {
+            *place
+        };
+        let container_id = pfg.add_or_update_node(caller, &container_place, None);
+        let ret_id = pfg.add_or_update_node(caller, &call_info.destination, None);
+        add_edge(
+            pfg,
+            worklist,
+            container_id,
+            ret_id,
+            CtxtSenSpanInfo::new(
+                caller.def_id,
+                call_info.caller_bb_id,
+                call_info.span,
+                CallerContext::new(vec![]),
+            ),
+            false,
+        );
+        let new_value_id = get_arg_node(pfg, caller, call_info, 1);
+        pfg.taken_values.insert((container_id, ret_id, new_value_id));
+    }
+    if call_info.args.len() > 1 {
+        add_transfer_model(pfg, worklist, tcx, caller, call_info, 1, 0);
+    }
+    true
+}
+
+fn get_arg_node<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    arg: usize,
+) -> Option<GlobalProjectionId> {
+    match call_info.args.get(arg) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => Some(pfg.add_or_update_node(caller, place, None)),
+        _ => None,
+    }
+}
+
+fn add_transfer_model<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    from_arg: usize,
+    to_arg: usize,
+) {
+    let get_arg_place = |i: usize| match call_info.args.get(i) {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => Some(*place),
+        _ => None,
+    };
+    let (from_place, to_place) = match (get_arg_place(from_arg), get_arg_place(to_arg)) {
+        (Some(from_place), Some(to_place)) => (from_place, to_place),
+        _ => return,
+    };
+
+    let to_ty = utils::get_ty_from_place(tcx, caller.def_id, &to_place);
+    let to_place = if to_ty.is_unsafe_ptr() || to_ty.is_ref() {
+        tcx.mk_place_deref(to_place)
+    } else {
+        to_place
+    };
+
+    let from_id = pfg.add_or_update_node(caller, &from_place, None);
+    let to_id = pfg.add_or_update_node(caller, &to_place, None);
+    add_edge(
+        pfg,
+        worklist,
+        from_id,
+        to_id,
+        CtxtSenSpanInfo::new(
+            caller.def_id,
+            call_info.caller_bb_id,
+            call_info.span,
+            CallerContext::new(vec![]),
+        ),
+        false,
+    );
+}
+
 fn diffuse_to_sub_level(
     ctxt: AnalysisContext,
     cur_g_proj_id: GlobalProjectionId,
@@ -448,12 +1330,39 @@ fn is_ptr_copy<'tcx>(
     }
 }
 
+// the pointer the place is behind, eg. `_1` of `(*_1).0`
+fn add_ptr_node<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    call_id: &CtxtSenCallId,
+    place: &Place<'tcx>,
+) -> GlobalProjectionId {
+    let last_deref = place
+        .projection
+        .iter()
+        .rposition(|elem| elem == PlaceElem::Deref)
+        .unwrap();
+    pfg.add_or_update_virtual_node(call_id, place.local, &place.projection[..last_deref], None)
+}
+
+fn is_owned_place_behind_ptr<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    def_id: DefId,
+    place: &Place<'tcx>,
+) -> bool {
+    if !place.projection.contains(&PlaceElem::Deref) {
+        return false;
+    }
+    let ty = utils::get_ty_from_place(tcx, def_id, place);
+    ty.needs_drop(tcx, tcx.param_env(def_id))
+}
+
 fn add_args_and_ret_edge<'tcx>(
     opts: &AnalysisOptions,
     pfg: &mut PointerFlowGraph<'tcx>,
     worklist: &mut VecDeque<PointsTo>,
     tcx: rustc_middle::ty::TyCtxt<'tcx>,
-    caller_cfg: &ControlFlowGraph,
+    caller_cfg: &ControlFlowGraph<'tcx>,
+    callee_cfg: &ControlFlowGraph<'tcx>,
     caller: &CtxtSenCallId,
     call_info: &CallInfo<'tcx>,
     target_context: &CallerContext,
@@ -466,9 +1375,13 @@ fn add_args_and_ret_edge<'tcx>(
     );
     let callee_id = CtxtSenCallId::new(call_info.callee_def_id, target_context.clone());
     // add edge from caller arg to callee parameter
-    for (i, arg) in call_info.args.iter().enumerate() {
-        let i = i + 1;
-
+    for (i, arg) in get_callee_params(tcx, caller, call_info) {
+        // a synthetic callee body may have fewer locals than the call has args
+        if !callee_cfg.local_infos.contains_key(&LocalId::from_usize(i)) {
+            log::debug!("no param {} in callee {:?}", i, call_info.callee_def_id);
+            continue;
+        }
+        let arg = &arg;
         let arg_place = match arg {
             Operand::Move(ref place) => Some(place),
             Operand::Copy(ref place) => Some(place),
@@ -479,7 +1392,9 @@ fn add_args_and_ret_edge<'tcx>(
             let need_add_edge = match arg {
                 Operand::Move(_) => true,
                 Operand::Copy(_) => {
-                    if is_ptr_copy(tcx, caller.def_id, arg_place) {
+                    if is_ptr_copy(tcx, caller.def_id, arg_place)
+                        || caller_cfg.address_locals.contains(&arg_place.local)
+                    {
                         true
                     } else {
                         log::debug!(
@@ -500,10 +1415,10 @@ fn add_args_and_ret_edge<'tcx>(
                 // arguments are seen as deref
                 if utils::let_arg_as_deref(opts) {
                     pfg.deref_edges
-                        .insert(DerefEdgeInfo::new(arg_id, param_id, (true, false)));
+                        .insert(DerefEdgeInfo::new(arg_id, param_id, (true, false), false));
                 }
 
-                add_edge(pfg, worklist, arg_id, param_id, span_info.clone());
+                add_edge(pfg, worklist, arg_id, param_id, span_info.clone(), false);
             } else {
                 log::debug!(
                     "ignored arg at caller {:?} callee: {:?}: {:?}",
@@ -515,16 +1430,122 @@ fn add_args_and_ret_edge<'tcx>(
         }
     }
 
+    if tcx.is_closure(call_info.callee_def_id) {
+        add_upvar_edges(pfg, worklist, tcx, caller_cfg, caller, call_info, &callee_id);
+    }
+
     // add edge from callee ret to caller ret
     let dest_local_info = caller_cfg
         .local_infos
         .get(&call_info.destination.local)
         .unwrap();
-    if !dest_local_info.ty.is_unit() {
-        let callee_ret_id =
-            pfg.add_or_update_node(&callee_id, &Place::from(LocalId::from_usize(0)), None);
+    if !dest_local_info.ty.is_unit() && callee_cfg.local_infos.contains_key(&RETURN_PLACE) {
+        let callee_ret_id = pfg.add_or_update_node(&callee_id, &Place::from(RETURN_PLACE), None);
         let ret_id = pfg.add_or_update_node(&caller, &call_info.destination, None);
-        add_edge(pfg, worklist, callee_ret_id, ret_id, span_info.clone());
+        add_edge(pfg, worklist, callee_ret_id, ret_id, span_info.clone(), false);
+    }
+}
+
+// pair each argument with the callee local it is passed to, a closure takes
+// its env as `_1` and the fields of the tupled arguments as `_2..`
+fn get_callee_params<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+) -> Vec<(usize, Operand<'tcx>)> {
+    let mut params = vec![];
+    let is_closure_body =
+        tcx.is_closure(call_info.callee_def_id) && tcx.generator_kind(call_info.callee_def_id).is_none();
+    for (i, arg) in call_info.args.iter().enumerate() {
+        if !is_closure_body || i == 0 {
+            params.push((i + 1, arg.clone()));
+            continue;
+        }
+        let tupled_place = match arg {
+            Operand::Move(place) | Operand::Copy(place) => place,
+            Operand::Constant(_) => continue,
+        };
+        let tupled_ty = utils::get_ty_from_place(tcx, caller.def_id, tupled_place);
+        if let rustc_middle::ty::Tuple(field_tys) = tupled_ty.kind() {
+            for (j, field_ty) in field_tys.iter().enumerate() {
+                let field_place =
+                    tcx.mk_place_field(*tupled_place, rustc_middle::mir::Field::from_usize(j), field_ty);
+                let field_arg = match arg {
+                    Operand::Copy(_) => Operand::Copy(field_place),
+                    _ => Operand::Move(field_place),
+                };
+                params.push((i + 1 + j, field_arg));
+            }
+        }
+    }
+    params
+}
+
+// the closure local behind the env argument, eg. `_4` of `_6 = &_4`
+fn get_closure_env_place<'tcx>(caller_cfg: &ControlFlowGraph<'tcx>, env_place: Place<'tcx>) -> Place<'tcx> {
+    let mut env_place = env_place;
+    let mut visited = HashSet::new();
+    while env_place.projection.is_empty() && visited.insert(env_place.local) {
+        let src_place = caller_cfg
+            .basic_block_infos
+            .values()
+            .flat_map(|bb_info| bb_info.assignment_infos.iter())
+            .find_map(|assignment| match (&assignment.op, &assignment.rvalue) {
+                (OpKind::Move | OpKind::Copy | OpKind::Ref, RvalKind::Addressed(src))
+                    if assignment.lvalue == env_place =>
+                {
+                    Some(*src)
+                }
+                _ => None,
+            });
+        match src_place {
+            Some(src_place) => env_place = src_place,
+            None => break,
+        }
+    }
+    env_place
+}
+
+// add edge from each upvar stored in the closure by the caller to the upvar
+// in the closure body, eg. `_4.0` to `(*_1).0`
+fn add_upvar_edges<'tcx>(
+    pfg: &mut PointerFlowGraph<'tcx>,
+    worklist: &mut VecDeque<PointsTo>,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller_cfg: &ControlFlowGraph<'tcx>,
+    caller: &CtxtSenCallId,
+    call_info: &CallInfo<'tcx>,
+    callee_id: &CtxtSenCallId,
+) {
+    let span_info = CtxtSenSpanInfo::new(
+        caller.def_id,
+        call_info.caller_bb_id,
+        call_info.span,
+        CallerContext::new(vec![]),
+    );
+    let env_place = match call_info.args.first() {
+        Some(Operand::Move(place)) | Some(Operand::Copy(place)) => get_closure_env_place(caller_cfg, *place),
+        _ => return,
+    };
+    let upvar_tys = match utils::get_ty_from_place(tcx, caller.def_id, &env_place).kind() {
+        rustc_middle::ty::Closure(_, substs) => substs.as_closure().upvar_tys().collect::<Vec<_>>(),
+        _ => return,
+    };
+
+    // the env is passed by reference to Fn and FnMut closures
+    let callee_env = Place::from(LocalId::from_usize(1));
+    let callee_env = if utils::get_ty_from_place(tcx, callee_id.def_id, &callee_env).is_ref() {
+        tcx.mk_place_deref(callee_env)
+    } else {
+        callee_env
+    };
+
+    for (i, upvar_ty) in upvar_tys.into_iter().enumerate() {
+        let field = rustc_middle::mir::Field::from_usize(i);
+        let upvar_id = pfg.add_or_update_node(caller, &tcx.mk_place_field(env_place, field, upvar_ty), None);
+        let param_upvar_id =
+            pfg.add_or_update_node(callee_id, &tcx.mk_place_field(callee_env, field, upvar_ty), None);
+        add_edge(pfg, worklist, upvar_id, param_upvar_id, span_info.clone(), false);
     }
 }
 
@@ -583,9 +1604,9 @@ fn add_args_to_ret_edge<'tcx>(
                     // arguments are seen as deref
                     if utils::let_arg_as_deref(opts) {
                         pfg.deref_edges
-                            .insert(DerefEdgeInfo::new(arg_id, ret_id, (true, false)));
+                            .insert(DerefEdgeInfo::new(arg_id, ret_id, (true, false), false));
                     }
-                    add_edge(pfg, worklist, arg_id, ret_id, span_info.clone());
+                    add_edge(pfg, worklist, arg_id, ret_id, span_info.clone(), false);
                 } else {
                     log::debug!(
                         "ignored arg at caller {:?} callee: {:?}: {:?}",
@@ -634,6 +1655,117 @@ static ref ARG_TO_RET_DEF_NAMES: Vec<&'static str> = vec![
     "as_bytes_mut",
 ];
 
+// (path prefix, name) of functions that give up the ownership of a box
+static ref BOX_INTO_RAW_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::boxed::", "::into_raw"),
+];
+
+// (path prefix, name) of functions that drop the value behind a pointer
+static ref DROP_IN_PLACE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::ptr::", "::drop_in_place"),
+];
+
+// (path prefix, name) of functions that rebuild an owner from the raw parts of its buffer
+static ref RAW_PARTS_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::vec::", "::from_raw_parts"),
+    ("alloc::string::", "::from_raw_parts"),
+];
+
+// (path prefix, name) of functions that copy the value out of a pointer
+static ref PTR_READ_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::ptr::", "::read"),
+    ("core::ptr::", "::read_unaligned"),
+];
+
+// (path prefix, name) of functions that move a value into a pointer without dropping the old one
+static ref PTR_WRITE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::ptr::", "::write"),
+    ("core::ptr::", "::write_unaligned"),
+];
+
+// (path prefix, name) of functions that move a value into a pointer and return the old one
+static ref REPLACE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::mem::", "::replace"),
+    ("core::ptr::", "::replace"),
+];
+
+// (path prefix, name) of functions that move the value out of a container into the return value,
+// `replace` moves its second arg in
+static ref TAKE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::option::", "::take"),
+    ("core::option::", "::replace"),
+    ("core::cell::", "::take"),
+    ("core::cell::", "::replace"),
+    ("core::cell::", "::into_inner"),
+    ("core::mem::", "::take"),
+];
+
+// (path prefix, name) of functions that reinterpret the bits of the arg as the return value
+static ref TRANSMUTE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::intrinsics::", "::transmute"),
+];
+
+// (path prefix, name) of functions that allocate a box
+static ref ALLOC_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::alloc::", "::exchange_malloc"),
+    ("alloc::boxed::", "::new"),
+];
+
+// (path prefix, name) of functions that take the ownership of a value without dropping it
+static ref FORGET_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::mem::", "::forget"),
+];
+
+// (path prefix, name) of functions that create an rc owning the value
+static ref RC_NEW_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::rc::", "::new"),
+    ("alloc::sync::", "::new"),
+];
+
+// (path prefix, name) of functions that clone an rc handle, the trait method is called for a generic clone
+static ref RC_CLONE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::clone::", "::clone"),
+    ("alloc::rc::", "::clone"),
+    ("alloc::sync::", "::clone"),
+];
+
+// (path prefix, name) of functions that create a weak pointer from a strong one
+static ref WEAK_DOWNGRADE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::rc::", "::downgrade"),
+    ("alloc::sync::", "::downgrade"),
+];
+
+// (path prefix, name) of functions that try to get a strong pointer back from a weak one
+static ref WEAK_UPGRADE_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("alloc::rc::", "::upgrade"),
+    ("alloc::sync::", "::upgrade"),
+];
+
+// (path prefix, name) of functions that borrow a container by a guard
+static ref GUARD_BORROW_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::cell::", "::borrow"),
+    ("core::cell::", "::borrow_mut"),
+    ("std::sync::", "::lock"),
+    ("std::sync::", "::read"),
+    ("std::sync::", "::write"),
+];
+
+// (path prefix, name) of functions that get the data behind a guard
+static ref GUARD_DEREF_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::ops::deref::", "::deref"),
+    ("core::ops::deref::", "::deref_mut"),
+];
+
+// (path prefix, name) of functions that consume an iterator
+static ref ITER_NEXT_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::iter::", "::next"),
+];
+
+// (path prefix, name) of functions that assume an option is `Some` without checking
+static ref UNCHECKED_UNWRAP_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::option::", "::unwrap_unchecked"),
+];
+
 // ignore defs, eg. clone()
 static ref IGNORE_DEF_NAMES: Vec<&'static str> = vec![
     "clone",
@@ -1,14 +1,26 @@
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::terminator::TerminatorKind;
+use rustc_middle::mir::visit::{MutVisitor, NonMutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::AggregateKind;
+use rustc_middle::mir::BinOp;
+use rustc_middle::mir::BorrowKind;
+use rustc_middle::mir::CastKind;
+use rustc_middle::mir::InlineAsmOperand;
+use rustc_middle::mir::Location;
+use rustc_middle::mir::Mutability;
 use rustc_middle::mir::Operand;
 use rustc_middle::mir::Place;
+use rustc_middle::mir::PlaceElem;
 use rustc_middle::mir::ProjectionElem;
 use rustc_middle::mir::Rvalue;
 use rustc_middle::mir::StatementKind;
+use rustc_middle::mir::START_BLOCK;
 use rustc_middle::ty;
 use rustc_middle::ty::TyKind;
 use rustc_span::Span;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use itertools::Itertools;
 
 use crate::core::utils;
 
@@ -34,17 +46,93 @@ pub struct ControlFlowGraph<'tcx> {
     pub basic_block_infos: HashMap<BasicBlockId, BasicBlockInfo<'tcx>>,
     pub call_infos: HashMap<BasicBlockId, CallInfo<'tcx>>,
     pub ret_bbs: HashSet<BasicBlockId>,
+    // blocks only reached by unwinding from a failed assertion, the cleanups of which are removed from the successors
+    pub assert_failure_bbs: HashSet<BasicBlockId>,
+    // drops reached again through a loop without reinitializing the dropped place
+    pub loop_drop_bbs: HashSet<BasicBlockId>,
+    // bug kinds allowed by `#[mc::allow(..)]` on the fn, or on the fn enclosing the closure
+    pub allowed_bugs: HashSet<String>,
+    // integers holding an address exposed from a pointer, and the integers computed from them
+    pub address_locals: HashSet<LocalId>,
+    // the assigns of a pointer or from a pointer the pointer flow of which is not modeled, with their rvalue kinds
+    pub unmodeled_assigns: Vec<(Span, &'static str)>,
     pub is_local_crate: bool,
 }
 
 impl<'tcx> ControlFlowGraph<'tcx> {
+    // textual dump for `--print-cfg`, blocks and locals are sorted by id
+    pub fn to_readable_string(&self, tcx: rustc_middle::ty::TyCtxt<'tcx>) -> String {
+        let mut lines = vec![format!("control flow graph of fn {}:", tcx.def_path_str(self.def_id))];
+
+        lines.push("locals:".to_string());
+        for local in self.local_infos.keys().sorted() {
+            let local_info = self.local_infos.get(local).unwrap();
+            let var_name = match &local_info.var_name {
+                Some(var_name) => format!(" ({})", var_name),
+                None => String::new(),
+            };
+            let need_drop = if local_info.need_drop { ", need drop" } else { "" };
+            lines.push(format!("    {:?}: {}{}{}", local, local_info.ty, var_name, need_drop));
+        }
+
+        for bb in self.basic_block_infos.keys().sorted() {
+            let bb_info = self.basic_block_infos.get(bb).unwrap();
+            let mut flags = vec![];
+            if bb_info.is_cleanup {
+                flags.push("cleanup");
+            }
+            if self.ret_bbs.contains(bb) {
+                flags.push("return");
+            }
+            if self.assert_failure_bbs.contains(bb) {
+                flags.push("assert failure");
+            }
+            if self.loop_drop_bbs.contains(bb) {
+                flags.push("loop drop");
+            }
+            if flags.is_empty() {
+                lines.push(format!("{:?}:", bb));
+            } else {
+                lines.push(format!("{:?} ({}):", bb, flags.join(", ")));
+            }
+
+            for assign in bb_info.assignment_infos.iter() {
+                let rvalue = match &assign.rvalue {
+                    RvalKind::Constant => "const".to_string(),
+                    RvalKind::Addressed(place) => format!("{:?}", place),
+                };
+                lines.push(format!(
+                    "    assign: {:?} = {:?} {} at {:?}",
+                    assign.lvalue, assign.op, rvalue, assign.stat_span
+                ));
+            }
+            if let Some(call_info) = self.call_infos.get(bb) {
+                lines.push(format!(
+                    "    call: {:?} = {}({}) at {:?}",
+                    call_info.destination,
+                    tcx.def_path_str(call_info.callee_def_id),
+                    call_info.args.iter().map(|arg| format!("{:?}", arg)).join(", "),
+                    call_info.span
+                ));
+            }
+            lines.push(format!("    terminator: {:?}", bb_info.terminator.kind));
+            lines.push(format!(
+                "    successors: [{}]",
+                bb_info.successors.iter().sorted().map(|succ| format!("{:?}", succ)).join(", ")
+            ));
+        }
+
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
     pub fn new(
         opts: &AnalysisOptions,
         tcx: rustc_middle::ty::TyCtxt<'tcx>,
         def_id: rustc_hir::def_id::DefId,
         is_local_crate: bool,
     ) -> Self {
-        let body: &rustc_middle::mir::Body = tcx.optimized_mir(def_id);
+        let body: &rustc_middle::mir::Body = &resolve_constant_indices(tcx, def_id, utils::get_mir_body(tcx, def_id));
         if utils::has_dbg(opts, "body") {
             log::debug!("body of def id {:?}: \n{:#?}", def_id, body);
         }
@@ -81,6 +169,9 @@ impl<'tcx> ControlFlowGraph<'tcx> {
             }
         }
 
+        let address_locals = get_address_locals(body);
+        let unmodeled_assigns = get_unmodeled_assigns(tcx, body, &address_locals);
+
         let call_infos = body
             .basic_blocks()
             .iter_enumerated()
@@ -109,8 +200,12 @@ impl<'tcx> ControlFlowGraph<'tcx> {
                                 // let target_body = tcx.optimized_mir(*target_id);
                                 // log::debug!("target body: {:#?}", target_body);
                             }
-                            let callee_def_id = *target_id;
-                            let call_info = CallInfo::new(
+                            let (callee_def_id, impl_callee_def_ids) =
+                                match get_closure_callee(tcx, *target_id, constant.literal.ty()) {
+                                    Some(closure_id) => (closure_id, vec![]),
+                                    None => resolve_trait_callee(tcx, def_id, *target_id, constant.literal.ty()),
+                                };
+                            let mut call_info = CallInfo::new(
                                 callee_def_id,
                                 bb,
                                 func.clone(),
@@ -118,6 +213,7 @@ impl<'tcx> ControlFlowGraph<'tcx> {
                                 destination.clone(),
                                 terminator.source_info.span,
                             );
+                            call_info.impl_callee_def_ids = impl_callee_def_ids;
                             return Some((bb, call_info));
                             // }
                         }
@@ -141,14 +237,41 @@ impl<'tcx> ControlFlowGraph<'tcx> {
             .flatten()
             .collect::<HashSet<BasicBlockId>>();
 
+        let mut assert_cleanups = HashSet::new();
         let basic_block_infos = body
             .basic_blocks()
             .iter_enumerated()
             .map(|(bb, bb_data)| {
-                let successors =
-                    get_basic_block_successors(&opts, &bb_data.terminator.as_ref().unwrap().kind);
+                let terminator = bb_data.terminator.as_ref().unwrap();
+                let mut successors = get_basic_block_successors(&opts, &terminator.kind);
+                // the cleanup of a failed assertion is dead under panic=abort, and the drops
+                // in it only confuse users, so we never unwind from an assertion
+                if let TerminatorKind::Call {
+                    target: None,
+                    cleanup: Some(cleanup),
+                    ..
+                } = terminator.kind
+                {
+                    if utils::get_assert_call_site(terminator.source_info.span).is_some() {
+                        successors.remove(&cleanup);
+                        assert_cleanups.insert(cleanup);
+                    }
+                }
+                // the value moved into `mem::forget` is never dropped, but its cleanup drops it
+                if let TerminatorKind::Call {
+                    func: Operand::Constant(ref constant),
+                    cleanup: Some(cleanup),
+                    ..
+                } = terminator.kind
+                {
+                    if let ty::FnDef(callee_def_id, _) = constant.literal.ty().kind() {
+                        if utils::is_mem_forget(&utils::parse_def_id(*callee_def_id)) {
+                            successors.remove(&cleanup);
+                        }
+                    }
+                }
 
-                let assignment_infos: Vec<AssignmentInfo> = bb_data
+                let mut assignment_infos: Vec<AssignmentInfo> = bb_data
                     .statements
                     .iter()
                     .map(|stat| match stat.kind {
@@ -185,7 +308,7 @@ impl<'tcx> ControlFlowGraph<'tcx> {
                                 log::debug!("");
                             }
 
-                            get_assignment_infos(assign, stat.source_info.span)
+                            get_assignment_infos(tcx, assign, stat.source_info.span, &address_locals)
                         }
                         _ => {
                             log::debug!("ignored non-assign statement: {:?}", stat);
@@ -194,6 +317,32 @@ impl<'tcx> ControlFlowGraph<'tcx> {
                     })
                     .flatten()
                     .collect();
+                // the old value is dropped, then the new value is moved in
+                if let TerminatorKind::DropAndReplace { place, ref value, .. } = terminator.kind {
+                    assignment_infos.extend(get_assignment_infos(
+                        tcx,
+                        &Box::new((place, Rvalue::Use(value.clone()))),
+                        terminator.source_info.span,
+                        &address_locals,
+                    ));
+                }
+                // the outputs of inline asm are freshly written, what a pointer written by asm
+                // points to is unknown, so the dereferences through it are never checked
+                if let TerminatorKind::InlineAsm { ref operands, .. } = terminator.kind {
+                    let span = terminator.source_info.span;
+                    for operand in operands.iter() {
+                        let place = match operand {
+                            InlineAsmOperand::Out { place: Some(place), .. }
+                            | InlineAsmOperand::InOut { out_place: Some(place), .. } => *place,
+                            _ => continue,
+                        };
+                        let ty = place.ty(&body.local_decls, tcx).ty;
+                        if ty.is_unsafe_ptr() || ty.is_ref() {
+                            log::warn!("pointer written by inline asm is not tracked: {:?} at {:?}", place, span);
+                        }
+                        assignment_infos.push(AssignmentInfo::new(place, RvalKind::Constant, span, OpKind::Copy));
+                    }
+                }
 
                 let bb_info = BasicBlockInfo::new(
                     bb,
@@ -206,6 +355,18 @@ impl<'tcx> ControlFlowGraph<'tcx> {
             })
             .collect::<HashMap<_, _>>();
 
+        // the blocks unreachable for another reason, eg. after a call that never returns, are kept
+        let reachable_bbs = get_reachable_bbs(&basic_block_infos, [START_BLOCK]);
+        let assert_failure_bbs = get_reachable_bbs(&basic_block_infos, assert_cleanups)
+            .into_iter()
+            .filter(|bb| !reachable_bbs.contains(bb))
+            .collect();
+
+        let loop_drop_bbs = get_loop_drop_bbs(body, &basic_block_infos);
+        if utils::has_dbg(opts, "loop-drop") {
+            log::debug!("loop drops of def id {:?}: {:?}", def_id, loop_drop_bbs);
+        }
+
         Self {
             options: opts.clone(),
             def_id,
@@ -213,11 +374,300 @@ impl<'tcx> ControlFlowGraph<'tcx> {
             basic_block_infos,
             call_infos,
             ret_bbs,
+            assert_failure_bbs,
+            loop_drop_bbs,
+            allowed_bugs: get_allowed_bugs(tcx, def_id),
+            address_locals,
+            unmodeled_assigns,
             is_local_crate,
         }
     }
 }
 
+// the blocks reached from `starts` through the successors, a synthetic body may have no blocks at all
+pub fn get_reachable_bbs(
+    basic_block_infos: &HashMap<BasicBlockId, BasicBlockInfo>,
+    starts: impl IntoIterator<Item = BasicBlockId>,
+) -> HashSet<BasicBlockId> {
+    let mut reachable_bbs = HashSet::new();
+    let mut bb_work_list = starts.into_iter().collect::<Vec<_>>();
+    while let Some(bb) = bb_work_list.pop() {
+        if let Some(bb_info) = basic_block_infos.get(&bb) {
+            if reachable_bbs.insert(bb) {
+                bb_work_list.extend(bb_info.successors.iter());
+            }
+        }
+    }
+    reachable_bbs
+}
+
+// bug kinds that can be allowed by `#[mc::allow(..)]`
+const ALLOWABLE_BUGS: [&str; 2] = ["use_after_free", "double_free"];
+
+// eg. `#[mc::allow(use_after_free, double_free)]`, a closure shares the attributes of its enclosing fn
+fn get_allowed_bugs(tcx: rustc_middle::ty::TyCtxt<'_>, def_id: DefId) -> HashSet<String> {
+    let mut allowed_bugs = HashSet::new();
+    for attr in tcx.get_attrs_unchecked(tcx.typeck_root_def_id(def_id)) {
+        let is_mc_allow = match &attr.kind {
+            rustc_ast::AttrKind::Normal(item, _) => {
+                item.path.segments.iter().map(|segment| segment.ident.as_str()).eq(["mc", "allow"])
+            }
+            rustc_ast::AttrKind::DocComment(..) => false,
+        };
+        if !is_mc_allow {
+            continue;
+        }
+
+        for meta_item in attr.meta_item_list().unwrap_or_default() {
+            let name = meta_item.name_or_empty();
+            if ALLOWABLE_BUGS.contains(&name.as_str()) {
+                allowed_bugs.insert(name.to_string());
+            } else {
+                tcx.sess.span_warn(
+                    meta_item.span(),
+                    &format!("(memory check) unknown bug kind, expected one of: {}", ALLOWABLE_BUGS.join(", ")),
+                );
+            }
+        }
+    }
+    allowed_bugs
+}
+
+// an index local only assigned a constant is the same element wherever it's used, eg.
+// `_7 = const 0_usize; _6 = &mut _1[_7]`, it's resolved to a `ConstantIndex` so that the
+// elements at distinct constant indices are tracked apart, the other indices stay dynamic.
+// The body is only copied when it has such an index
+fn resolve_constant_indices<'a, 'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    def_id: DefId,
+    body: &'a rustc_middle::mir::Body<'tcx>,
+) -> Cow<'a, rustc_middle::mir::Body<'tcx>> {
+    let mut writes = WriteCounter::default();
+    writes.visit_body(body);
+
+    let param_env = tcx.param_env(def_id);
+    let constant_indices = body
+        .basic_blocks()
+        .iter()
+        .flat_map(|bb_data| bb_data.statements.iter())
+        .filter_map(|stat| match &stat.kind {
+            StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(constant)))) => {
+                let local = place.as_local()?;
+                let is_only_write = local.as_usize() > body.arg_count && writes.counts.get(&local) == Some(&1);
+                if !is_only_write || *constant.ty().kind() != TyKind::Uint(ty::UintTy::Usize) {
+                    return None;
+                }
+                Some((local, constant.literal.try_eval_usize(tcx, param_env)?))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    if constant_indices.is_empty() {
+        return Cow::Borrowed(body);
+    }
+    let mut body = body.clone();
+    ConstantIndexResolver { tcx, constant_indices }.visit_body(&mut body);
+    Cow::Owned(body)
+}
+
+// the writes to each local, taking the address may write it later
+#[derive(Default)]
+struct WriteCounter {
+    counts: HashMap<LocalId, usize>,
+}
+
+impl<'tcx> Visitor<'tcx> for WriteCounter {
+    fn visit_local(&mut self, local: &LocalId, context: PlaceContext, _location: Location) {
+        if context.is_mutating_use() || context == PlaceContext::NonMutatingUse(NonMutatingUseContext::AddressOf) {
+            *self.counts.entry(*local).or_default() += 1;
+        }
+    }
+}
+
+struct ConstantIndexResolver<'tcx> {
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    constant_indices: HashMap<LocalId, u64>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ConstantIndexResolver<'tcx> {
+    fn tcx<'a>(&'a self) -> rustc_middle::ty::TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn process_projection_elem(&mut self, elem: PlaceElem<'tcx>, _location: Location) -> Option<PlaceElem<'tcx>> {
+        match elem {
+            ProjectionElem::Index(local) => {
+                let offset = *self.constant_indices.get(&local)?;
+                Some(ProjectionElem::ConstantIndex {
+                    offset,
+                    min_length: offset + 1,
+                    from_end: false,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+lazy_static! {
+// (path prefix, name) of functions that bitwise copy a value out of a pointer
+static ref BITWISE_READ_DEF_NAMES: Vec<(&'static str, &'static str)> = vec![
+    ("core::ptr::", "::read"),
+    ("core::ptr::", "::read_unaligned"),
+    ("core::ptr::", "::read_volatile"),
+];
+}
+
+// a drop on a cycle frees the same value again in the next iteration, unless the
+// dropped place gets a fresh value on the cycle
+fn get_loop_drop_bbs<'tcx>(
+    body: &rustc_middle::mir::Body<'tcx>,
+    basic_block_infos: &HashMap<BasicBlockId, BasicBlockInfo<'tcx>>,
+) -> HashSet<BasicBlockId> {
+    let mut predecessors = HashMap::<BasicBlockId, Vec<BasicBlockId>>::new();
+    for (bb, bb_info) in basic_block_infos.iter() {
+        for succ in bb_info.successors.iter() {
+            predecessors.entry(*succ).or_default().push(*bb);
+        }
+    }
+    let reach = |start: BasicBlockId, next: &dyn Fn(BasicBlockId) -> Vec<BasicBlockId>| {
+        let mut visited = HashSet::new();
+        let mut work_list = next(start);
+        while let Some(bb) = work_list.pop() {
+            if visited.insert(bb) {
+                work_list.extend(next(bb));
+            }
+        }
+        visited
+    };
+
+    let mut loop_drop_bbs = HashSet::new();
+    for (bb, bb_info) in basic_block_infos.iter() {
+        let place = match bb_info.terminator.kind {
+            TerminatorKind::Drop { ref place, .. } if !bb_info.is_cleanup => place,
+            _ => continue,
+        };
+        if place.projection.contains(&ProjectionElem::Deref) {
+            continue;
+        }
+
+        let forward = reach(*bb, &|bb| {
+            basic_block_infos.get(&bb).unwrap().successors.iter().copied().collect()
+        });
+        if !forward.contains(bb) {
+            continue;
+        }
+        let backward = reach(*bb, &|bb| predecessors.get(&bb).cloned().unwrap_or_default());
+        let cycle = LoopBody {
+            body,
+            bbs: forward.intersection(&backward).copied().collect(),
+        };
+
+        if !cycle.is_fresh_local(place.local, &mut HashSet::new()) {
+            loop_drop_bbs.insert(*bb);
+        }
+    }
+    loop_drop_bbs
+}
+
+enum LocalWrite<'a, 'tcx> {
+    Assign(&'a Rvalue<'tcx>),
+    Call(DefId, &'a [Operand<'tcx>]),
+    Other,
+}
+
+struct LoopBody<'a, 'tcx> {
+    body: &'a rustc_middle::mir::Body<'tcx>,
+    bbs: HashSet<BasicBlockId>,
+}
+
+impl<'a, 'tcx> LoopBody<'a, 'tcx> {
+    // the writes to the whole local, writes through a pointer in it don't count
+    fn get_writes(&self, local: LocalId) -> Vec<LocalWrite<'a, 'tcx>> {
+        let mut writes = vec![];
+        for bb in self.bbs.iter() {
+            let bb_data = &self.body.basic_blocks()[*bb];
+            for stat in bb_data.statements.iter() {
+                if let StatementKind::Assign(box (ref lvalue, ref rvalue)) = stat.kind {
+                    if lvalue.local == local && !lvalue.projection.contains(&ProjectionElem::Deref) {
+                        writes.push(LocalWrite::Assign(rvalue));
+                    }
+                }
+            }
+            if let TerminatorKind::Call {
+                ref func,
+                ref args,
+                ref destination,
+                ..
+            } = bb_data.terminator().kind
+            {
+                if destination.local == local
+                    && !destination.projection.contains(&ProjectionElem::Deref)
+                {
+                    match func.const_fn_def() {
+                        Some((callee_def_id, _)) => writes.push(LocalWrite::Call(callee_def_id, args)),
+                        None => writes.push(LocalWrite::Other),
+                    }
+                }
+            }
+        }
+        writes
+    }
+
+    // the local holds a new value in every iteration
+    fn is_fresh_local(&self, local: LocalId, visited: &mut HashSet<LocalId>) -> bool {
+        if !visited.insert(local) {
+            return false;
+        }
+        self.get_writes(local).into_iter().any(|write| match write {
+            LocalWrite::Assign(Rvalue::Use(Operand::Copy(src) | Operand::Move(src)))
+            | LocalWrite::Assign(Rvalue::Cast(_, Operand::Copy(src) | Operand::Move(src), _)) => {
+                !src.projection.contains(&ProjectionElem::Deref)
+                    && self.is_fresh_local(src.local, visited)
+            }
+            LocalWrite::Call(callee_def_id, args) => {
+                let def_name = utils::parse_def_id(callee_def_id).join("::");
+                let is_bitwise_read = utils::is_def_named(&def_name, &BITWISE_READ_DEF_NAMES);
+                !(is_bitwise_read
+                    && args
+                        .first()
+                        .map_or(false, |arg| self.is_invariant_operand(arg, &mut HashSet::new())))
+            }
+            _ => true,
+        })
+    }
+
+    // the operand has the same value in every iteration
+    fn is_invariant_operand(&self, op: &Operand<'tcx>, visited: &mut HashSet<LocalId>) -> bool {
+        match op {
+            Operand::Copy(place) | Operand::Move(place) => {
+                !place.projection.contains(&ProjectionElem::Deref)
+                    && self.is_invariant_local(place.local, visited)
+            }
+            Operand::Constant(_) => true,
+        }
+    }
+
+    fn is_invariant_local(&self, local: LocalId, visited: &mut HashSet<LocalId>) -> bool {
+        if !visited.insert(local) {
+            return true;
+        }
+        self.get_writes(local).into_iter().all(|write| match write {
+            LocalWrite::Assign(Rvalue::Use(op) | Rvalue::Cast(_, op, _)) => {
+                self.is_invariant_operand(op, visited)
+            }
+            // the address of a local never changes
+            LocalWrite::Assign(Rvalue::Ref(_, _, src) | Rvalue::AddressOf(_, src)) => {
+                !src.projection.iter().any(|elem| {
+                    matches!(elem, ProjectionElem::Deref | ProjectionElem::Index(_))
+                })
+            }
+            _ => false,
+        })
+    }
+}
+
 fn get_basic_block_successors(
     opts: &AnalysisOptions,
     terminator_kind: &TerminatorKind,
@@ -352,9 +802,89 @@ fn get_rvalue_name<'tcx>(rvalue: &Rvalue<'tcx>) -> String {
     }
 }
 
+// eg. `_7 = move _8 as usize`, `_9 = CheckedAdd(move _7, const 4_usize)`, `_6 = move (_9.0: usize)`
+fn get_address_locals(body: &rustc_middle::mir::Body) -> HashSet<LocalId> {
+    let assigns = body
+        .basic_blocks()
+        .iter()
+        .flat_map(|bb_data| bb_data.statements.iter())
+        .filter_map(|stat| match &stat.kind {
+            StatementKind::Assign(assign) => Some(&**assign),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut address_locals = HashSet::new();
+    loop {
+        let len = address_locals.len();
+        for (place, rvalue) in assigns.iter() {
+            let is_address = |op: &Operand| op.place().map_or(false, |place| address_locals.contains(&place.local));
+            let holds_address = match rvalue {
+                Rvalue::Cast(CastKind::PointerExposeAddress, _, _) => true,
+                Rvalue::Use(op) | Rvalue::Cast(CastKind::Misc, op, _) => is_address(op),
+                Rvalue::BinaryOp(bin_op, ops) | Rvalue::CheckedBinaryOp(bin_op, ops) => {
+                    is_address_arithmetic(*bin_op) && (is_address(&ops.0) || is_address(&ops.1))
+                }
+                _ => false,
+            };
+            if holds_address {
+                address_locals.insert(place.local);
+            }
+        }
+        if address_locals.len() == len {
+            return address_locals;
+        }
+    }
+}
+
+// eg. `_3 = Len((*_2))`, `_5 = Eq(move _6, move _7)` on pointers, the pointer flow and the uses
+// through the pointers stop at these assigns
+fn get_unmodeled_assigns<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    body: &rustc_middle::mir::Body<'tcx>,
+    address_locals: &HashSet<LocalId>,
+) -> Vec<(Span, &'static str)> {
+    let carries_pointer = |op: &Operand<'tcx>| {
+        let ty = op.ty(&body.local_decls, tcx);
+        ty.is_unsafe_ptr() || ty.is_ref() || op.place().map_or(false, |place| address_locals.contains(&place.local))
+    };
+    body.basic_blocks()
+        .iter()
+        .flat_map(|bb_data| bb_data.statements.iter())
+        .filter_map(|stat| {
+            let assign = match &stat.kind {
+                StatementKind::Assign(assign) => assign,
+                _ => return None,
+            };
+            let kind = match &assign.1 {
+                Rvalue::ThreadLocalRef(_) => "ThreadLocalRef",
+                Rvalue::Len(place) if place.projection.contains(&ProjectionElem::Deref) => "Len",
+                Rvalue::BinaryOp(_, ops) if carries_pointer(&ops.0) || carries_pointer(&ops.1) => "BinaryOp",
+                Rvalue::CheckedBinaryOp(_, ops) if carries_pointer(&ops.0) || carries_pointer(&ops.1) => {
+                    "CheckedBinaryOp"
+                }
+                Rvalue::UnaryOp(_, op) if carries_pointer(op) => "UnaryOp",
+                _ => return None,
+            };
+            // the arithmetic on an address is modeled
+            if !get_assignment_infos(tcx, assign, stat.source_info.span, address_locals).is_empty() {
+                return None;
+            }
+            Some((stat.source_info.span, kind))
+        })
+        .collect()
+}
+
+// the arithmetic moving or tagging an address keeps pointing into the same object
+fn is_address_arithmetic(bin_op: BinOp) -> bool {
+    matches!(bin_op, BinOp::Add | BinOp::Sub | BinOp::BitAnd | BinOp::BitOr | BinOp::Offset)
+}
+
 fn get_assignment_infos<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
     assign: &Box<(Place<'tcx>, Rvalue<'tcx>)>,
     span: Span,
+    address_locals: &HashSet<LocalId>,
 ) -> Vec<AssignmentInfo<'tcx>> {
     match assign.1 {
         Rvalue::Use(ref op) => match op {
@@ -387,22 +917,26 @@ fn get_assignment_infos<'tcx>(
             }
         },
         // eg. _2 = &_1
-        Rvalue::Ref(_, _, ref rvalue) => {
-            vec![AssignmentInfo::new(
+        Rvalue::Ref(_, borrow_kind, ref rvalue) => {
+            let mut assignment_info = AssignmentInfo::new(
                 assign.0,
                 RvalKind::Addressed(*rvalue),
                 span,
                 OpKind::Ref,
-            )]
+            );
+            assignment_info.is_mut = matches!(borrow_kind, BorrowKind::Mut { .. });
+            vec![assignment_info]
         }
         // eg. _14 = &raw const (*_15), let i = &x as *const i32
-        Rvalue::AddressOf(_, ref rvalue) => {
-            vec![AssignmentInfo::new(
+        Rvalue::AddressOf(mutability, ref rvalue) => {
+            let mut assignment_info = AssignmentInfo::new(
                 assign.0,
                 RvalKind::Addressed(*rvalue),
                 span,
                 OpKind::AddressOf,
-            )]
+            );
+            assignment_info.is_mut = mutability == Mutability::Mut;
+            vec![assignment_info]
         }
         // eg. a = vec![1, 2]
         // _6 = alloc::alloc::exchange_malloc(..), _7 = ShallowInitBox(move _6)
@@ -459,6 +993,30 @@ fn get_assignment_infos<'tcx>(
                 )]
             }
         },
+        // eg. _1 = [move _2, move _3], each element goes to its own index
+        Rvalue::Aggregate(box AggregateKind::Array(_), ref ops) => ops
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let elem = ProjectionElem::ConstantIndex {
+                    offset: i as u64,
+                    min_length: ops.len() as u64,
+                    from_end: false,
+                };
+                let place = tcx.mk_place_elem(assign.0, elem);
+                match op {
+                    Operand::Copy(ref rvalue) => {
+                        AssignmentInfo::new(place, RvalKind::Addressed(*rvalue), span, OpKind::Copy)
+                    }
+                    Operand::Move(ref rvalue) => {
+                        AssignmentInfo::new(place, RvalKind::Addressed(*rvalue), span, OpKind::Move)
+                    }
+                    Operand::Constant(ref _constant) => {
+                        AssignmentInfo::new(place, RvalKind::Constant, span, OpKind::Copy)
+                    }
+                }
+            })
+            .collect(),
         Rvalue::Aggregate(_, ref ops) => ops
             .iter()
             .map(|op| match op {
@@ -526,6 +1084,42 @@ fn get_assignment_infos<'tcx>(
             log::debug!("unhandled assign: {:?} in span {:?}", assign, span);
             vec![]
         }
+        // eg. Offset(move _11, const 1_isize), CheckedAdd(move _7, const 4_usize) on an address,
+        // the result points to the object of the base pointer
+        Rvalue::BinaryOp(bin_op, ref ops) | Rvalue::CheckedBinaryOp(bin_op, ref ops)
+            if is_address_arithmetic(bin_op) =>
+        {
+            let bases = match bin_op {
+                BinOp::Offset => vec![&ops.0],
+                _ => vec![&ops.0, &ops.1],
+            };
+            bases
+                .into_iter()
+                .filter_map(|op| match op {
+                    Operand::Copy(ref rvalue)
+                        if bin_op == BinOp::Offset || address_locals.contains(&rvalue.local) =>
+                    {
+                        Some(AssignmentInfo::new(
+                            assign.0,
+                            RvalKind::Addressed(*rvalue),
+                            span,
+                            OpKind::Copy,
+                        ))
+                    }
+                    Operand::Move(ref rvalue)
+                        if bin_op == BinOp::Offset || address_locals.contains(&rvalue.local) =>
+                    {
+                        Some(AssignmentInfo::new(
+                            assign.0,
+                            RvalKind::Addressed(*rvalue),
+                            span,
+                            OpKind::Move,
+                        ))
+                    }
+                    _ => None,
+                })
+                .collect()
+        }
         // eg. Gt(move _4, move _5)
         Rvalue::BinaryOp(_, _) => {
             log::debug!("unhandled assign: {:?} in span {:?}", assign, span);
@@ -547,6 +1141,71 @@ fn get_assignment_infos<'tcx>(
     }
 }
 
+// calls of Fn/FnMut/FnOnce and Generator::resume on a closure or generator type
+// go to its body, which is then analyzed like any other callee
+pub fn get_closure_callee<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    target_id: DefId,
+    func_ty: ty::Ty<'tcx>,
+) -> Option<DefId> {
+    let substs = match func_ty.kind() {
+        ty::FnDef(_, substs) => substs,
+        _ => return None,
+    };
+    let trait_id = tcx.trait_of_item(target_id)?;
+    let is_fn_trait = tcx.fn_trait_kind_from_lang_item(trait_id).is_some();
+    let is_gen_trait = tcx.lang_items().gen_trait() == Some(trait_id);
+    match substs.types().next()?.kind() {
+        ty::Closure(closure_id, _) if is_fn_trait => Some(*closure_id),
+        ty::Generator(generator_id, _, _) if is_gen_trait => Some(*generator_id),
+        _ => None,
+    }
+}
+
+// a call of a method of a local trait goes to the method of the impl when the receiver type is
+// known, a call on a `dyn Trait` or a type parameter may go to the method of any impl in the local
+// crate (class hierarchy analysis), the traits of other crates are left to the models of their methods
+pub fn resolve_trait_callee<'tcx>(
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    caller_def_id: DefId,
+    target_id: DefId,
+    func_ty: ty::Ty<'tcx>,
+) -> (DefId, Vec<DefId>) {
+    let substs = match func_ty.kind() {
+        ty::FnDef(_, substs) => substs,
+        _ => return (target_id, vec![]),
+    };
+    let trait_id = match tcx.trait_of_item(target_id) {
+        Some(trait_id) if trait_id.is_local() => trait_id,
+        _ => return (target_id, vec![]),
+    };
+    let param_env = tcx.param_env_reveal_all_normalized(caller_def_id);
+    match ty::Instance::resolve(tcx, param_env, target_id, substs) {
+        Ok(Some(ty::Instance { def: ty::InstanceDef::Item(def), .. })) => (def.did, vec![]),
+        Ok(Some(ty::Instance { def: ty::InstanceDef::Virtual(..), .. })) | Ok(None) => {
+            (target_id, get_impl_callees(tcx, trait_id, target_id))
+        }
+        _ => (target_id, vec![]),
+    }
+}
+
+// the method of each impl of the trait, the default one for the impls not overriding it
+fn get_impl_callees(tcx: rustc_middle::ty::TyCtxt<'_>, trait_id: DefId, trait_item_id: DefId) -> Vec<DefId> {
+    let has_default = tcx.associated_item(trait_item_id).defaultness.has_value();
+    let mut callees = tcx
+        .all_impls(trait_id)
+        .filter_map(|impl_id| {
+            tcx.impl_item_implementor_ids(impl_id)
+                .get(&trait_item_id)
+                .copied()
+                .or_else(|| has_default.then(|| trait_item_id))
+        })
+        .collect::<Vec<_>>();
+    callees.sort();
+    callees.dedup();
+    callees
+}
+
 pub fn try_create_cfg<'tcx>(
     opts: &AnalysisOptions,
     tcx: rustc_middle::ty::TyCtxt<'tcx>,
@@ -555,8 +1214,10 @@ pub fn try_create_cfg<'tcx>(
 ) -> Option<ControlFlowGraph<'tcx>> {
     if is_local_crate {
         if let Some(other) = tcx.hir().body_const_context(def_id.expect_local()) {
-            log::debug!("ignore const context of def id {:?}: {:?}", def_id, other);
-            return None;
+            if !utils::analyze_const_eval(opts) {
+                log::debug!("ignore const context of def id {:?}: {:?}", def_id, other);
+                return None;
+            }
         }
     }
 
@@ -565,6 +1226,9 @@ pub fn try_create_cfg<'tcx>(
         if utils::has_dbg(&opts, "cfg") {
             log::debug!("control flow graph of def id {:?}: {:#?}", def_id, cfg);
         }
+        if utils::should_print_cfg(opts, def_id) {
+            print!("{}", cfg.to_readable_string(tcx));
+        }
         Some(cfg)
     } else {
         log::debug!("MIR is unavailable for def id {:?}", def_id);
@@ -574,13 +1238,11 @@ pub fn try_create_cfg<'tcx>(
 
 pub fn add_called_info(_opts: &AnalysisOptions, called_infos: &mut HashMap<DefId, HashSet<GlobalBasicBlockId>>, cfg: &ControlFlowGraph) {
     for (bb, call_info) in cfg.call_infos.iter() {
-        if !called_infos.contains_key(&call_info.callee_def_id) {
-            called_infos.insert(call_info.callee_def_id, HashSet::new());
-        }
-        let called_info = called_infos.get_mut(&call_info.callee_def_id).unwrap();
-        
-        for successor in cfg.basic_block_infos.get(bb).unwrap().successors.iter() {
-            called_info.insert(GlobalBasicBlockId::new(cfg.def_id, *successor));
+        for callee_def_id in call_info.callees() {
+            let called_info = called_infos.entry(callee_def_id).or_default();
+            for successor in cfg.basic_block_infos.get(bb).unwrap().successors.iter() {
+                called_info.insert(GlobalBasicBlockId::new(cfg.def_id, *successor));
+            }
         }
     }
 }
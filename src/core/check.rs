@@ -1,33 +1,150 @@
 use colored::Colorize;
 use rustc_hir::def_id::DefId;
+use rustc_middle::mir::tcx::PlaceTy;
+use rustc_middle::mir::visit::{MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::{Body, Operand, Place, PlaceElem, Rvalue, StatementKind, TerminatorKind, RETURN_PLACE};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
 use rustc_span::Span;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
 use termcolor::Color;
 
 use super::cfg::ControlFlowGraph;
-use super::{CtxtSenSpanInfo, DropObjectId, GlobalBasicBlockId, GlobalProjectionId};
+use super::pfg::ProjectionNeighborInfo;
+use super::{
+    AnalysisOptions, BasicBlockId, BasicBlockInfo, CallerContext, LocalId, RvalKind, CtxtSenSpanInfo, DropObjectId, GlobalBasicBlockId, GlobalLocalId,
+    GlobalProjectionId,
+};
 use crate::core::analysis::AnalysisContext;
 use crate::core::utils;
+use crate::core::utils::ReachabilityCache;
 use itertools::Itertools;
 
 pub fn check_memory_bug(ctxt: &AnalysisContext) -> CheckInfo {
     let mut check_info = CheckInfo::new();
+    // the checks query many of the same block pairs
+    let reach = ReachabilityCache::new(&ctxt.cfgs, &ctxt.called_infos);
+
+    check_info.uaf_infos = check_uaf(ctxt, &reach);
+    check_info.df_infos = check_df(ctxt, &reach);
+    check_info.df_infos.extend(check_loop_df(ctxt));
+    check_info.df_infos.extend(check_field_drop(ctxt, &reach));
+    check_info.df_infos.extend(check_manual_drop_df(ctxt, &reach));
+    check_info.df_infos.extend(check_raw_parts_df(ctxt, &reach));
+    check_info.dp_infos = check_dp(ctxt);
+
+    let (box_df_infos, leak_infos) = check_box_ownership(ctxt, &reach);
+    check_info.df_infos.extend(box_df_infos);
+    check_info.leak_infos = leak_infos;
+    check_info.leak_infos.extend(check_alloc_leak(ctxt));
+
+    if utils::model_rc_weak(&ctxt.options) {
+        check_info.uaf_infos.extend(check_weak_upgrade(ctxt, &reach));
+    }
+
+    if utils::check_guard_escape(&ctxt.options) {
+        check_info.uaf_infos.extend(check_guard_escape(ctxt, &reach));
+    }
+
+    if utils::check_iter_escape(&ctxt.options) {
+        check_info.uaf_infos.extend(check_iter_escape(ctxt, &reach));
+    }
+
+    if utils::check_alias_assign(&ctxt.options) {
+        check_info.alias_assign_infos = check_alias_assign(ctxt);
+    }
+
+    if utils::check_ffi_callback(&ctxt.options) {
+        check_info.ffi_callback_infos = check_ffi_callback(ctxt, &reach);
+    }
+
+    if utils::check_rc_cycle(&ctxt.options) {
+        check_info.rc_cycle_infos = check_rc_cycle(ctxt);
+    }
+
+    if utils::check_use_after_move(&ctxt.options) {
+        check_info.use_after_move_infos = check_use_after_move(ctxt);
+    }
 
-    check_info.uaf_infos = check_uaf(&ctxt);
-    check_info.df_infos = check_df(&ctxt);
+    // allowed by `#[mc::allow(..)]` on the fn of either span, not on the entry
+    let is_allowed = |span_info: &CtxtSenSpanInfo, kind: &str| {
+        ctxt.cfgs
+            .get(&span_info.def_id)
+            .map_or(false, |cfg| cfg.allowed_bugs.contains(kind))
+    };
+    check_info.uaf_infos.retain(|info| {
+        !is_allowed(&info.deref_span, "use_after_free") && !is_allowed(&info.drop_span, "use_after_free")
+    });
+    check_info.df_infos.retain(|info| {
+        !is_allowed(&info.first_drop_span, "double_free") && !is_allowed(&info.then_drop_span, "double_free")
+    });
 
     check_info
 }
 
 // TODO: ensure all defids come from local
-pub fn merge_check_info(
-    cfgs: &HashMap<DefId, ControlFlowGraph>,
+pub fn merge_check_info<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    options: &AnalysisOptions,
+    cfgs: &HashMap<DefId, ControlFlowGraph<'tcx>>,
     check_infos: &HashMap<DefId, CheckInfo>, // checkinfo entry from the key: defid
 ) -> CheckResult {
-    let get_var_name = |proj_id: GlobalProjectionId| {
-        let cfg = cfgs.get(&proj_id.g_local_id.def_id).unwrap();
-        let local_info = cfg.local_infos.get(&proj_id.g_local_id.local_id).unwrap();
-        local_info.var_name.clone()
+    let get_local_name = |g_local_id: GlobalLocalId| {
+        // the fn may have no cfg, then its variables have no names
+        cfgs.get(&g_local_id.def_id)
+            .and_then(|cfg| cfg.local_infos.get(&g_local_id.local_id))
+            .and_then(|local_info| local_info.var_name.clone())
+    };
+    let get_var_name = |proj_id: GlobalProjectionId| get_local_name(proj_id.g_local_id);
+    // resolve the enclosing function before collapsing to span
+    let get_fn_path = |span_info: &CtxtSenSpanInfo| tcx.def_path_str(span_info.def_id);
+    // spans inside assertions point at the assertion
+    let get_span = |span_info: &CtxtSenSpanInfo| utils::get_user_span(span_info.span);
+    // the call sites of the calling context, outermost first
+    let get_call_path = |span_info: &CtxtSenSpanInfo| {
+        span_info
+            .caller_context
+            .g_bb_ids
+            .iter()
+            .filter_map(|g_bb_id| {
+                let call_info = cfgs.get(&g_bb_id.def_id)?.call_infos.get(&g_bb_id.bb_id)?;
+                Some(UnitResult::new(
+                    utils::get_user_span(call_info.span),
+                    None,
+                    tcx.def_path_str(g_bb_id.def_id),
+                ))
+            })
+            .collect::<Vec<_>>()
+    };
+    // an allocation in a macro like `vec!` points at the macro call
+    let get_alloc = |span_info: &CtxtSenSpanInfo| {
+        UnitResult::new(span_info.span.source_callsite(), None, get_fn_path(span_info))
+    };
+
+    // only keep findings touching the lines changed since the base ref
+    let changed_lines = utils::changed_only(options).and_then(|base_ref| {
+        match utils::get_changed_lines(base_ref) {
+            Ok(changed_lines) => Some(changed_lines),
+            Err(err) => {
+                output_level_text(
+                    "warning",
+                    &format!("cannot get changes since {}, report all findings: {}", base_ref, err),
+                );
+                None
+            }
+        }
+    });
+    let is_changed = |spans: &[Span]| match &changed_lines {
+        Some(changed_lines) => spans
+            .iter()
+            .any(|span| utils::span_intersects_changed_lines(span, changed_lines)),
+        None => true,
+    };
+    // a finding crossing into a path not excluded is still reported
+    let is_reported = |spans: &[Span]| {
+        is_changed(spans) && !spans.iter().all(|span| utils::is_excluded_path(options, span))
     };
 
 
@@ -48,17 +165,52 @@ pub fn merge_check_info(
         //     continue;
         // }
 
-        let uaf_span = UafSpan::new(uaf_info.deref_span.span, uaf_info.drop_span.span);
-        let uaf_result = UafResult::new(
-            uaf_info.deref_span.span,
+        if !is_reported(&[get_span(&uaf_info.deref_span), get_span(&uaf_info.drop_span)]) {
+            continue;
+        }
+
+        let uaf_span = UafSpan::new(get_span(&uaf_info.deref_span), get_span(&uaf_info.drop_span));
+        let mut uaf_result = UafResult::new(
+            get_span(&uaf_info.deref_span),
             get_var_name(uaf_info.deref_proj_id),
-            uaf_info.drop_span.span,
+            get_fn_path(&uaf_info.deref_span),
+            get_span(&uaf_info.drop_span),
             get_var_name(uaf_info.drop_obj_id.into()),
+            get_fn_path(&uaf_info.drop_span),
         );
+        uaf_result.alias = uaf_info.alias_edge.as_ref().map(|edge| {
+            UnitResult::new(
+                get_span(&edge.span_info),
+                get_var_name(edge.neighbor_id),
+                get_fn_path(&edge.span_info),
+            )
+        });
+        uaf_result.alloc = uaf_info.alloc_span.as_ref().map(get_alloc);
+        if utils::call_path(options) {
+            uaf_result.call_path = get_call_path(&uaf_info.drop_span);
+        }
         if !uaf_results.contains_key(&uaf_span) {
             uaf_results.insert(uaf_span, Some(uaf_result).into_iter().collect());
         } else {
             let uaf_results_with_span = uaf_results.get_mut(&uaf_span).unwrap();
+            // the same bug found in other contexts, keep the one with the pointer creation,
+            // then the one with the shortest call path
+            if let Some(same_result) = uaf_results_with_span
+                .iter()
+                .find(|res| res.is_same_target(&uaf_result))
+                .cloned()
+            {
+                let is_preferred = match (&same_result.alias, &uaf_result.alias) {
+                    (None, Some(_)) => true,
+                    (Some(_), None) => false,
+                    _ => call_path_order(&uaf_result.call_path) < call_path_order(&same_result.call_path),
+                };
+                if is_preferred {
+                    uaf_results_with_span.remove(&same_result);
+                    uaf_results_with_span.insert(uaf_result);
+                }
+                continue;
+            }
             if uaf_result.has_var_name() {
                 // the first does not have var name, pop it
                 if uaf_results_with_span.len() == 1
@@ -89,17 +241,50 @@ pub fn merge_check_info(
         //     continue;
         // }
 
-        let df_span = DfSpan::new(df_info.first_drop_span.span, df_info.then_drop_span.span);
-        let df_result = DfResult::new(
-            df_info.first_drop_span.span,
+        if !is_reported(&[get_span(&df_info.first_drop_span), get_span(&df_info.then_drop_span)]) {
+            continue;
+        }
+
+        let df_span = DfSpan::new(get_span(&df_info.first_drop_span), get_span(&df_info.then_drop_span));
+        let mut df_result = DfResult::new(
+            get_span(&df_info.first_drop_span),
             get_var_name(df_info.first_drop_obj_id.into()),
-            df_info.then_drop_span.span,
+            get_fn_path(&df_info.first_drop_span),
+            get_span(&df_info.then_drop_span),
             get_var_name(df_info.then_drop_obj_id.into()),
+            get_fn_path(&df_info.then_drop_span),
         );
+        df_result.points_to_count = df_info.points_to_count;
+        df_result.alias_path = df_info
+            .alias_path
+            .iter()
+            .map(|edge| {
+                UnitResult::new(
+                    get_span(&edge.span_info),
+                    get_var_name(edge.neighbor_id),
+                    get_fn_path(&edge.span_info),
+                )
+            })
+            .collect();
+        df_result.alloc = df_info.alloc_span.as_ref().map(get_alloc);
         if !df_results.contains_key(&df_span) {
             df_results.insert(df_span, Some(df_result).into_iter().collect());
         } else {
             let df_results_with_span = df_results.get_mut(&df_span).unwrap();
+            // the same bug found in other contexts, keep the one with most objects
+            if let Some(same_result) = df_results_with_span
+                .iter()
+                .find(|res| res.is_same_target(&df_result))
+                .cloned()
+            {
+                if same_result.points_to_count < df_result.points_to_count
+                    || same_result.alias_path.len() < df_result.alias_path.len()
+                {
+                    df_results_with_span.remove(&same_result);
+                    df_results_with_span.insert(df_result);
+                }
+                continue;
+            }
             if df_result.has_var_name() {
                 // the first does not have var name, pop it
                 if df_results_with_span.len() == 1
@@ -112,6 +297,182 @@ pub fn merge_check_info(
         }
     }
 
+    // handle dp info
+    let mut dp_results = HashMap::<DpSpan, DpResult>::new();
+    let dp_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.dp_infos.iter());
+    for dp_info in dp_iter {
+        let escape_span = get_span(&dp_info.escape_span);
+        let drop_span = get_span(&dp_info.drop_span);
+        if !is_reported(&[escape_span, drop_span]) {
+            continue;
+        }
+
+        let dp_result = DpResult::new(
+            escape_span,
+            get_var_name(dp_info.escape_proj_id),
+            get_fn_path(&dp_info.escape_span),
+            drop_span,
+            get_var_name(dp_info.drop_obj_id.into()),
+            get_fn_path(&dp_info.drop_span),
+        );
+        // prefer the result which has var name
+        match dp_results.get(&DpSpan::new(escape_span, drop_span)) {
+            Some(res) if res.has_var_name() => {}
+            _ => {
+                dp_results.insert(DpSpan::new(escape_span, drop_span), dp_result);
+            }
+        }
+    }
+
+    // handle leak info
+    let mut leak_results = HashMap::<Span, UnitResult>::new();
+    let mut leak_exit_results = HashMap::<Span, UnitResult>::new();
+    let leak_into_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.leak_infos.iter());
+    for leak_info in leak_into_iter {
+        if !is_reported(&[get_span(&leak_info.leak_span)]) {
+            continue;
+        }
+
+        let leak_result = UnitResult::new(
+            get_span(&leak_info.leak_span),
+            get_var_name(leak_info.leak_obj_id.into()),
+            get_fn_path(&leak_info.leak_span),
+        );
+        if let Some(exit_span) = &leak_info.exit_span {
+            leak_exit_results.insert(
+                leak_result.span,
+                UnitResult::new(get_span(exit_span), None, get_fn_path(exit_span)),
+            );
+        }
+        // prefer the result which has var name
+        match leak_results.get(&leak_result.span) {
+            Some(res) if res.var_name.is_some() => {}
+            _ => {
+                leak_results.insert(leak_result.span, leak_result);
+            }
+        }
+    }
+
+    // handle alias assign info
+    let mut alias_assign_results = HashMap::<Span, UnitResult>::new();
+    let alias_assign_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.alias_assign_infos.iter());
+    for alias_assign_info in alias_assign_iter {
+        if !is_reported(&[get_span(&alias_assign_info.assign_span)]) {
+            continue;
+        }
+
+        let alias_assign_result = UnitResult::new(
+            get_span(&alias_assign_info.assign_span),
+            get_var_name(alias_assign_info.ptr_proj_id),
+            get_fn_path(&alias_assign_info.assign_span),
+        );
+        match alias_assign_results.get(&alias_assign_result.span) {
+            Some(res) if res.var_name.is_some() => {}
+            _ => {
+                alias_assign_results.insert(alias_assign_result.span, alias_assign_result);
+            }
+        }
+    }
+
+    // handle ffi callback info
+    let mut ffi_callback_results = HashMap::<(Span, Span), (UnitResult, UnitResult)>::new();
+    let ffi_callback_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.ffi_callback_infos.iter());
+    for ffi_callback_info in ffi_callback_iter {
+        let register_span = get_span(&ffi_callback_info.register_span);
+        let drop_span = get_span(&ffi_callback_info.drop_span);
+        if !is_reported(&[register_span, drop_span]) {
+            continue;
+        }
+
+        let register_result = UnitResult::new(
+            register_span,
+            get_var_name(ffi_callback_info.ctx_proj_id),
+            get_fn_path(&ffi_callback_info.register_span),
+        );
+        let drop_result = UnitResult::new(
+            drop_span,
+            get_var_name(ffi_callback_info.drop_obj_id.into()),
+            get_fn_path(&ffi_callback_info.drop_span),
+        );
+        match ffi_callback_results.get(&(register_span, drop_span)) {
+            Some((reg_res, drop_res)) if reg_res.var_name.is_some() && drop_res.var_name.is_some() => {}
+            _ => {
+                ffi_callback_results.insert((register_span, drop_span), (register_result, drop_result));
+            }
+        }
+    }
+
+    // handle use after move info
+    let mut use_after_move_results = HashMap::<(Span, Span), (UnitResult, UnitResult)>::new();
+    let mut use_after_move_chains = HashMap::<(Span, Span), Vec<UnitResult>>::new();
+    let use_after_move_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.use_after_move_infos.iter());
+    for use_after_move_info in use_after_move_iter {
+        let move_span = get_span(&use_after_move_info.move_span);
+        let use_span = get_span(&use_after_move_info.use_span);
+        if !is_reported(&[move_span, use_span]) {
+            continue;
+        }
+
+        let move_result = UnitResult::new(
+            move_span,
+            get_local_name(use_after_move_info.moved_local_id),
+            get_fn_path(&use_after_move_info.move_span),
+        );
+        let use_result = UnitResult::new(
+            use_span,
+            get_local_name(use_after_move_info.used_local_id),
+            get_fn_path(&use_after_move_info.use_span),
+        );
+        // the declaration of the local the value started in, then where it was moved before
+        let origin_id = use_after_move_info.origin_local_id;
+        let origin = cfgs
+            .get(&origin_id.def_id)
+            .and_then(|cfg| cfg.local_infos.get(&origin_id.local_id))
+            .map(|local_info| {
+                UnitResult::new(local_info.decl_span, local_info.var_name.clone(), tcx.def_path_str(origin_id.def_id))
+            });
+        let earlier_moves = use_after_move_info.earlier_moves.iter().map(|(moved_local_id, span_info)| {
+            UnitResult::new(get_span(span_info), get_local_name(*moved_local_id), get_fn_path(span_info))
+        });
+        use_after_move_chains
+            .entry((move_span, use_span))
+            .or_insert_with(|| origin.into_iter().chain(earlier_moves).collect());
+        use_after_move_results
+            .entry((move_span, use_span))
+            .or_insert((move_result, use_result));
+    }
+
+    // handle rc cycle info, the same cycle may be found from several entries
+    let mut rc_cycle_results = HashMap::<Vec<Span>, Vec<UnitResult>>::new();
+    let rc_cycle_iter = check_infos
+        .iter()
+        .flat_map(|(_, check_info)| check_info.rc_cycle_infos.iter());
+    for rc_cycle_info in rc_cycle_iter {
+        let creation_results = rc_cycle_info
+            .creations
+            .iter()
+            .map(|(rc_id, span_info)| {
+                UnitResult::new(get_span(span_info), get_var_name(*rc_id), get_fn_path(span_info))
+            })
+            .sorted_by(|a, b| a.span.cmp(&b.span))
+            .dedup_by(|a, b| a.span == b.span)
+            .collect::<Vec<_>>();
+        let spans = creation_results.iter().map(|res| res.span).collect::<Vec<_>>();
+        if !is_reported(&spans) {
+            continue;
+        }
+        rc_cycle_results.entry(spans).or_insert(creation_results);
+    }
 
     // second filter, merge all same first drop
     let merged_uaf_results = merge_to_same_uaf_drop(&uaf_results);
@@ -124,6 +485,14 @@ pub fn merge_check_info(
     check_result.df_results = df_results;
     check_result.merged_uaf_results = merged_uaf_results;
     check_result.merged_df_results = merged_df_results;
+    check_result.dp_results = dp_results;
+    check_result.leak_results = leak_results;
+    check_result.leak_exit_results = leak_exit_results;
+    check_result.alias_assign_results = alias_assign_results;
+    check_result.ffi_callback_results = ffi_callback_results;
+    check_result.rc_cycle_results = rc_cycle_results;
+    check_result.use_after_move_results = use_after_move_results;
+    check_result.use_after_move_chains = use_after_move_chains;
 
     check_result
 }
@@ -133,12 +502,12 @@ fn merge_to_same_uaf_drop(uaf_results: &HashMap<UafSpan, HashSet<UafResult>>) ->
     let uaf_iter = uaf_results.iter().map(|(_, result)| result.iter()).flatten();
     
     for uaf in uaf_iter {
-        let drop_unit = UnitResult::new(uaf.drop_span, uaf.drop_var_name.clone());
+        let drop_unit = UnitResult::new(uaf.drop_span, uaf.drop_var_name.clone(), uaf.drop_fn_path.clone());
         if !merged_results.contains_key(&drop_unit) {
             merged_results.insert(drop_unit.clone(), HashSet::new());
         }
         let merged_results_with_drop = merged_results.get_mut(&drop_unit).unwrap();
-        let deref_unit = UnitResult::new(uaf.deref_span, uaf.deref_var_name.clone());
+        let deref_unit = UnitResult::new(uaf.deref_span, uaf.deref_var_name.clone(), uaf.deref_fn_path.clone());
         merged_results_with_drop.insert(deref_unit);
     }
 
@@ -150,12 +519,12 @@ fn merge_to_same_df_drop(df_results: &HashMap<DfSpan, HashSet<DfResult>>) -> Has
     let df_iter = df_results.iter().map(|(_, result)| result.iter()).flatten();
     
     for df in df_iter {
-        let first_drop_unit = UnitResult::new(df.first_drop_span, df.first_drop_var_name.clone());
+        let first_drop_unit = UnitResult::new(df.first_drop_span, df.first_drop_var_name.clone(), df.first_drop_fn_path.clone());
         if !merged_results.contains_key(&first_drop_unit) {
             merged_results.insert(first_drop_unit.clone(), HashSet::new());
         }
         let merged_results_with_first_drop = merged_results.get_mut(&first_drop_unit).unwrap();
-        let then_drop_unit = UnitResult::new(df.then_drop_span, df.then_drop_var_name.clone());
+        let then_drop_unit = UnitResult::new(df.then_drop_span, df.then_drop_var_name.clone(), df.then_drop_fn_path.clone());
         merged_results_with_first_drop.insert(then_drop_unit);
     }
 
@@ -169,21 +538,141 @@ fn merge_to_same_df_drop(df_results: &HashMap<DfSpan, HashSet<DfResult>>) -> Has
 
 
 
-pub fn output_check_result(check_result: &CheckResult) {
+// the file, line and column a span starts at, the hash maps of results are printed in this order
+fn span_order(span: &Span) -> (String, usize, usize) {
+    let (filename, line_range, column_range) = utils::parse_span(span);
+    (filename, line_range.0, column_range.0)
+}
+
+fn call_path_order(call_path: &[UnitResult]) -> (usize, Vec<(String, usize, usize)>) {
+    (call_path.len(), call_path.iter().map(|step| span_order(&step.span)).collect())
+}
+
+fn sorted_units<'a>(results: impl Iterator<Item = &'a UnitResult>) -> Vec<&'a UnitResult> {
+    results
+        .sorted_by_cached_key(|res| (span_order(&res.span), res.var_name.clone(), res.fn_path.clone()))
+        .collect()
+}
+
+fn sorted_dp_results(check_result: &CheckResult) -> Vec<&DpResult> {
+    check_result
+        .dp_results
+        .values()
+        .sorted_by_cached_key(|res| (span_order(&res.escape_span), span_order(&res.drop_span)))
+        .collect()
+}
+
+// a cycle is keyed by the sorted spans of its rc creations, ordered by the first one
+fn sorted_rc_cycle_results(check_result: &CheckResult) -> Vec<&Vec<UnitResult>> {
+    check_result
+        .rc_cycle_results
+        .values()
+        .sorted_by_cached_key(|creation_results| span_order(&creation_results[0].span))
+        .collect()
+}
+
+// the results of two spans, eg. the ffi callback and use after move results
+fn sorted_span_pair_results(
+    results: &HashMap<(Span, Span), (UnitResult, UnitResult)>,
+) -> Vec<&(UnitResult, UnitResult)> {
+    results
+        .values()
+        .sorted_by_cached_key(|(register_res, drop_res)| (span_order(&register_res.span), span_order(&drop_res.span)))
+        .collect()
+}
+
+// one line after the analysis, the numbers of uaf and df findings are counted after merging
+pub fn analysis_summary(fn_count: usize, entry_count: usize, check_result: &CheckResult) -> String {
+    format!(
+        "analyzed {} functions, {} entries, found {} use-after-free and {} double-free issues",
+        fn_count,
+        entry_count,
+        check_result.merged_uaf_results.len(),
+        check_result.merged_df_results.len()
+    )
+}
+
+// eg. `3 pointer operations were not modeled (BinaryOp: 2, Len: 1); ...`, counted by rvalue kind
+pub fn unmodeled_summary(unmodeled_assigns: &[(Span, &str)]) -> Option<String> {
+    if unmodeled_assigns.is_empty() {
+        return None;
+    }
+    let mut counts = BTreeMap::<&str, usize>::new();
+    for (_, kind) in unmodeled_assigns.iter() {
+        *counts.entry(kind).or_default() += 1;
+    }
+    Some(format!(
+        "{} pointer operations were not modeled ({}); results may be incomplete; rerun with --open-dbg=1 --DBG=assign for details",
+        unmodeled_assigns.len(),
+        counts.iter().map(|(kind, count)| format!("{}: {}", kind, count)).join(", ")
+    ))
+}
+
+// the counts of `analysis_summary` as one json object, for tracking them over runs
+pub fn json_summary(fn_count: usize, entry_count: usize, check_result: &CheckResult) -> serde_json::Value {
+    serde_json::json!({
+        "functions_analyzed": fn_count,
+        "entries": entry_count,
+        "uaf": check_result.merged_uaf_results.len(),
+        "df": check_result.merged_df_results.len(),
+    })
+}
+
+pub fn write_json_summary(summary: &serde_json::Value, path: &str) -> std::io::Result<()> {
+    if path == "-" {
+        println!("{}", summary);
+        return Ok(());
+    }
+    std::fs::write(path, format!("{}\n", summary))
+}
+
+// the uaf and df findings beyond the cap each, printed as a single notice
+pub fn suppressed_findings(uaf_count: usize, df_count: usize, max_findings: Option<usize>) -> usize {
+    max_findings.map_or(0, |max| uaf_count.saturating_sub(max) + df_count.saturating_sub(max))
+}
+
+fn output_suppressed_findings(suppressed_count: usize) {
+    if suppressed_count > 0 {
+        output_level_text(
+            "info",
+            &format!("... and {} more findings suppressed", suppressed_count),
+        );
+    }
+}
+
+pub fn output_check_result(check_result: &CheckResult, max_findings: Option<usize>) {
+    let max = max_findings.unwrap_or(usize::MAX);
     // handle uaf
-    let uaf_result_iter = check_result
+    let uaf_results = check_result
         .uaf_results
-        .iter()
-        .map(|(_, result)| result.iter())
-        .flatten();
-    for uaf_result in uaf_result_iter {
-        output_level_text("warning", "use after free memory bug may exists");
+        .values()
+        .flatten()
+        .sorted_by_cached_key(|res| {
+            (
+                span_order(&res.drop_span),
+                span_order(&res.deref_span),
+                res.drop_var_name.clone(),
+                res.deref_var_name.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let uaf_count = uaf_results.len();
+    for uaf_result in uaf_results.into_iter().take(max) {
+        output_level_text(
+            "warning",
+            &format!("use after free memory bug may exists in fn {}", uaf_result.drop_fn_path),
+        );
         let (filename, line_range, column_range) = utils::parse_span(&uaf_result.drop_span);
         let problem_text = match &uaf_result.drop_var_name {
             Some(var_name) => format!("first drop here, relative variable: {}", var_name),
             None => "first drop here.".to_string(),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        output_call_path(&uaf_result.call_path);
+
+        if let Some(alias) = &uaf_result.alias {
+            output_uaf_alias(alias);
+        }
 
         let (filename, line_range, column_range) = utils::parse_span(&uaf_result.deref_span);
         let problem_text = match &uaf_result.deref_var_name {
@@ -191,52 +680,114 @@ pub fn output_check_result(check_result: &CheckResult) {
             None => "then dereference here.".to_string(),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+        if let Some(alloc) = &uaf_result.alloc {
+            output_alloc(alloc);
+        }
     }
 
     // handle df
-    let df_result_iter = check_result
+    let df_results = check_result
         .df_results
-        .iter()
-        .map(|(_, result)| result.iter())
-        .flatten();
-    for df_result in df_result_iter {
-        output_level_text("warning", "double free memory bug may exists");
+        .values()
+        .flatten()
+        .sorted_by_cached_key(|res| {
+            (
+                span_order(&res.first_drop_span),
+                span_order(&res.then_drop_span),
+                res.first_drop_var_name.clone(),
+                res.then_drop_var_name.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let df_count = df_results.len();
+    for df_result in df_results.into_iter().take(max) {
+        output_level_text(
+            "warning",
+            &format!("double free memory bug may exists in fn {}", df_result.first_drop_fn_path),
+        );
+        let is_cross_fn = df_result.first_drop_fn_path != df_result.then_drop_fn_path;
         let (filename, line_range, column_range) = utils::parse_span(&df_result.first_drop_span);
+        let first_drop_text = df_drop_text("first drop here", &df_result.first_drop_fn_path, is_cross_fn);
         let problem_text = match &df_result.first_drop_var_name {
-            Some(var_name) => format!("first drop here, relative variable: {}", var_name),
-            None => "first drop here.".to_string(),
+            Some(var_name) => format!(
+                "{}, relative variable: {}, points to {} object(s)",
+                first_drop_text, var_name, df_result.points_to_count
+            ),
+            None => format!("{}, points to {} object(s).", first_drop_text, df_result.points_to_count),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
 
         let (filename, line_range, column_range) = utils::parse_span(&df_result.then_drop_span);
+        let then_drop_text = df_drop_text("then drop here", &df_result.then_drop_fn_path, is_cross_fn);
         let problem_text = match &df_result.then_drop_var_name {
-            Some(var_name) => format!("then drop here, relative variable: {}", var_name),
-            None => "then drop here.".to_string(),
+            Some(var_name) => format!("{}, relative variable: {}", then_drop_text, var_name),
+            None => format!("{}.", then_drop_text),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        output_alias_path(&df_result.alias_path);
+
+        if let Some(alloc) = &df_result.alloc {
+            output_alloc(alloc);
+        }
     }
+    output_suppressed_findings(suppressed_findings(uaf_count, df_count, max_findings));
+
+    // handle dp
+    output_dp_results(check_result);
+
+    // handle leak
+    output_leak_results(check_result);
+
+    // handle alias assign
+    output_alias_assign_results(check_result);
+
+    // handle ffi callback
+    output_ffi_callback_results(check_result);
+
+    // handle use after move
+    output_use_after_move_results(check_result);
+
+    // handle rc cycle
+    output_rc_cycle_results(check_result);
 
     let total_str = format!(
-        "total: {} uaf bugs, {} df bugs",
+        "total: {} uaf bugs, {} df bugs, {} dp bugs, {} leak bugs, {} alias assign bugs, {} ffi callback bugs, {} rc cycle bugs, {} use after move bugs",
         check_result.uaf_results.len(),
-        check_result.df_results.len()
+        check_result.df_results.len(),
+        check_result.dp_results.len(),
+        check_result.leak_results.len(),
+        check_result.alias_assign_results.len(),
+        check_result.ffi_callback_results.len(),
+        check_result.rc_cycle_results.len(),
+        check_result.use_after_move_results.len()
     );
     output_level_text("info", &total_str);
 }
 
 
-pub fn output_merged_result(check_result: &CheckResult){
+pub fn output_merged_result(check_result: &CheckResult, max_findings: Option<usize>) {
+    let max = max_findings.unwrap_or(usize::MAX);
     // handle uaf
-    for drop_res in check_result.merged_uaf_results.keys() {
-        output_level_text("warning", "use after free memory bug may exists");
+    for drop_res in sorted_units(check_result.merged_uaf_results.keys()).into_iter().take(max) {
+        output_level_text(
+            "warning",
+            &format!("use after free memory bug may exists in fn {}", drop_res.fn_path),
+        );
         let (filename, line_range, column_range) = utils::parse_span(&drop_res.span);
         let problem_text = match &drop_res.var_name {
             Some(var_name) => format!("first drop here, relative variable: {}", var_name),
             None => "first drop here.".to_string(),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        if let Some(call_path) = find_uaf_call_path(check_result, drop_res.span) {
+            output_call_path(call_path);
+        }
         
-        for deref_res in check_result.merged_uaf_results.get(drop_res).unwrap().iter() {
+        for deref_res in sorted_units(check_result.merged_uaf_results.get(drop_res).unwrap().iter()) {
+            if let Some(alias) = find_uaf_alias(check_result, drop_res.span, deref_res.span) {
+                output_uaf_alias(alias);
+            }
             let (filename, line_range, column_range) = utils::parse_span(&deref_res.span);
             let problem_text = match &deref_res.var_name {
                 Some(var_name) => format!("then dereference here, relative variable: {}", var_name),
@@ -244,335 +795,2791 @@ pub fn output_merged_result(check_result: &CheckResult){
             };
             output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
         }
+        if let Some(alloc) = find_uaf_alloc(check_result, drop_res.span) {
+            output_alloc(alloc);
+        }
         utils::println_with_color("", Color::White).unwrap();
         utils::println_with_color("", Color::White).unwrap();
     }
 
     // handle df
-    for first_drop_res in check_result.merged_df_results.keys() {
-        output_level_text("warning", "double free memory bug may exists");
+    for first_drop_res in sorted_units(check_result.merged_df_results.keys()).into_iter().take(max) {
+        output_level_text(
+            "warning",
+            &format!("double free memory bug may exists in fn {}", first_drop_res.fn_path),
+        );
+        let df_results_with_first_drop = check_result
+            .df_results
+            .values()
+            .flatten()
+            .filter(|res| res.first_drop_span == first_drop_res.span)
+            .collect::<Vec<_>>();
+        let points_to_count = df_results_with_first_drop
+            .iter()
+            .map(|res| res.points_to_count)
+            .max()
+            .unwrap_or_default();
+
+        let then_drops = check_result.merged_df_results.get(first_drop_res).unwrap();
+        let is_cross_fn = then_drops.iter().any(|res| res.fn_path != first_drop_res.fn_path);
         let (filename, line_range, column_range) = utils::parse_span(&first_drop_res.span);
+        let first_drop_text = df_drop_text("first drop here", &first_drop_res.fn_path, is_cross_fn);
         let problem_text = match &first_drop_res.var_name {
-            Some(var_name) => format!("first drop here, relative variable: {}", var_name),
-            None => "first drop here.".to_string(),
+            Some(var_name) => format!(
+                "{}, relative variable: {}, points to {} object(s)",
+                first_drop_text, var_name, points_to_count
+            ),
+            None => format!("{}, points to {} object(s).", first_drop_text, points_to_count),
         };
         output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
         
-        for then_drop_res in check_result.merged_df_results.get(first_drop_res).unwrap().iter() {
+        for then_drop_res in sorted_units(then_drops.iter()) {
             let (filename, line_range, column_range) = utils::parse_span(&then_drop_res.span);
+            let then_drop_text = df_drop_text("then drop here", &then_drop_res.fn_path, is_cross_fn);
             let problem_text = match &then_drop_res.var_name {
-                Some(var_name) => format!("then drop here, relative variable: {}", var_name),
-                None => "then drop here.".to_string(),
+                Some(var_name) => format!("{}, relative variable: {}", then_drop_text, var_name),
+                None => format!("{}.", then_drop_text),
             };
             output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+            if let Some(df_result) = df_results_with_first_drop
+                .iter()
+                .find(|res| res.then_drop_span == then_drop_res.span)
+            {
+                output_alias_path(&df_result.alias_path);
+            }
+        }
+        if let Some(alloc) = df_results_with_first_drop.iter().find_map(|res| res.alloc.as_ref()) {
+            output_alloc(alloc);
         }
         utils::println_with_color("", Color::White).unwrap();
         utils::println_with_color("", Color::White).unwrap();
     }
+    output_suppressed_findings(suppressed_findings(
+        check_result.merged_uaf_results.len(),
+        check_result.merged_df_results.len(),
+        max_findings,
+    ));
+
+    // handle dp
+    output_dp_results(check_result);
+
+    // handle leak
+    output_leak_results(check_result);
+
+    // handle alias assign
+    output_alias_assign_results(check_result);
+
+    // handle ffi callback
+    output_ffi_callback_results(check_result);
+
+    // handle use after move
+    output_use_after_move_results(check_result);
+
+    // handle rc cycle
+    output_rc_cycle_results(check_result);
 
     let total_str = format!(
-        "total: {} uaf bugs, {} df bugs",
+        "total: {} uaf bugs, {} df bugs, {} dp bugs, {} leak bugs, {} alias assign bugs, {} ffi callback bugs, {} rc cycle bugs, {} use after move bugs",
         check_result.merged_uaf_results.len(),
-        check_result.merged_df_results.len()
+        check_result.merged_df_results.len(),
+        check_result.dp_results.len(),
+        check_result.leak_results.len(),
+        check_result.alias_assign_results.len(),
+        check_result.ffi_callback_results.len(),
+        check_result.rc_cycle_results.len(),
+        check_result.use_after_move_results.len()
     );
     output_level_text("info", &total_str);
 } 
 
+// a merged finding, the primary span with the labeled spans
+struct Finding {
+    span: Span,
+    // the enclosing function the finding is attributed to
+    fn_path: String,
+    kind: &'static str,
+    message: String,
+    labels: Vec<(Span, String)>,
+}
 
-#[derive(Debug)]
-pub struct CheckInfo {
-    pub uaf_infos: Vec<UafInfo>,
-    pub df_infos: Vec<DfInfo>,
+// the findings ordered by the location of their primary span
+fn sorted_findings(check_result: &CheckResult) -> Vec<Finding> {
+    get_findings(check_result)
+        .into_iter()
+        .sorted_by_cached_key(|finding| (span_order(&finding.span), finding.message.clone()))
+        .collect()
 }
 
-impl CheckInfo {
-    pub fn new() -> Self {
-        Self {
-            uaf_infos: Vec::new(),
-            df_infos: Vec::new(),
+fn get_findings(check_result: &CheckResult) -> Vec<Finding> {
+    let label = |text: &str, var_name: &Option<String>| match var_name {
+        Some(var_name) => format!("{}, relative variable: {}", text, var_name),
+        None => format!("{}.", text),
+    };
+    let mut findings = vec![];
+
+    for drop_res in sorted_units(check_result.merged_uaf_results.keys()) {
+        let mut labels = vec![(drop_res.span, label("first drop here", &drop_res.var_name))];
+        if let Some(call_path) = find_uaf_call_path(check_result, drop_res.span) {
+            for step in call_path.iter() {
+                labels.push((step.span, format!("through call path: called here in fn {}", step.fn_path)));
+            }
+        }
+        for deref_res in sorted_units(check_result.merged_uaf_results.get(drop_res).unwrap().iter()) {
+            if let Some(alias) = find_uaf_alias(check_result, drop_res.span, deref_res.span) {
+                labels.push((alias.span, label("pointer created here", &alias.var_name)));
+            }
+            labels.push((deref_res.span, label("then dereference here", &deref_res.var_name)));
+        }
+        if let Some(alloc) = find_uaf_alloc(check_result, drop_res.span) {
+            labels.push((alloc.span, label("allocated here", &None)));
+        }
+        findings.push(Finding {
+            span: drop_res.span,
+            fn_path: drop_res.fn_path.clone(),
+            kind: "uaf",
+            message: format!("use after free memory bug may exists in fn {}", drop_res.fn_path),
+            labels,
+        });
+    }
+
+    for first_drop_res in sorted_units(check_result.merged_df_results.keys()) {
+        let then_drops = check_result.merged_df_results.get(first_drop_res).unwrap();
+        let is_cross_fn = then_drops.iter().any(|res| res.fn_path != first_drop_res.fn_path);
+        let first_drop_text = df_drop_text("first drop here", &first_drop_res.fn_path, is_cross_fn);
+        let mut labels = vec![(first_drop_res.span, label(&first_drop_text, &first_drop_res.var_name))];
+        for then_drop_res in sorted_units(then_drops.iter()) {
+            let then_drop_text = df_drop_text("then drop here", &then_drop_res.fn_path, is_cross_fn);
+            labels.push((then_drop_res.span, label(&then_drop_text, &then_drop_res.var_name)));
+        }
+        if let Some(alloc) = check_result
+            .df_results
+            .values()
+            .flatten()
+            .filter(|res| res.first_drop_span == first_drop_res.span)
+            .find_map(|res| res.alloc.as_ref())
+        {
+            labels.push((alloc.span, label("allocated here", &None)));
         }
+        findings.push(Finding {
+            span: first_drop_res.span,
+            fn_path: first_drop_res.fn_path.clone(),
+            kind: "df",
+            message: format!("double free memory bug may exists in fn {}", first_drop_res.fn_path),
+            labels,
+        });
+    }
+
+    for dp_res in check_result.dp_results.values() {
+        findings.push(Finding {
+            span: dp_res.escape_span,
+            fn_path: dp_res.escape_fn_path.clone(),
+            kind: "dp",
+            message: format!("dangling pointer memory bug may exists in fn {}", dp_res.escape_fn_path),
+            labels: vec![
+                (dp_res.escape_span, label("pointer escapes the fn here", &dp_res.escape_var_name)),
+                (
+                    dp_res.drop_span,
+                    label("then the pointee is dropped before returning", &dp_res.drop_var_name),
+                ),
+            ],
+        });
+    }
+
+    for leak_res in check_result.leak_results.values() {
+        let labels = match check_result.leak_exit_results.get(&leak_res.span) {
+            Some(exit_res) => vec![
+                (leak_res.span, label("allocated here but not dropped on every path", &leak_res.var_name)),
+                (exit_res.span, label("then returns here without dropping it", &None)),
+            ],
+            None => vec![(
+                leak_res.span,
+                label("ownership given up here but never reclaimed", &leak_res.var_name),
+            )],
+        };
+        findings.push(Finding {
+            span: leak_res.span,
+            fn_path: leak_res.fn_path.clone(),
+            kind: "leak",
+            message: format!("memory leak bug may exists in fn {}", leak_res.fn_path),
+            labels,
+        });
+    }
+
+    for alias_assign_res in check_result.alias_assign_results.values() {
+        findings.push(Finding {
+            span: alias_assign_res.span,
+            fn_path: alias_assign_res.fn_path.clone(),
+            kind: "alias_assign",
+            message: format!(
+                "self assignment through aliased pointers may exists in fn {}",
+                alias_assign_res.fn_path
+            ),
+            labels: vec![(
+                alias_assign_res.span,
+                label("the assigned value is dropped as the old value here", &alias_assign_res.var_name),
+            )],
+        });
+    }
+
+    for creation_results in check_result.rc_cycle_results.values() {
+        findings.push(Finding {
+            span: creation_results[0].span,
+            fn_path: creation_results[0].fn_path.clone(),
+            kind: "rc_cycle",
+            message: format!(
+                "reference cycle of rcs may leak memory in fn {}",
+                creation_results[0].fn_path
+            ),
+            labels: creation_results
+                .iter()
+                .map(|res| (res.span, label("rc in the cycle created here", &res.var_name)))
+                .collect(),
+        });
+    }
+
+    for (register_res, drop_res) in check_result.ffi_callback_results.values() {
+        findings.push(Finding {
+            span: drop_res.span,
+            fn_path: drop_res.fn_path.clone(),
+            kind: "ffi_callback",
+            message: format!("use after free through a foreign callback may exists in fn {}", drop_res.fn_path),
+            labels: vec![
+                (register_res.span, label("callback registered here", &register_res.var_name)),
+                (
+                    drop_res.span,
+                    label("then drop here while the callback may still use it", &drop_res.var_name),
+                ),
+            ],
+        });
     }
+
+    for (move_res, use_res) in check_result.use_after_move_results.values() {
+        let chain = move_chain_labels(check_result, move_res, use_res)
+            .into_iter()
+            .map(|(res, text)| (res.span, label(text, &res.var_name)));
+        findings.push(Finding {
+            span: use_res.span,
+            fn_path: use_res.fn_path.clone(),
+            kind: "use_after_move",
+            message: format!("use after move memory bug may exists in fn {}", use_res.fn_path),
+            labels: chain
+                .chain([
+                    (move_res.span, label("moved out here", &move_res.var_name)),
+                    (use_res.span, label("then used here", &use_res.var_name)),
+                ])
+                .collect(),
+        });
+    }
+
+    findings
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct UnitResult {
-    pub span: Span,
-    pub var_name: Option<String>,
+/// A source location, resolved while the source map of the compiler is alive
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    pub file: String,
+    pub line_range: (usize, usize),
+    pub column_range: (usize, usize),
 }
 
-impl UnitResult {
-    pub fn new(span: Span, var_name: Option<String>) -> Self {
-        Self { span, var_name }
+impl Location {
+    fn new(span: &Span) -> Self {
+        let (file, line_range, column_range) = utils::parse_span(span);
+        Self { file, line_range, column_range }
     }
 }
-#[derive(Debug)]
-pub struct CheckResult {
-    pub uaf_results: HashMap<UafSpan, HashSet<UafResult>>,
-    pub merged_uaf_results: HashMap<UnitResult, HashSet<UnitResult>>,
-    pub df_results: HashMap<DfSpan, HashSet<DfResult>>,
-    pub merged_df_results: HashMap<UnitResult, HashSet<UnitResult>>,
+
+/// A merged finding without spans, it outlives the compiler
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedFinding {
+    /// `uaf`, `df`, `dp`, `leak`, `alias_assign`, `rc_cycle`, `ffi_callback` or `use_after_move`
+    pub kind: &'static str,
+    pub fn_path: String,
+    pub message: String,
+    pub location: Location,
+    pub labels: Vec<(Location, String)>,
 }
 
-impl CheckResult {
-    pub fn new() -> Self {
-        Self {
-            uaf_results: HashMap::new(),
-            df_results: HashMap::new(),
-            merged_df_results: HashMap::new(),
-            merged_uaf_results: HashMap::new(),
+/// The merged findings sorted by their locations
+pub fn resolve_findings(check_result: &CheckResult) -> Vec<ResolvedFinding> {
+    get_findings(check_result)
+        .into_iter()
+        .map(|finding| ResolvedFinding {
+            kind: finding.kind,
+            fn_path: finding.fn_path,
+            message: finding.message,
+            location: Location::new(&finding.span),
+            labels: finding
+                .labels
+                .iter()
+                .map(|(span, label)| (Location::new(span), label.clone()))
+                .collect(),
+        })
+        .sorted_by(|a, b| a.location.cmp(&b.location).then_with(|| a.message.cmp(&b.message)))
+        .collect()
+}
+
+/// The number of findings of each enclosing function, the functions with the most findings first
+pub fn summary_by_function(check_result: &CheckResult) -> Vec<(String, usize)> {
+    let mut counts = HashMap::<String, usize>::new();
+    for finding in get_findings(check_result) {
+        *counts.entry(finding.fn_path).or_default() += 1;
+    }
+    let mut summary = counts.into_iter().collect::<Vec<_>>();
+    summary.sort_by(|(fn_a, count_a), (fn_b, count_b)| count_b.cmp(count_a).then_with(|| fn_a.cmp(fn_b)));
+    summary
+}
+
+// the findings as printed by `--format=text-no-color`, sorted by the file and line of their first
+// location so that the reports of two runs can be diffed
+pub fn write_report(check_result: &CheckResult, path: &str) -> std::io::Result<()> {
+    let text = utils::capture_plain_output(|| output_merged_result(check_result, None));
+
+    // each finding starts with its level, the total is the last one
+    let mut findings: Vec<String> = vec![];
+    for line in text.lines() {
+        if line.starts_with("warning:(memory check)") || line.starts_with("info:(memory check)") {
+            findings.push(String::new());
+        }
+        if let Some(finding) = findings.last_mut() {
+            finding.push_str(line);
+            finding.push('\n');
         }
     }
+    let total = findings.pop().unwrap_or_default();
+
+    let location = |finding: &String| {
+        let location = finding
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("--> "))
+            .unwrap_or_default();
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next().and_then(|column| column.parse::<usize>().ok());
+        let line = parts.next().and_then(|line| line.parse::<usize>().ok());
+        (parts.next().unwrap_or_default().to_owned(), line, column)
+    };
+    findings.sort_by_cached_key(|finding| (location(finding), finding.clone()));
+
+    std::fs::write(path, findings.concat() + &total)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct UafResult {
-    pub deref_span: Span,
-    pub deref_var_name: Option<String>,
-    pub drop_span: Span,
-    pub drop_var_name: Option<String>,
+pub fn output_function_summary(summary: &[(String, usize)]) {
+    output_level_text("info", "findings by function:");
+    let width = summary.iter().map(|(_, count)| count.to_string().len()).max().unwrap_or(0);
+    for (fn_path, count) in summary.iter() {
+        println!("  {:>width$}  {}", count, fn_path, width = width);
+    }
 }
 
-impl UafResult {
-    pub fn new(
-        deref_span: Span,
-        deref_var_name: Option<String>,
-        drop_span: Span,
-        drop_var_name: Option<String>,
-    ) -> Self {
-        Self {
-            deref_span,
-            deref_var_name,
-            drop_span,
-            drop_var_name,
+// one json line `{"summary_by_function":[{"function":...,"findings":...}]}`
+pub fn output_function_summary_json(summary: &[(String, usize)]) {
+    let functions = summary
+        .iter()
+        .map(|(fn_path, count)| serde_json::json!({ "function": fn_path, "findings": count }))
+        .collect::<Vec<_>>();
+    println!("{}", serde_json::json!({ "summary_by_function": functions }));
+}
+
+/// The `top` fns that took the most time, the slowest first
+pub fn slowest_functions(times: impl IntoIterator<Item = (String, Duration)>, top: usize) -> Vec<(String, Duration)> {
+    let mut slowest = times.into_iter().collect::<Vec<_>>();
+    slowest.sort_by(|(fn_a, time_a), (fn_b, time_b)| time_b.cmp(time_a).then_with(|| fn_a.cmp(fn_b)));
+    slowest.truncate(top);
+    slowest
+}
+
+// printed to stderr under `--profile`, stdout may be parsed in the json formats
+pub fn output_profile(title: &str, unit: &str, times: &HashMap<DefId, Duration>, top: usize) {
+    let total = times.values().sum::<Duration>();
+    eprintln!(
+        "profile: {} of {} {} took {:.3}ms, the slowest:",
+        title,
+        times.len(),
+        unit,
+        total.as_secs_f64() * 1000.0
+    );
+    let paths = times.iter().map(|(def_id, time)| (utils::parse_def_id(*def_id).join("::"), *time));
+    for (fn_path, time) in slowest_functions(paths, top) {
+        eprintln!("  {:>10.3}ms  {}", time.as_secs_f64() * 1000.0, fn_path);
+    }
+}
+
+/// The drop objects and the pfg nodes of all entries, each entry has a pfg of its own
+pub fn total_stats(stats: &HashMap<DefId, (usize, usize)>) -> (usize, usize) {
+    stats
+        .values()
+        .fold((0, 0), |(objects, nodes), (entry_objects, entry_nodes)| (objects + entry_objects, nodes + entry_nodes))
+}
+
+// printed to stderr under `--stats` like the profile, the number of drop objects and pfg nodes of each entry
+pub fn output_stats(stats: &HashMap<DefId, (usize, usize)>) {
+    let (objects, nodes) = total_stats(stats);
+    eprintln!("stats: {} drop objects and {} pfg nodes in {} entries:", objects, nodes, stats.len());
+    let entries = stats
+        .iter()
+        .map(|(def_id, counts)| (utils::parse_def_id(*def_id).join("::"), *counts))
+        .sorted();
+    for (fn_path, (objects, nodes)) in entries {
+        eprintln!("  {:>8} objects {:>8} nodes  {}", objects, nodes, fn_path);
+    }
+}
+
+/// The uaf, df and dp results as one json array, each object has the kind and the two spans
+pub fn output_check_result_json(check_result: &CheckResult) {
+    let to_json = |span: &Span, var_name: &Option<String>| {
+        let (filename, line_range, column_range) = utils::parse_span(span);
+        serde_json::json!({
+            "file": filename,
+            "line_start": line_range.0,
+            "line_end": line_range.1,
+            "column_start": column_range.0,
+            "column_end": column_range.1,
+            "var_name": var_name,
+        })
+    };
+
+    let uaf_iter = check_result.uaf_results.values().flatten().map(|res| {
+        (res.drop_span, serde_json::json!({
+            "kind": "uaf",
+            "fn": res.drop_fn_path,
+            "drop": to_json(&res.drop_span, &res.drop_var_name),
+            "deref": to_json(&res.deref_span, &res.deref_var_name),
+            "alloc": res.alloc.as_ref().map(|alloc| to_json(&alloc.span, &None)),
+        }))
+    });
+    let df_iter = check_result.df_results.values().flatten().map(|res| {
+        (res.first_drop_span, serde_json::json!({
+            "kind": "df",
+            "fn": res.first_drop_fn_path,
+            "first_drop": to_json(&res.first_drop_span, &res.first_drop_var_name),
+            "then_drop": to_json(&res.then_drop_span, &res.then_drop_var_name),
+            "alloc": res.alloc.as_ref().map(|alloc| to_json(&alloc.span, &None)),
+        }))
+    });
+    let dp_iter = check_result.dp_results.values().map(|res| {
+        (res.escape_span, serde_json::json!({
+            "kind": "dp",
+            "fn": res.escape_fn_path,
+            "escape": to_json(&res.escape_span, &res.escape_var_name),
+            "drop": to_json(&res.drop_span, &res.drop_var_name),
+        }))
+    });
+
+    // the hash maps have no order, keep the output stable for diffing
+    let findings = uaf_iter
+        .chain(df_iter)
+        .chain(dp_iter)
+        .sorted_by_cached_key(|(span, finding)| (span_order(span), finding.to_string()))
+        .map(|(_, finding)| finding)
+        .collect::<Vec<_>>();
+    println!("{}", serde_json::Value::Array(findings));
+}
+
+/// The uaf, df and dp results as a SARIF 2.1.0 log, with paths relative to the manifest directory
+pub fn output_check_result_sarif(check_result: &CheckResult) {
+    const RULES: [(&str, &str); 3] = [
+        ("memory-check/use-after-free", "Use after free"),
+        ("memory-check/double-free", "Double free"),
+        ("memory-check/dangling-pointer", "Dangling pointer"),
+    ];
+    let base_dir = utils::sarif_base_dir();
+    // both sarif and the span are 1-based, and the end column is exclusive in both
+    let to_location = |span: &Span| {
+        let (filename, line_range, column_range) = utils::parse_span(span);
+        serde_json::json!({
+            "artifactLocation": utils::to_sarif_artifact_location(&filename, &base_dir),
+            "region": {
+                "startLine": line_range.0,
+                "startColumn": column_range.0,
+                "endLine": line_range.1,
+                "endColumn": column_range.1,
+            },
+        })
+    };
+    let related_location = |id: usize, span: &Span, text: &str, var_name: &Option<String>| {
+        let text = match var_name {
+            Some(var_name) => format!("{}, relative variable: {}", text, var_name),
+            None => format!("{}.", text),
+        };
+        serde_json::json!({ "id": id, "physicalLocation": to_location(span), "message": { "text": text } })
+    };
+    let to_result = |rule_index: usize, fn_path: &str, first: serde_json::Value, then: serde_json::Value| {
+        let (rule_id, name) = RULES[rule_index];
+        serde_json::json!({
+            "ruleId": rule_id,
+            "ruleIndex": rule_index,
+            "level": "warning",
+            "message": {
+                // links to the related locations by id
+                "text": format!(
+                    "{} memory bug may exists in fn {}, see [here](0) and [here](1)",
+                    name.to_lowercase(),
+                    fn_path
+                ),
+            },
+            "locations": [{ "physicalLocation": first["physicalLocation"] }],
+            "relatedLocations": [first, then],
+        })
+    };
+
+    let uaf_iter = check_result.uaf_results.values().flatten().map(|res| {
+        (res.drop_span, to_result(
+            0,
+            &res.drop_fn_path,
+            related_location(0, &res.drop_span, "first drop here", &res.drop_var_name),
+            related_location(1, &res.deref_span, "then dereference here", &res.deref_var_name),
+        ))
+    });
+    let df_iter = check_result.df_results.values().flatten().map(|res| {
+        let is_cross_fn = res.first_drop_fn_path != res.then_drop_fn_path;
+        (res.first_drop_span, to_result(
+            1,
+            &res.first_drop_fn_path,
+            related_location(
+                0,
+                &res.first_drop_span,
+                &df_drop_text("first drop here", &res.first_drop_fn_path, is_cross_fn),
+                &res.first_drop_var_name,
+            ),
+            related_location(
+                1,
+                &res.then_drop_span,
+                &df_drop_text("then drop here", &res.then_drop_fn_path, is_cross_fn),
+                &res.then_drop_var_name,
+            ),
+        ))
+    });
+    let dp_iter = check_result.dp_results.values().map(|res| {
+        (res.escape_span, to_result(
+            2,
+            &res.escape_fn_path,
+            related_location(0, &res.escape_span, "pointer escapes the fn here", &res.escape_var_name),
+            related_location(1, &res.drop_span, "then the pointee is dropped before returning", &res.drop_var_name),
+        ))
+    });
+    let results = uaf_iter
+        .chain(df_iter)
+        .chain(dp_iter)
+        .sorted_by_cached_key(|(span, finding)| (span_order(span), finding.to_string()))
+        .map(|(_, result)| result)
+        .collect::<Vec<_>>();
+
+    let rules = RULES
+        .iter()
+        .map(|(id, name)| serde_json::json!({ "id": id, "shortDescription": { "text": name } }))
+        .collect::<Vec<_>>();
+    let sarif = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": { "name": "mc", "version": env!("CARGO_PKG_VERSION"), "rules": rules },
+            },
+            // a base uri must end with `/`
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": format!("{}/", utils::path_to_uri(&base_dir).trim_end_matches('/')) },
+            },
+            // rustc counts the columns in chars
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+    println!("{}", sarif);
+}
+
+// the findings as rustc diagnostics, which are json lines under `--error-format=json`
+pub fn emit_merged_result(sess: &Session, check_result: &CheckResult) {
+    for finding in sorted_findings(check_result) {
+        let mut diag = sess.struct_span_warn(finding.span, &format!("(memory check) {}", finding.message));
+        for (span, label) in finding.labels {
+            diag.span_label(span, label);
+        }
+        diag.emit();
+    }
+}
+
+// the findings as LSP `textDocument/publishDiagnostics` notifications, one json line per file,
+// `root_file` is always published so that the stale diagnostics of a clean file are cleared
+pub fn output_lsp_diagnostics(check_result: &CheckResult, root_file: Option<&Path>) {
+    let mut file_lines = HashMap::<String, Vec<String>>::new();
+    let mut to_range = |span: &Span| {
+        let (filename, line_range, column_range) = utils::parse_span(span);
+        let lines = file_lines
+            .entry(filename.clone())
+            .or_insert_with(|| utils::try_get_lines_in_file(&filename, (1, usize::MAX)).unwrap_or_default());
+        let position = |line: usize, column: usize| {
+            let line_text = lines.get(line.wrapping_sub(1)).map(|s| s.as_str()).unwrap_or_default();
+            let (line, character) = utils::to_lsp_position(line_text, line, column);
+            serde_json::json!({ "line": line, "character": character })
+        };
+        let range = serde_json::json!({
+            "start": position(line_range.0, column_range.0),
+            "end": position(line_range.1, column_range.1),
+        });
+        (utils::path_to_uri(Path::new(&filename)), range)
+    };
+
+    let mut diagnostics = HashMap::<String, Vec<serde_json::Value>>::new();
+    if let Some(root_file) = root_file {
+        diagnostics.insert(utils::path_to_uri(root_file), vec![]);
+    }
+    for finding in sorted_findings(check_result) {
+        let (uri, range) = to_range(&finding.span);
+        let related_information = finding
+            .labels
+            .iter()
+            .map(|(span, label)| {
+                let (uri, range) = to_range(span);
+                serde_json::json!({ "location": { "uri": uri, "range": range }, "message": label })
+            })
+            .collect::<Vec<_>>();
+        diagnostics.entry(uri).or_default().push(serde_json::json!({
+            "range": range,
+            // warning
+            "severity": 2,
+            "source": "memory check",
+            "message": finding.message,
+            "relatedInformation": related_information,
+        }));
+    }
+
+    for (uri, diagnostics) in diagnostics.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        });
+        println!("{}", notification);
+    }
+}
+
+fn find_uaf_alias(check_result: &CheckResult, drop_span: Span, deref_span: Span) -> Option<&UnitResult> {
+    check_result
+        .uaf_results
+        .get(&UafSpan::new(deref_span, drop_span))?
+        .iter()
+        .filter_map(|res| res.alias.as_ref())
+        .min_by_key(|alias| span_order(&alias.span))
+}
+
+fn find_uaf_alloc(check_result: &CheckResult, drop_span: Span) -> Option<&UnitResult> {
+    check_result
+        .uaf_results
+        .values()
+        .flatten()
+        .filter(|res| res.drop_span == drop_span)
+        .filter_map(|res| res.alloc.as_ref())
+        .min_by_key(|alloc| span_order(&alloc.span))
+}
+
+fn find_uaf_call_path(check_result: &CheckResult, drop_span: Span) -> Option<&Vec<UnitResult>> {
+    check_result
+        .uaf_results
+        .values()
+        .flatten()
+        .filter(|res| res.drop_span == drop_span && !res.call_path.is_empty())
+        .map(|res| &res.call_path)
+        .min_by_key(|call_path| call_path_order(call_path))
+}
+
+// the drops of a double free in different fns are told apart by their fns
+fn df_drop_text(text: &str, fn_path: &str, is_cross_fn: bool) -> String {
+    if is_cross_fn {
+        format!("{} in fn {}", text, fn_path)
+    } else {
+        text.to_owned()
+    }
+}
+
+// only collected under `--call-path`
+fn output_call_path(call_path: &[UnitResult]) {
+    for step in call_path.iter() {
+        let (filename, line_range, column_range) = utils::parse_span(&step.span);
+        let problem_text = format!("through call path: called here in fn {}", step.fn_path);
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+    }
+}
+
+fn output_alloc(alloc: &UnitResult) {
+    let (filename, line_range, column_range) = utils::parse_span(&alloc.span);
+    output_code_and_problem_info(&filename, line_range, column_range, "allocated here.");
+}
+
+fn output_uaf_alias(alias: &UnitResult) {
+    let (filename, line_range, column_range) = utils::parse_span(&alias.span);
+    let problem_text = match &alias.var_name {
+        Some(var_name) => format!("pointer created here, relative variable: {}", var_name),
+        None => "pointer created here.".to_string(),
+    };
+    output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+}
+
+// only collected under `--verbose-df`
+fn output_alias_path(alias_path: &[UnitResult]) {
+    for step in alias_path.iter() {
+        let (filename, line_range, column_range) = utils::parse_span(&step.span);
+        let problem_text = match &step.var_name {
+            Some(var_name) => format!("alias flows here, relative variable: {}", var_name),
+            None => "alias flows here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+    }
+}
+
+fn output_dp_results(check_result: &CheckResult) {
+    for dp_res in sorted_dp_results(check_result) {
+        output_level_text(
+            "warning",
+            &format!("dangling pointer memory bug may exists in fn {}", dp_res.escape_fn_path),
+        );
+        let (filename, line_range, column_range) = utils::parse_span(&dp_res.escape_span);
+        let problem_text = match &dp_res.escape_var_name {
+            Some(var_name) => format!("pointer escapes the fn here, relative variable: {}", var_name),
+            None => "pointer escapes the fn here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+        let (filename, line_range, column_range) = utils::parse_span(&dp_res.drop_span);
+        let problem_text = match &dp_res.drop_var_name {
+            Some(var_name) => format!("then the pointee is dropped before returning, relative variable: {}", var_name),
+            None => "then the pointee is dropped before returning.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+fn output_leak_results(check_result: &CheckResult) {
+    for leak_res in sorted_units(check_result.leak_results.values()) {
+        output_level_text(
+            "warning",
+            &format!("memory leak bug may exists in fn {}", leak_res.fn_path),
+        );
+        let exit_res = check_result.leak_exit_results.get(&leak_res.span);
+        let text = match exit_res {
+            Some(_) => "allocated here but not dropped on every path",
+            None => "ownership given up here but never reclaimed",
+        };
+        let (filename, line_range, column_range) = utils::parse_span(&leak_res.span);
+        let problem_text = match &leak_res.var_name {
+            Some(var_name) => format!("{}, relative variable: {}", text, var_name),
+            None => format!("{}.", text),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+        if let Some(exit_res) = exit_res {
+            let (filename, line_range, column_range) = utils::parse_span(&exit_res.span);
+            output_code_and_problem_info(&filename, line_range, column_range, "then returns here without dropping it.");
+        }
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+fn output_alias_assign_results(check_result: &CheckResult) {
+    for alias_assign_res in sorted_units(check_result.alias_assign_results.values()) {
+        output_level_text(
+            "warning",
+            &format!(
+                "self assignment through aliased pointers may exists in fn {}",
+                alias_assign_res.fn_path
+            ),
+        );
+        let (filename, line_range, column_range) = utils::parse_span(&alias_assign_res.span);
+        let problem_text = match &alias_assign_res.var_name {
+            Some(var_name) => format!(
+                "the assigned value is dropped as the old value here, relative variable: {}",
+                var_name
+            ),
+            None => "the assigned value is dropped as the old value here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+fn output_rc_cycle_results(check_result: &CheckResult) {
+    for creation_results in sorted_rc_cycle_results(check_result) {
+        output_level_text(
+            "warning",
+            &format!(
+                "reference cycle of rcs may leak memory in fn {}",
+                creation_results[0].fn_path
+            ),
+        );
+        for creation_res in creation_results.iter() {
+            let (filename, line_range, column_range) = utils::parse_span(&creation_res.span);
+            let problem_text = match &creation_res.var_name {
+                Some(var_name) => format!("rc in the cycle created here, relative variable: {}", var_name),
+                None => "rc in the cycle created here.".to_string(),
+            };
+            output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        }
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+fn output_ffi_callback_results(check_result: &CheckResult) {
+    for (register_res, drop_res) in sorted_span_pair_results(&check_result.ffi_callback_results) {
+        output_level_text(
+            "warning",
+            &format!(
+                "use after free through a foreign callback may exists in fn {}",
+                drop_res.fn_path
+            ),
+        );
+        let (filename, line_range, column_range) = utils::parse_span(&register_res.span);
+        let problem_text = match &register_res.var_name {
+            Some(var_name) => format!("callback registered here, relative variable: {}", var_name),
+            None => "callback registered here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+        let (filename, line_range, column_range) = utils::parse_span(&drop_res.span);
+        let problem_text = match &drop_res.var_name {
+            Some(var_name) => format!(
+                "then drop here while the callback may still use it, relative variable: {}",
+                var_name
+            ),
+            None => "then drop here while the callback may still use it.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+// the declaration of the moved value and its earlier moves with their labels, the declaration is
+// left out when it is the reported move itself, eg. a temporary
+fn move_chain_labels<'a>(
+    check_result: &'a CheckResult,
+    move_res: &UnitResult,
+    use_res: &UnitResult,
+) -> Vec<(&'a UnitResult, &'static str)> {
+    let chain = check_result.use_after_move_chains.get(&(move_res.span, use_res.span)).map_or(&[][..], |chain| chain);
+    chain
+        .iter()
+        .enumerate()
+        .filter(|(_, res)| res.span != move_res.span)
+        .map(|(i, res)| (res, if i == 0 { "declared here" } else { "moved here" }))
+        .collect()
+}
+
+fn output_use_after_move_results(check_result: &CheckResult) {
+    for (move_res, use_res) in sorted_span_pair_results(&check_result.use_after_move_results) {
+        output_level_text(
+            "warning",
+            &format!("use after move memory bug may exists in fn {}", use_res.fn_path),
+        );
+        for (res, text) in move_chain_labels(check_result, move_res, use_res) {
+            let (filename, line_range, column_range) = utils::parse_span(&res.span);
+            let problem_text = match &res.var_name {
+                Some(var_name) => format!("{}, relative variable: {}", text, var_name),
+                None => format!("{}.", text),
+            };
+            output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        }
+        let (filename, line_range, column_range) = utils::parse_span(&move_res.span);
+        let problem_text = match &move_res.var_name {
+            Some(var_name) => format!("moved out here, relative variable: {}", var_name),
+            None => "moved out here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+
+        let (filename, line_range, column_range) = utils::parse_span(&use_res.span);
+        let problem_text = match &use_res.var_name {
+            Some(var_name) => format!("then used here, relative variable: {}", var_name),
+            None => "then used here.".to_string(),
+        };
+        output_code_and_problem_info(&filename, line_range, column_range, &problem_text);
+        utils::println_with_color("", Color::White).unwrap();
+        utils::println_with_color("", Color::White).unwrap();
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckInfo {
+    pub uaf_infos: Vec<UafInfo>,
+    pub df_infos: Vec<DfInfo>,
+    pub dp_infos: Vec<DpInfo>,
+    pub leak_infos: Vec<LeakInfo>,
+    pub alias_assign_infos: Vec<AliasAssignInfo>,
+    pub ffi_callback_infos: Vec<FfiCallbackInfo>,
+    pub rc_cycle_infos: Vec<RcCycleInfo>,
+    pub use_after_move_infos: Vec<UseAfterMoveInfo>,
+}
+
+impl CheckInfo {
+    pub fn new() -> Self {
+        Self {
+            uaf_infos: Vec::new(),
+            df_infos: Vec::new(),
+            dp_infos: Vec::new(),
+            leak_infos: Vec::new(),
+            alias_assign_infos: Vec::new(),
+            ffi_callback_infos: Vec::new(),
+            rc_cycle_infos: Vec::new(),
+            use_after_move_infos: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UnitResult {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_span"))]
+    pub span: Span,
+    pub var_name: Option<String>,
+    pub fn_path: String,
+}
+
+impl UnitResult {
+    pub fn new(span: Span, var_name: Option<String>, fn_path: String) -> Self {
+        Self { span, var_name, fn_path }
+    }
+}
+#[derive(Debug)]
+pub struct CheckResult {
+    pub uaf_results: HashMap<UafSpan, HashSet<UafResult>>,
+    pub merged_uaf_results: HashMap<UnitResult, HashSet<UnitResult>>,
+    pub df_results: HashMap<DfSpan, HashSet<DfResult>>,
+    pub merged_df_results: HashMap<UnitResult, HashSet<UnitResult>>,
+    pub dp_results: HashMap<DpSpan, DpResult>,
+    pub leak_results: HashMap<Span, UnitResult>,
+    // the returns that skip the drops of the leaked allocations, keyed by the allocation span
+    pub leak_exit_results: HashMap<Span, UnitResult>,
+    pub alias_assign_results: HashMap<Span, UnitResult>,
+    pub ffi_callback_results: HashMap<(Span, Span), (UnitResult, UnitResult)>,
+    // keyed by the sorted spans of the rc creations in the cycle
+    pub rc_cycle_results: HashMap<Vec<Span>, Vec<UnitResult>>,
+    pub use_after_move_results: HashMap<(Span, Span), (UnitResult, UnitResult)>,
+    // the declaration of the moved value and its moves before the reported one, keyed like `use_after_move_results`
+    pub use_after_move_chains: HashMap<(Span, Span), Vec<UnitResult>>,
+}
+
+impl CheckResult {
+    pub fn new() -> Self {
+        Self {
+            uaf_results: HashMap::new(),
+            df_results: HashMap::new(),
+            merged_df_results: HashMap::new(),
+            merged_uaf_results: HashMap::new(),
+            dp_results: HashMap::new(),
+            leak_results: HashMap::new(),
+            leak_exit_results: HashMap::new(),
+            alias_assign_results: HashMap::new(),
+            ffi_callback_results: HashMap::new(),
+            rc_cycle_results: HashMap::new(),
+            use_after_move_results: HashMap::new(),
+            use_after_move_chains: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uaf_results.is_empty()
+            && self.df_results.is_empty()
+            && self.dp_results.is_empty()
+            && self.leak_results.is_empty()
+            && self.alias_assign_results.is_empty()
+            && self.ffi_callback_results.is_empty()
+            && self.rc_cycle_results.is_empty()
+            && self.use_after_move_results.is_empty()
+    }
+
+    /// The uaf results sorted by the locations of their drops and uses
+    pub fn sorted_uaf_results(&self) -> Vec<&UafResult> {
+        self.uaf_results
+            .values()
+            .flatten()
+            .sorted_by_cached_key(|res| (Location::new(&res.drop_span), Location::new(&res.deref_span)))
+            .collect()
+    }
+
+    /// The df results sorted by the locations of their first and then drops
+    pub fn sorted_df_results(&self) -> Vec<&DfResult> {
+        self.df_results
+            .values()
+            .flatten()
+            .sorted_by_cached_key(|res| (Location::new(&res.first_drop_span), Location::new(&res.then_drop_span)))
+            .collect()
+    }
+}
+
+/// One line per uaf and df result. The spans are resolved by the source map, so the results
+/// are only displayed while the compiler is alive
+impl std::fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for res in self.sorted_uaf_results() {
+            writeln!(f, "{}", res)?;
+        }
+        for res in self.sorted_df_results() {
+            writeln!(f, "{}", res)?;
+        }
+        Ok(())
+    }
+}
+
+/// `{"uaf": [..], "df": [..]}`, sorted as `sorted_uaf_results` and `sorted_df_results`
+#[cfg(feature = "serde")]
+impl serde::Serialize for CheckResult {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("CheckResult", 2)?;
+        state.serialize_field("uaf", &self.sorted_uaf_results())?;
+        state.serialize_field("df", &self.sorted_df_results())?;
+        state.end()
+    }
+}
+
+// a span as `{"file": .., "line": .., "column": ..}` of its start
+#[cfg(feature = "serde")]
+fn serialize_span<S: serde::Serializer>(span: &Span, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    let (file, line_range, column_range) = utils::parse_span(span);
+    let mut state = serializer.serialize_struct("Span", 3)?;
+    state.serialize_field("file", &file)?;
+    state.serialize_field("line", &line_range.0)?;
+    state.serialize_field("column", &column_range.0)?;
+    state.end()
+}
+
+// eg. `a.rs:3:5 in fn f (`x`)`
+fn display_point(span: &Span, fn_path: &str, var_name: &Option<String>) -> String {
+    let (file, line_range, column_range) = utils::parse_span(span);
+    let var = var_name.as_ref().map_or(String::new(), |var_name| format!(" (`{}`)", var_name));
+    format!("{}:{}:{} in fn {}{}", file, line_range.0, column_range.0, fn_path, var)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UafResult {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_span"))]
+    pub deref_span: Span,
+    pub deref_var_name: Option<String>,
+    pub deref_fn_path: String,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_span"))]
+    pub drop_span: Span,
+    pub drop_var_name: Option<String>,
+    pub drop_fn_path: String,
+    pub alias: Option<UnitResult>,
+    pub alloc: Option<UnitResult>,
+    // the call sites the drop is reached through, outermost first, only under `--call-path`
+    pub call_path: Vec<UnitResult>,
+}
+
+impl UafResult {
+    pub fn new(
+        deref_span: Span,
+        deref_var_name: Option<String>,
+        deref_fn_path: String,
+        drop_span: Span,
+        drop_var_name: Option<String>,
+        drop_fn_path: String,
+    ) -> Self {
+        Self {
+            deref_span,
+            deref_var_name,
+            deref_fn_path,
+            drop_span,
+            drop_var_name,
+            drop_fn_path,
+            alias: None,
+            alloc: None,
+            call_path: vec![],
+        }
+    }
+
+    pub fn has_var_name(&self) -> bool {
+        self.deref_var_name.is_some() || self.drop_var_name.is_some()
+    }
+
+    pub fn is_same_target(&self, other: &Self) -> bool {
+        self.deref_span == other.deref_span
+            && self.deref_var_name == other.deref_var_name
+            && self.drop_span == other.drop_span
+            && self.drop_var_name == other.drop_var_name
+    }
+}
+
+// eg. `use after free: dropped at a.rs:3:5 in fn f (`x`), then used at a.rs:4:9 in fn f (`p`)`
+impl std::fmt::Display for UafResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "use after free: dropped at {}, then used at {}",
+            display_point(&self.drop_span, &self.drop_fn_path, &self.drop_var_name),
+            display_point(&self.deref_span, &self.deref_fn_path, &self.deref_var_name)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub struct UafSpan {
+    pub deref_span: Span,
+    pub drop_span: Span,
+}
+
+impl UafSpan {
+    pub fn new(deref_span: Span, drop_span: Span) -> Self {
+        Self {
+            deref_span,
+            drop_span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DfResult {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_span"))]
+    pub first_drop_span: Span,
+    pub first_drop_var_name: Option<String>,
+    pub first_drop_fn_path: String,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_span"))]
+    pub then_drop_span: Span,
+    pub then_drop_var_name: Option<String>,
+    pub then_drop_fn_path: String,
+    // number of objects the first dropped place points to
+    pub points_to_count: usize,
+    pub alias_path: Vec<UnitResult>,
+    pub alloc: Option<UnitResult>,
+}
+
+impl DfResult {
+    pub fn new(
+        first_drop_span: Span,
+        first_drop_var_name: Option<String>,
+        first_drop_fn_path: String,
+        then_drop_span: Span,
+        then_drop_var_name: Option<String>,
+        then_drop_fn_path: String,
+    ) -> Self {
+        Self {
+            first_drop_span,
+            first_drop_var_name,
+            first_drop_fn_path,
+            then_drop_span,
+            then_drop_var_name,
+            then_drop_fn_path,
+            points_to_count: 0,
+            alias_path: Vec::new(),
+            alloc: None,
+        }
+    }
+
+    pub fn has_var_name(&self) -> bool {
+        self.first_drop_var_name.is_some() || self.then_drop_var_name.is_some()
+    }
+
+    pub fn is_same_target(&self, other: &Self) -> bool {
+        self.first_drop_span == other.first_drop_span
+            && self.first_drop_var_name == other.first_drop_var_name
+            && self.then_drop_span == other.then_drop_span
+            && self.then_drop_var_name == other.then_drop_var_name
+    }
+}
+
+// eg. `double free: first dropped at a.rs:3:5 in fn f (`x`), then dropped at a.rs:4:5 in fn f (`y`)`
+impl std::fmt::Display for DfResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "double free: first dropped at {}, then dropped at {}",
+            display_point(&self.first_drop_span, &self.first_drop_fn_path, &self.first_drop_var_name),
+            display_point(&self.then_drop_span, &self.then_drop_fn_path, &self.then_drop_var_name)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub struct DfSpan {
+    pub first_drop_span: Span,
+    pub then_drop_span: Span,
+}
+
+impl DfSpan {
+    pub fn new(first_drop_span: Span, then_drop_span: Span) -> Self {
+        Self {
+            first_drop_span,
+            then_drop_span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DpResult {
+    pub escape_span: Span,
+    pub escape_var_name: Option<String>,
+    pub escape_fn_path: String,
+    pub drop_span: Span,
+    pub drop_var_name: Option<String>,
+    pub drop_fn_path: String,
+}
+
+impl DpResult {
+    pub fn new(
+        escape_span: Span,
+        escape_var_name: Option<String>,
+        escape_fn_path: String,
+        drop_span: Span,
+        drop_var_name: Option<String>,
+        drop_fn_path: String,
+    ) -> Self {
+        Self {
+            escape_span,
+            escape_var_name,
+            escape_fn_path,
+            drop_span,
+            drop_var_name,
+            drop_fn_path,
+        }
+    }
+
+    pub fn has_var_name(&self) -> bool {
+        self.escape_var_name.is_some() || self.drop_var_name.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub struct DpSpan {
+    pub escape_span: Span,
+    pub drop_span: Span,
+}
+
+impl DpSpan {
+    pub fn new(escape_span: Span, drop_span: Span) -> Self {
+        Self {
+            escape_span,
+            drop_span,
+        }
+    }
+}
+
+// the same finding as reported, which differs only in the contexts or the projections,
+// the locals are compared too since many locals are dropped with the span of one `}`
+trait SameSpan {
+    fn is_same_span(&self, other: &Self) -> bool;
+}
+
+#[derive(Debug)]
+pub struct UafInfo {
+    pub deref_proj_id: GlobalProjectionId,
+    pub deref_span: CtxtSenSpanInfo,
+    pub drop_obj_id: DropObjectId,
+    pub drop_span: CtxtSenSpanInfo,
+    // the assignment creating the pointer to the dropped object
+    pub alias_edge: Option<ProjectionNeighborInfo>,
+    // the allocation of the dropped box
+    pub alloc_span: Option<CtxtSenSpanInfo>,
+}
+
+impl UafInfo {
+    pub fn new(
+        deref_proj_id: GlobalProjectionId,
+        deref_span: CtxtSenSpanInfo,
+        drop_obj_id: DropObjectId,
+        drop_span: CtxtSenSpanInfo,
+    ) -> Self {
+        Self {
+            deref_proj_id,
+            deref_span,
+            drop_obj_id,
+            drop_span,
+            alias_edge: None,
+            alloc_span: None,
+        }
+    }
+}
+
+impl SameSpan for UafInfo {
+    fn is_same_span(&self, other: &Self) -> bool {
+        self.deref_span.span == other.deref_span.span
+            && self.drop_span.span == other.drop_span.span
+            && self.deref_proj_id.g_local_id == other.deref_proj_id.g_local_id
+            && get_g_local_id(self.drop_obj_id) == get_g_local_id(other.drop_obj_id)
+    }
+}
+
+#[derive(Debug)]
+pub struct DfInfo {
+    pub first_drop_obj_id: DropObjectId,
+    pub first_drop_span: CtxtSenSpanInfo,
+
+    pub then_drop_obj_id: DropObjectId,
+    pub then_drop_span: CtxtSenSpanInfo,
+
+    pub points_to_count: usize,
+    // edges along which the aliasing is introduced, only collected under `--verbose-df`
+    pub alias_path: Vec<ProjectionNeighborInfo>,
+    // the allocation of the dropped box
+    pub alloc_span: Option<CtxtSenSpanInfo>,
+}
+
+impl DfInfo {
+    pub fn new(
+        first_drop_obj_id: DropObjectId,
+        first_drop_span: CtxtSenSpanInfo,
+        then_drop_obj_id: DropObjectId,
+        then_drop_span: CtxtSenSpanInfo,
+        points_to_count: usize,
+        alias_path: Vec<ProjectionNeighborInfo>,
+    ) -> Self {
+        Self {
+            first_drop_obj_id,
+            first_drop_span,
+            then_drop_obj_id,
+            then_drop_span,
+            points_to_count,
+            alias_path,
+            alloc_span: None,
+        }
+    }
+}
+
+impl SameSpan for DfInfo {
+    fn is_same_span(&self, other: &Self) -> bool {
+        self.first_drop_span.span == other.first_drop_span.span
+            && self.then_drop_span.span == other.then_drop_span.span
+            && get_g_local_id(self.first_drop_obj_id) == get_g_local_id(other.first_drop_obj_id)
+            && get_g_local_id(self.then_drop_obj_id) == get_g_local_id(other.then_drop_obj_id)
+    }
+}
+
+fn get_g_local_id(obj_id: DropObjectId) -> GlobalLocalId {
+    let g_proj_id: GlobalProjectionId = obj_id.into();
+    g_proj_id.g_local_id
+}
+
+#[derive(Debug)]
+pub struct DpInfo {
+    // the pointer in the return place or behind an argument
+    pub escape_proj_id: GlobalProjectionId,
+    pub escape_span: CtxtSenSpanInfo,
+    pub drop_obj_id: DropObjectId,
+    pub drop_span: CtxtSenSpanInfo,
+}
+
+impl DpInfo {
+    pub fn new(
+        escape_proj_id: GlobalProjectionId,
+        escape_span: CtxtSenSpanInfo,
+        drop_obj_id: DropObjectId,
+        drop_span: CtxtSenSpanInfo,
+    ) -> Self {
+        Self {
+            escape_proj_id,
+            escape_span,
+            drop_obj_id,
+            drop_span,
+        }
+    }
+}
+
+impl SameSpan for DpInfo {
+    fn is_same_span(&self, other: &Self) -> bool {
+        self.escape_span.span == other.escape_span.span && self.drop_span.span == other.drop_span.span
+    }
+}
+
+#[derive(Debug)]
+pub struct LeakInfo {
+    pub leak_obj_id: DropObjectId,
+    pub leak_span: CtxtSenSpanInfo,
+    // the return reached without dropping the allocated box
+    pub exit_span: Option<CtxtSenSpanInfo>,
+}
+
+impl LeakInfo {
+    pub fn new(leak_obj_id: DropObjectId, leak_span: CtxtSenSpanInfo) -> Self {
+        Self {
+            leak_obj_id,
+            leak_span,
+            exit_span: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AliasAssignInfo {
+    pub ptr_proj_id: GlobalProjectionId,
+    pub assign_span: CtxtSenSpanInfo,
+}
+
+impl AliasAssignInfo {
+    pub fn new(ptr_proj_id: GlobalProjectionId, assign_span: CtxtSenSpanInfo) -> Self {
+        Self {
+            ptr_proj_id,
+            assign_span,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FfiCallbackInfo {
+    pub ctx_proj_id: GlobalProjectionId,
+    pub register_span: CtxtSenSpanInfo,
+    pub drop_obj_id: DropObjectId,
+    pub drop_span: CtxtSenSpanInfo,
+}
+
+impl FfiCallbackInfo {
+    pub fn new(
+        ctx_proj_id: GlobalProjectionId,
+        register_span: CtxtSenSpanInfo,
+        drop_obj_id: DropObjectId,
+        drop_span: CtxtSenSpanInfo,
+    ) -> Self {
+        Self {
+            ctx_proj_id,
+            register_span,
+            drop_obj_id,
+            drop_span,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UseAfterMoveInfo {
+    pub moved_local_id: GlobalLocalId,
+    pub move_span: CtxtSenSpanInfo,
+    // the moved local itself, or a pointer derived from it
+    pub used_local_id: GlobalLocalId,
+    pub use_span: CtxtSenSpanInfo,
+    // the local the moved value started in, then the moves it came through before, oldest first
+    pub origin_local_id: GlobalLocalId,
+    pub earlier_moves: Vec<(GlobalLocalId, CtxtSenSpanInfo)>,
+}
+
+impl UseAfterMoveInfo {
+    pub fn new(
+        moved_local_id: GlobalLocalId,
+        move_span: CtxtSenSpanInfo,
+        used_local_id: GlobalLocalId,
+        use_span: CtxtSenSpanInfo,
+    ) -> Self {
+        Self {
+            moved_local_id,
+            move_span,
+            used_local_id,
+            use_span,
+            origin_local_id: moved_local_id,
+            earlier_moves: vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RcCycleInfo {
+    // the rcs owning each other, with the spans of their creations
+    pub creations: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>,
+}
+
+impl RcCycleInfo {
+    pub fn new(creations: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>) -> Self {
+        Self { creations }
+    }
+}
+
+fn get_alias_path(
+    ctxt: &AnalysisContext,
+    from: GlobalProjectionId,
+    to: GlobalProjectionId,
+) -> Vec<ProjectionNeighborInfo> {
+    if !utils::verbose_df(&ctxt.options) {
+        return vec![];
+    }
+    ctxt.pfg.paths_from(from, to).unwrap_or_default()
+}
+
+fn check_df(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<DfInfo> {
+    let mut df_infos = Vec::new();
+
+    for first_drop_obj_id in ctxt.pfg.multi_drop_objects.iter() {
+        let points_to = &ctxt
+            .pfg
+            .get_projection_node((*first_drop_obj_id).into())
+            .points_to;
+        // If the object is not pointed by any other object, it is not a double free.
+        if points_to.len() <= 1 {
+            continue;
+        }
+
+        // exclude itself
+        let then_drop_objs = points_to
+            .iter()
+            .filter(|&obj_id| obj_id != first_drop_obj_id)
+            .map(|obj_id| *obj_id)
+            .collect::<HashSet<DropObjectId>>();
+
+        let first_drop_span_infos = &ctxt
+            .pfg
+            .get_projection_node((*first_drop_obj_id).into())
+            .cs_drop_spans;
+
+        for then_drop_obj in then_drop_objs.iter() {
+            // the handles of an rc only free the shared value once
+            if ctxt.pfg.rc_handle_objects.contains(first_drop_obj_id)
+                && ctxt.pfg.rc_handle_objects.contains(then_drop_obj)
+            {
+                continue;
+            }
+            if is_taken_from(ctxt, *first_drop_obj_id, (*then_drop_obj).into())
+                || is_taken_from(ctxt, *then_drop_obj, (*first_drop_obj_id).into())
+            {
+                continue;
+            }
+            let then_drop_span_infos = &ctxt
+                .pfg
+                .get_projection_node((*then_drop_obj).into())
+                .cs_drop_spans;
+            let alias_path = get_alias_path(ctxt, (*then_drop_obj).into(), (*first_drop_obj_id).into());
+            // both drops free the same box
+            let alloc_span = ctxt
+                .pfg
+                .get_alloc_site(*first_drop_obj_id)
+                .or_else(|| ctxt.pfg.get_alloc_site(*then_drop_obj));
+
+            let product = first_drop_span_infos
+                .iter()
+                .cartesian_product(then_drop_span_infos.iter());
+
+            for (first_drop_span_info, then_drop_span_info) in product {
+                if is_consumed_pair(ctxt, reach, first_drop_span_info, then_drop_span_info) {
+                    continue;
+                }
+                // if first drop object can arrive then drop object, it is a double free.
+                if reach.can_arrive_in_context(first_drop_span_info, then_drop_span_info) {
+                    let mut target_info = DfInfo::new(
+                        *first_drop_obj_id,
+                        first_drop_span_info.clone(),
+                        *then_drop_obj,
+                        then_drop_span_info.clone(),
+                        points_to.len(),
+                        alias_path.clone(),
+                    );
+                    target_info.alloc_span = alloc_span.cloned();
+
+                    // the same pair of drops in other contexts
+                    if !contains_same_span(&df_infos, &target_info) {
+                        df_infos.push(target_info);
+                    }
+                }
+
+                // if then drop object can arrive first drop object, it is a double free.
+                if reach.can_arrive_in_context(then_drop_span_info, first_drop_span_info) {
+                    let mut target_info = DfInfo::new(
+                        *then_drop_obj,
+                        then_drop_span_info.clone(),
+                        *first_drop_obj_id,
+                        first_drop_span_info.clone(),
+                        ctxt.pfg.get_projection_node((*then_drop_obj).into()).points_to.len(),
+                        alias_path.clone(),
+                    );
+                    target_info.alloc_span = alloc_span.cloned();
+
+                    // the same pair of drops in other contexts
+                    if !contains_same_span(&df_infos, &target_info) {
+                        df_infos.push(target_info);
+                    }
+                }
+            }
+        }
+    }
+
+    df_infos
+}
+
+// the container of `Option::take` and the like drops another value than the one taken into the return value
+// and flowing to `g_proj_id`, the value moved in by `replace` is not taken
+fn is_taken_from(ctxt: &AnalysisContext, container_obj: DropObjectId, g_proj_id: GlobalProjectionId) -> bool {
+    let points_to = |g_proj_id: GlobalProjectionId| &ctxt.pfg.get_projection_node(g_proj_id).points_to;
+    ctxt.pfg.taken_values.iter().any(|(container_id, ret_id, new_value_id)| {
+        points_to(*container_id).contains(&container_obj)
+            && !new_value_id.map_or(false, |new_value_id| points_to(new_value_id).contains(&container_obj))
+            && ctxt.pfg.can_flow_to(*ret_id, g_proj_id)
+    })
+}
+
+// the cleanup drop of a local moved into `mem::drop` or `mem::forget` only runs on the paths before
+// the call, never together with a drop reached from the call
+fn is_consumed_pair(
+    ctxt: &AnalysisContext,
+    reach: &ReachabilityCache,
+    a: &CtxtSenSpanInfo,
+    b: &CtxtSenSpanInfo,
+) -> bool {
+    let is_consumed_before = |drop: &CtxtSenSpanInfo, other: &CtxtSenSpanInfo| {
+        ctxt.pfg
+            .consumed_drops
+            .get(&GlobalBasicBlockId::new(drop.def_id, drop.basic_block_id))
+            .map_or(false, |consume_bb_id| {
+                drop.def_id == other.def_id
+                    && reach.can_arrive(*consume_bb_id, GlobalBasicBlockId::new(other.def_id, other.basic_block_id))
+            })
+    };
+    is_consumed_before(a, b) || is_consumed_before(b, a)
+}
+
+// a pointer escapes the fn through the return place or an argument, while the object it points to
+// is dropped by the fn before every return
+fn check_dp(ctxt: &AnalysisContext) -> Vec<DpInfo> {
+    let mut dp_infos = Vec::new();
+
+    for (g_local_id, node) in ctxt.pfg.nodes.iter() {
+        let def_id = g_local_id.def_id;
+        let cfg = match ctxt.cfgs.get(&def_id) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+        let body = utils::get_mir_body(ctxt.tcx, def_id);
+        let local = g_local_id.local_id;
+        let is_arg = local != RETURN_PLACE && local.as_usize() <= body.arg_count;
+        if local != RETURN_PLACE && !is_arg {
+            continue;
+        }
+        let dominators = body.dominators();
+
+        for (proj_id, proj_node) in node.projection_nodes.iter() {
+            // the argument itself is a copy owned by the fn, only what it points to is seen by the caller
+            if is_arg && proj_node.projection.first() != Some(&PlaceElem::Deref) {
+                continue;
+            }
+            let escape_proj_id = GlobalProjectionId::new(*g_local_id, *proj_id);
+            // the owned values like `Box` move the object to the caller
+            let is_ptr = get_projection_ty(ctxt, escape_proj_id)
+                .map_or(false, |ty| ty.is_unsafe_ptr() || ty.is_ref());
+            if !is_ptr {
+                continue;
+            }
+
+            for drop_obj_id in proj_node.points_to.iter() {
+                let drop_span_infos = &ctxt
+                    .pfg
+                    .get_projection_node((*drop_obj_id).into())
+                    .cs_drop_spans;
+
+                for drop_span_info in drop_span_infos.iter().filter(|info| info.def_id == def_id) {
+                    let drop_bb_id = drop_span_info.basic_block_id;
+                    let dominates_ret = !cfg.ret_bbs.is_empty()
+                        && cfg.ret_bbs.iter().all(|ret_bb| {
+                            dominators.is_reachable(*ret_bb) && dominators.is_dominated_by(*ret_bb, drop_bb_id)
+                        });
+                    if !dominates_ret {
+                        continue;
+                    }
+
+                    // the assignment storing the pointer, or the drop if the path is unknown
+                    let escape_span = ctxt
+                        .pfg
+                        .flow_path((*drop_obj_id).into(), escape_proj_id)
+                        .and_then(|path| path.last().map(|edge| edge.span_info.clone()))
+                        .unwrap_or_else(|| drop_span_info.clone());
+                    dp_infos.push(DpInfo::new(
+                        escape_proj_id,
+                        escape_span,
+                        *drop_obj_id,
+                        drop_span_info.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    dp_infos
+}
+
+// a drop in a loop frees the same object in every iteration
+fn check_loop_df(ctxt: &AnalysisContext) -> Vec<DfInfo> {
+    let mut df_infos = Vec::new();
+
+    for call_id in ctxt.cs_reachable_calls.iter() {
+        let cfg = match ctxt.cfgs.get(&call_id.def_id) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+        for bb_id in cfg.loop_drop_bbs.iter() {
+            let place = match cfg.basic_block_infos.get(bb_id).unwrap().terminator.kind {
+                TerminatorKind::Drop { ref place, .. } => place,
+                _ => continue,
+            };
+            let g_local_id = GlobalLocalId::new(call_id.def_id, place.local);
+            let drop_proj_id = match ctxt.pfg.nodes.get(&g_local_id).and_then(|node| {
+                node.try_get_projection_id(place.projection, &call_id.caller_context)
+            }) {
+                Some(proj_id) => GlobalProjectionId::new(g_local_id, proj_id),
+                None => continue,
+            };
+
+            let drop_node = ctxt.pfg.get_projection_node(drop_proj_id);
+            let drop_span_info = drop_node.cs_drop_spans.iter().find(|span_info| {
+                span_info.def_id == call_id.def_id && span_info.basic_block_id == *bb_id
+            });
+            if let Some(drop_span_info) = drop_span_info {
+                df_infos.push(DfInfo::new(
+                    drop_proj_id.into(),
+                    drop_span_info.clone(),
+                    drop_proj_id.into(),
+                    drop_span_info.clone(),
+                    drop_node.points_to.len(),
+                    vec![],
+                ));
+            }
+        }
+    }
+
+    df_infos
+}
+
+// a field dropped by `ptr::drop_in_place` is freed again by the drop glue of its owner
+fn check_field_drop(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<DfInfo> {
+    let mut df_infos = Vec::new();
+
+    for (ptr_id, manual_drop_span_info) in ctxt.pfg.manual_drops.iter() {
+        let manual_drop_bb_id =
+            GlobalBasicBlockId::new(manual_drop_span_info.def_id, manual_drop_span_info.basic_block_id);
+        let ptr_node = ctxt.pfg.get_projection_node(*ptr_id);
+
+        for owner_obj_id in ptr_node.points_to.iter() {
+            let owner_id: GlobalProjectionId = (*owner_obj_id).into();
+            let owner_node = ctxt.pfg.get_projection_node(owner_id);
+
+            // the field of the owner whose address is passed to `drop_in_place`, or the owner itself
+            // if it is a field dropped on its own, eg. after its sibling is moved out
+            let is_field_drop = matches!(owner_node.projection.last(), Some(PlaceElem::Field(..)));
+            let field_id = ctxt
+                .pfg
+                .get_node(owner_id.g_local_id)
+                .projection_nodes
+                .iter()
+                .filter(|(_, field_node)| {
+                    owner_node.caller_context.is_same(&field_node.caller_context)
+                        && owner_node.is_prefix_of(&field_node.projection)
+                        && match field_node.projection.get(owner_node.projection.len()) {
+                            Some(PlaceElem::Field(..)) => true,
+                            None => is_field_drop,
+                            _ => false,
+                        }
+                        && !field_node.projection.contains(&PlaceElem::Deref)
+                })
+                .map(|(proj_id, _)| GlobalProjectionId::new(owner_id.g_local_id, *proj_id))
+                .find(|field_id| {
+                    !is_manually_dropped(ctxt, *field_id, owner_node.projection.len())
+                        && ctxt.pfg.can_flow_to(*field_id, *ptr_id)
+                });
+            let field_id = match field_id {
+                Some(field_id) => field_id,
+                None => continue,
+            };
+
+            for owner_drop_span_info in owner_node.cs_drop_spans.iter() {
+                let owner_drop_bb_id = GlobalBasicBlockId::new(
+                    owner_drop_span_info.def_id,
+                    owner_drop_span_info.basic_block_id,
+                );
+                // skip the drops on unwinding, eg. of an owner forgotten on the normal path
+                let is_cleanup = ctxt
+                    .cfgs
+                    .get(&owner_drop_span_info.def_id)
+                    .and_then(|cfg| cfg.basic_block_infos.get(&owner_drop_span_info.basic_block_id))
+                    .map_or(false, |bb_info| bb_info.is_cleanup);
+                if !is_cleanup && reach.can_arrive(manual_drop_bb_id, owner_drop_bb_id) {
+                    df_infos.push(DfInfo::new(
+                        field_id.into(),
+                        manual_drop_span_info.clone(),
+                        *owner_obj_id,
+                        owner_drop_span_info.clone(),
+                        ptr_node.points_to.len(),
+                        get_alias_path(ctxt, field_id, *ptr_id),
+                    ));
+                }
+            }
+        }
+    }
+
+    df_infos
+}
+
+// the drop glue of the owner skips everything inside a `ManuallyDrop`
+fn is_manually_dropped(ctxt: &AnalysisContext, field_id: GlobalProjectionId, owner_proj_len: usize) -> bool {
+    let local_ty = match ctxt.cfgs.get(&field_id.g_local_id.def_id)
+        .and_then(|cfg| cfg.local_infos.get(&field_id.g_local_id.local_id))
+    {
+        Some(local_info) => local_info.ty,
+        None => return false,
+    };
+    let projection = &ctxt.pfg.get_projection_node(field_id).projection;
+
+    let mut place_ty = PlaceTy::from_ty(local_ty);
+    for (i, elem) in projection.iter().enumerate() {
+        place_ty = place_ty.projection_ty(ctxt.tcx, *elem);
+        if i >= owner_proj_len
+            && place_ty.ty.ty_adt_def().map_or(false, |adt_def| adt_def.is_manually_drop())
+        {
+            return true;
+        }
+    }
+    false
+}
+
+// an object released by `Box::into_raw` is owned by every dropped box it flows to,
+// no owner means it is leaked, and two owners mean it is freed twice
+fn check_box_ownership(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> (Vec<DfInfo>, Vec<LeakInfo>) {
+    let mut df_infos = Vec::new();
+    let mut leak_infos = Vec::new();
+
+    let is_box_owner = |g_proj_id: GlobalProjectionId| {
+        let proj_node = ctxt.pfg.get_projection_node(g_proj_id);
+        if proj_node.cs_drop_spans.is_empty() || !proj_node.projection.is_empty() {
+            return false;
+        }
+        ctxt.cfgs
+            .get(&g_proj_id.g_local_id.def_id)
+            .and_then(|cfg| cfg.local_infos.get(&g_proj_id.g_local_id.local_id))
+            .map_or(false, |local_info| local_info.ty.is_box())
+    };
+
+    for (released_obj_id, released_span_info) in ctxt.pfg.raw_released_objects.iter() {
+        let owners = ctxt
+            .pfg
+            .nodes
+            .iter()
+            .flat_map(|(g_local_id, node)| {
+                node.projection_nodes
+                    .iter()
+                    .filter(|(_, proj_node)| proj_node.points_to.contains(released_obj_id))
+                    .map(move |(proj_id, _)| GlobalProjectionId::new(*g_local_id, *proj_id))
+            })
+            .filter(|g_proj_id| is_box_owner(*g_proj_id))
+            .collect::<Vec<_>>();
+
+        if owners.is_empty() {
+            leak_infos.push(LeakInfo::new(*released_obj_id, released_span_info.clone()));
+            continue;
+        }
+
+        for (first_owner, then_owner) in owners.iter().tuple_combinations() {
+            let first_drop_span_infos = &ctxt.pfg.get_projection_node(*first_owner).cs_drop_spans;
+            let then_drop_span_infos = &ctxt.pfg.get_projection_node(*then_owner).cs_drop_spans;
+
+            let product = first_drop_span_infos
+                .iter()
+                .cartesian_product(then_drop_span_infos.iter());
+
+            for (first_drop_span_info, then_drop_span_info) in product {
+                if is_consumed_pair(ctxt, reach, first_drop_span_info, then_drop_span_info) {
+                    continue;
+                }
+                if reach.can_arrive_in_context(first_drop_span_info, then_drop_span_info) {
+                    df_infos.push(DfInfo::new(
+                        (*first_owner).into(),
+                        first_drop_span_info.clone(),
+                        (*then_owner).into(),
+                        then_drop_span_info.clone(),
+                        ctxt.pfg.get_projection_node(*first_owner).points_to.len(),
+                        get_alias_path(ctxt, (*released_obj_id).into(), *then_owner),
+                    ));
+                }
+
+                if reach.can_arrive_in_context(then_drop_span_info, first_drop_span_info) {
+                    df_infos.push(DfInfo::new(
+                        (*then_owner).into(),
+                        then_drop_span_info.clone(),
+                        (*first_owner).into(),
+                        first_drop_span_info.clone(),
+                        ctxt.pfg.get_projection_node(*then_owner).points_to.len(),
+                        get_alias_path(ctxt, (*released_obj_id).into(), *first_owner),
+                    ));
+                }
+            }
+        }
+    }
+
+    (df_infos, leak_infos)
+}
+
+// a box allocated in a fn and only owned by its locals must be dropped before every return,
+// the boxes whose ownership leaves the fn are left to the caller or the callee
+fn check_alloc_leak<'tcx>(ctxt: &AnalysisContext<'tcx>) -> Vec<LeakInfo> {
+    let mut leak_infos = Vec::new();
+
+    let overlaps = |a: &[PlaceElem<'tcx>], b: &[PlaceElem<'tcx>]| a.iter().zip(b.iter()).all(|(x, y)| x == y);
+    // the enclosing places and the fields own the box together, the places behind it don't
+    let is_same_owner = |a: &[PlaceElem<'tcx>], b: &[PlaceElem<'tcx>]| {
+        let rest = if a.len() > b.len() { &a[b.len()..] } else { &b[a.len()..] };
+        overlaps(a, b) && !rest.contains(&PlaceElem::Deref)
+    };
+
+    for (alloc_id, alloc_span_info) in ctxt.pfg.allocations.iter() {
+        let def_id = alloc_span_info.def_id;
+        let cfg = match ctxt.cfgs.get(&def_id) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+
+        // the places the box flows to, and the box seen through the pointers to it
+        let mut visited = HashSet::new();
+        let mut views = HashSet::new();
+        let mut work_list = vec![*alloc_id];
+        while let Some(g_proj_id) = work_list.pop() {
+            if !visited.insert(g_proj_id) {
+                continue;
+            }
+            let proj_node = ctxt.pfg.get_projection_node(g_proj_id);
+            work_list.extend(proj_node.neighbors.keys());
+
+            let is_view = views.contains(&g_proj_id);
+            let ptr_len = get_projection_ty(ctxt, g_proj_id)
+                .filter(|ty| ty.is_unsafe_ptr() || ty.is_ref())
+                .map(|_| proj_node.projection.len());
+            for (proj_id, other_node) in ctxt.pfg.get_node(g_proj_id.g_local_id).projection_nodes.iter() {
+                let other_id = GlobalProjectionId::new(g_proj_id.g_local_id, *proj_id);
+                let is_pointee = ptr_len.map_or(false, |len| {
+                    other_node.projection.get(len) == Some(&PlaceElem::Deref)
+                        && overlaps(&other_node.projection, &proj_node.projection)
+                });
+                if is_pointee || (is_view && is_same_owner(&other_node.projection, &proj_node.projection)) {
+                    views.insert(other_id);
+                    work_list.push(other_id);
+                } else if is_same_owner(&other_node.projection, &proj_node.projection) {
+                    work_list.push(other_id);
+                }
+            }
+        }
+        // the pointers to the box don't own it, but the callee may free the box through them
+        let (ptrs, owners): (Vec<_>, Vec<_>) = visited.into_iter().partition(|g_proj_id| {
+            views.contains(g_proj_id)
+                || get_projection_ty(ctxt, *g_proj_id).map_or(false, |ty| ty.is_unsafe_ptr() || ty.is_ref())
+        });
+        let is_place_of = |place: &Place<'tcx>, g_proj_id: &GlobalProjectionId, is_whole: bool| {
+            let projection = &ctxt.pfg.get_projection_node(*g_proj_id).projection;
+            g_proj_id.g_local_id.local_id == place.local
+                && if is_whole {
+                    projection.as_slice() == place.projection.as_ref()
+                } else {
+                    overlaps(projection, place.projection)
+                }
+        };
+
+        // returned, stored behind a pointer or moved into another fn
+        let is_moved_out = owners.iter().any(|owner_id| {
+            owner_id.g_local_id.def_id != def_id
+                || owner_id.g_local_id.local_id == RETURN_PLACE
+                || ctxt.pfg.get_projection_node(*owner_id).projection.contains(&PlaceElem::Deref)
+        });
+        let is_passed_to_call = cfg.call_infos.iter().any(|(bb_id, call_info)| {
+            !ctxt.pfg.forget_calls.contains(&GlobalBasicBlockId::new(def_id, *bb_id))
+                && call_info.args.iter().any(|arg| match arg {
+                    Operand::Move(place) => owners.iter().any(|owner_id| is_place_of(place, owner_id, false)),
+                    _ => false,
+                } || match arg {
+                    Operand::Move(place) | Operand::Copy(place) => ptrs.iter().any(|ptr_id| {
+                        is_place_of(place, ptr_id, true)
+                            && get_projection_ty(ctxt, *ptr_id).map_or(false, |ty| ty.is_unsafe_ptr())
+                    }),
+                    _ => false,
+                })
+        });
+        if is_moved_out || is_passed_to_call {
+            continue;
+        }
+
+        // a drop of a part or of the whole of an owner frees the box
+        let drop_bbs = owners
+            .iter()
+            .flat_map(|owner_id| {
+                let owner_proj = &ctxt.pfg.get_projection_node(*owner_id).projection;
+                ctxt.pfg
+                    .get_node(owner_id.g_local_id)
+                    .projection_nodes
+                    .values()
+                    .filter(move |proj_node| overlaps(&proj_node.projection, owner_proj))
+                    .flat_map(|proj_node| proj_node.cs_drop_spans.iter())
+                    .filter(|drop_span_info| drop_span_info.def_id == def_id)
+                    .map(|drop_span_info| drop_span_info.basic_block_id)
+            })
+            .collect::<HashSet<_>>();
+
+        if let Some(ret_bb_id) = find_undropped_return(cfg, alloc_span_info.basic_block_id, &drop_bbs) {
+            let ret_span = cfg.basic_block_infos[&ret_bb_id].terminator.source_info.span;
+            let mut leak_info = LeakInfo::new((*alloc_id).into(), alloc_span_info.clone());
+            leak_info.exit_span = Some(CtxtSenSpanInfo::new(
+                def_id,
+                ret_bb_id,
+                ret_span,
+                alloc_span_info.caller_context.clone(),
+            ));
+            leak_infos.push(leak_info);
+        }
+    }
+
+    leak_infos
+}
+
+// a return reached from the allocation without passing any of the drops, unwinding never returns
+fn find_undropped_return(
+    cfg: &ControlFlowGraph,
+    alloc_bb_id: BasicBlockId,
+    drop_bbs: &HashSet<BasicBlockId>,
+) -> Option<BasicBlockId> {
+    // the flags of the conditionally moved places are only assigned constants
+    let is_drop_flag = |local: LocalId| {
+        let mut assigns = cfg
+            .basic_block_infos
+            .values()
+            .flat_map(|bb_info| bb_info.assignment_infos.iter())
+            .filter(|assign| assign.lvalue.local == local && assign.lvalue.projection.is_empty())
+            .peekable();
+        assigns.peek().is_some() && assigns.all(|assign| matches!(assign.rvalue, RvalKind::Constant))
+    };
+    // the switch on a drop flag skips the drop only if the place is moved
+    let guards_drop = |bb_info: &BasicBlockInfo| match &bb_info.terminator.kind {
+        TerminatorKind::SwitchInt { discr, .. } => {
+            discr.place().map_or(false, |place| is_drop_flag(place.local))
+                && bb_info.successors.iter().any(|succ| drop_bbs.contains(succ))
+        }
+        _ => false,
+    };
+
+    let mut visited = HashSet::new();
+    let mut work_list = cfg.basic_block_infos[&alloc_bb_id].successors.iter().copied().collect::<Vec<_>>();
+    while let Some(bb_id) = work_list.pop() {
+        if drop_bbs.contains(&bb_id) || !visited.insert(bb_id) {
+            continue;
+        }
+        let bb_info = &cfg.basic_block_infos[&bb_id];
+        if bb_info.is_cleanup || guards_drop(bb_info) {
+            continue;
+        }
+        if cfg.ret_bbs.contains(&bb_id) {
+            return Some(bb_id);
+        }
+        work_list.extend(bb_info.successors.iter().copied());
+    }
+
+    None
+}
+
+// an upgraded weak unwrapped without checking is used after the strong rc may have dropped
+fn check_weak_upgrade(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<UafInfo> {
+    let mut uaf_infos = Vec::new();
+
+    for (upgraded_id, weak_id) in ctxt.pfg.upgraded_weaks.iter() {
+        let unwrap_span_info = match ctxt.pfg.unchecked_unwraps.get(upgraded_id) {
+            Some(span_info) => span_info,
+            None => continue,
+        };
+        let unwrap_bb_id =
+            GlobalBasicBlockId::new(unwrap_span_info.def_id, unwrap_span_info.basic_block_id);
+
+        let strong_ids = ctxt
+            .pfg
+            .get_projection_node(*weak_id)
+            .points_to
+            .iter()
+            .filter_map(|weak_obj_id| ctxt.pfg.downgraded_weaks.get(weak_obj_id));
+        for strong_id in strong_ids {
+            for strong_obj_id in ctxt.pfg.get_projection_node(*strong_id).points_to.iter() {
+                let drop_span_infos = &ctxt
+                    .pfg
+                    .get_projection_node((*strong_obj_id).into())
+                    .cs_drop_spans;
+
+                for drop_span_info in drop_span_infos.iter() {
+                    let drop_bb_id =
+                        GlobalBasicBlockId::new(drop_span_info.def_id, drop_span_info.basic_block_id);
+                    if drop_bb_id != unwrap_bb_id
+                        && reach.can_arrive(drop_bb_id, unwrap_bb_id)
+                    {
+                        uaf_infos.push(UafInfo::new(
+                            *upgraded_id,
+                            unwrap_span_info.clone(),
+                            *strong_obj_id,
+                            drop_span_info.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    uaf_infos
+}
+
+// a guard dereferenced after its container may have dropped, eg. the guard is transmuted to
+// a longer lifetime
+fn check_guard_escape(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<UafInfo> {
+    let mut uaf_infos = Vec::new();
+
+    for (guard_ptr_id, deref_span_info) in ctxt.pfg.guard_derefs.iter() {
+        let deref_bb_id =
+            GlobalBasicBlockId::new(deref_span_info.def_id, deref_span_info.basic_block_id);
+
+        let container_ids = ctxt
+            .pfg
+            .get_projection_node(*guard_ptr_id)
+            .points_to
+            .iter()
+            .filter_map(|guard_obj_id| ctxt.pfg.borrowed_guards.get(guard_obj_id));
+        for container_id in container_ids {
+            for container_obj_id in ctxt.pfg.get_projection_node(*container_id).points_to.iter() {
+                let drop_span_infos = &ctxt
+                    .pfg
+                    .get_projection_node((*container_obj_id).into())
+                    .cs_drop_spans;
+
+                for drop_span_info in drop_span_infos.iter() {
+                    let drop_bb_id =
+                        GlobalBasicBlockId::new(drop_span_info.def_id, drop_span_info.basic_block_id);
+                    if drop_bb_id != deref_bb_id && reach.can_arrive(drop_bb_id, deref_bb_id) {
+                        uaf_infos.push(UafInfo::new(
+                            *guard_ptr_id,
+                            deref_span_info.clone(),
+                            *container_obj_id,
+                            drop_span_info.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    uaf_infos
+}
+
+// an iterator consumed after the collection it borrows may have dropped, eg. the adaptor is returned
+// with its lifetime extended by `transmute`, the `next` call is reported as the deref
+fn check_iter_escape(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<UafInfo> {
+    let mut uaf_infos = Vec::new();
+
+    for (iter_ptr_id, next_span_info) in ctxt.pfg.iter_nexts.iter() {
+        let next_bb_id = GlobalBasicBlockId::new(next_span_info.def_id, next_span_info.basic_block_id);
+
+        // the iterator behind the pointer, and the collection the iterator points to
+        let iter_obj_ids = &ctxt.pfg.get_projection_node(*iter_ptr_id).points_to;
+        let borrowed_obj_ids = iter_obj_ids
+            .iter()
+            .flat_map(|iter_obj_id| ctxt.pfg.get_projection_node((*iter_obj_id).into()).points_to.iter())
+            .chain(iter_obj_ids.iter())
+            .collect::<HashSet<_>>();
+        for borrowed_obj_id in borrowed_obj_ids {
+            let drop_span_infos = &ctxt
+                .pfg
+                .get_projection_node((*borrowed_obj_id).into())
+                .cs_drop_spans;
+
+            for drop_span_info in drop_span_infos.iter() {
+                let drop_bb_id = GlobalBasicBlockId::new(drop_span_info.def_id, drop_span_info.basic_block_id);
+                if drop_bb_id != next_bb_id && reach.can_arrive(drop_bb_id, next_bb_id) {
+                    uaf_infos.push(UafInfo::new(
+                        *iter_ptr_id,
+                        next_span_info.clone(),
+                        *borrowed_obj_id,
+                        drop_span_info.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    uaf_infos
+}
+
+// the moved value aliases the old value behind the pointer, which is dropped by the assignment
+fn check_alias_assign(ctxt: &AnalysisContext) -> Vec<AliasAssignInfo> {
+    let mut alias_assign_infos = Vec::new();
+
+    for (ptr_id, value_id, assign_span_info) in ctxt.pfg.ptr_assigns.iter() {
+        let pointee_objs = &ctxt.pfg.get_projection_node(*ptr_id).points_to;
+        let value_objs = &ctxt.pfg.get_projection_node(*value_id).points_to;
+        if !pointee_objs.is_disjoint(value_objs) {
+            alias_assign_infos.push(AliasAssignInfo::new(*ptr_id, assign_span_info.clone()));
+        }
+    }
+
+    alias_assign_infos
+}
+
+// two `ptr::drop_in_place` through pointers derived from the same place drop the same value
+fn check_manual_drop_df(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<DfInfo> {
+    let mut df_infos = Vec::new();
+
+    let ptr_ids = ctxt.pfg.manual_drops.keys().copied().collect::<Vec<_>>();
+    if ptr_ids.len() < 2 {
+        return df_infos;
+    }
+    let flow_sources = ctxt.pfg.flow_sources(&ptr_ids);
+
+    for (first_ptr_id, then_ptr_id) in ptr_ids.iter().tuple_combinations() {
+        let first_sources = flow_sources.get(first_ptr_id).unwrap();
+        let then_sources = flow_sources.get(then_ptr_id).unwrap();
+        let common_source = match first_sources.intersection(then_sources).next() {
+            Some(source) => *source,
+            None => continue,
+        };
+
+        for (first_ptr_id, then_ptr_id) in [(first_ptr_id, then_ptr_id), (then_ptr_id, first_ptr_id)] {
+            let first_drop_span_info = ctxt.pfg.manual_drops.get(first_ptr_id).unwrap();
+            let then_drop_span_info = ctxt.pfg.manual_drops.get(then_ptr_id).unwrap();
+            let first_drop_bb_id = GlobalBasicBlockId::new(
+                first_drop_span_info.def_id,
+                first_drop_span_info.basic_block_id,
+            );
+            let then_drop_bb_id = GlobalBasicBlockId::new(
+                then_drop_span_info.def_id,
+                then_drop_span_info.basic_block_id,
+            );
+            if first_drop_bb_id != then_drop_bb_id
+                && reach.can_arrive(first_drop_bb_id, then_drop_bb_id)
+            {
+                df_infos.push(DfInfo::new(
+                    (*first_ptr_id).into(),
+                    first_drop_span_info.clone(),
+                    (*then_ptr_id).into(),
+                    then_drop_span_info.clone(),
+                    // the value may not be a drop object, eg. inside `ManuallyDrop`
+                    ctxt.pfg.get_projection_node(*first_ptr_id).points_to.len().max(1),
+                    get_alias_path(ctxt, common_source, *then_ptr_id),
+                ));
+            }
+        }
+    }
+
+    df_infos
+}
+
+// two owners rebuilt by `Vec::from_raw_parts` from pointers derived from the same place
+// free the same buffer, the drops of an owner are the drops of the places it is moved to
+fn check_raw_parts_df(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<DfInfo> {
+    let mut df_infos = Vec::new();
+
+    let owners = &ctxt.pfg.raw_parts_owners;
+    if owners.len() < 2 {
+        return df_infos;
+    }
+    let ptr_ids = owners.iter().map(|(ptr_id, _, _)| *ptr_id).collect::<Vec<_>>();
+    let flow_sources = ctxt.pfg.flow_sources(&ptr_ids);
+
+    let get_drop_span_infos = |owner_id: GlobalProjectionId| {
+        let mut drop_span_infos = vec![];
+        let mut visited = HashSet::new();
+        let mut work_list = vec![owner_id];
+        while let Some(g_proj_id) = work_list.pop() {
+            if visited.insert(g_proj_id) {
+                let proj_node = ctxt.pfg.get_projection_node(g_proj_id);
+                drop_span_infos.extend(proj_node.cs_drop_spans.iter().cloned());
+                work_list.extend(proj_node.neighbors.keys());
+            }
+        }
+        drop_span_infos
+    };
+
+    for (first, then) in owners.iter().tuple_combinations() {
+        let common_source = match flow_sources
+            .get(&first.0)
+            .unwrap()
+            .intersection(flow_sources.get(&then.0).unwrap())
+            .next()
+        {
+            Some(source) => *source,
+            None => continue,
+        };
+
+        for ((_, first_owner_id, _), (_, then_owner_id, _)) in [(first, then), (then, first)] {
+            let product = get_drop_span_infos(*first_owner_id)
+                .into_iter()
+                .cartesian_product(get_drop_span_infos(*then_owner_id));
+            for (first_drop_span_info, then_drop_span_info) in product {
+                let first_drop_bb_id = GlobalBasicBlockId::new(
+                    first_drop_span_info.def_id,
+                    first_drop_span_info.basic_block_id,
+                );
+                let then_drop_bb_id = GlobalBasicBlockId::new(
+                    then_drop_span_info.def_id,
+                    then_drop_span_info.basic_block_id,
+                );
+                if first_drop_bb_id != then_drop_bb_id
+                    && reach.can_arrive(first_drop_bb_id, then_drop_bb_id)
+                {
+                    df_infos.push(DfInfo::new(
+                        (*first_owner_id).into(),
+                        first_drop_span_info,
+                        (*then_owner_id).into(),
+                        then_drop_span_info,
+                        1,
+                        get_alias_path(ctxt, common_source, *then_owner_id),
+                    ));
+                }
+            }
         }
     }
 
-    pub fn has_var_name(&self) -> bool {
-        self.deref_var_name.is_some() || self.drop_var_name.is_some()
-    }
+    df_infos
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-pub struct UafSpan {
-    pub deref_span: Span,
-    pub drop_span: Span,
-}
+// the foreign side may call back with the registered pointer any time after the registration,
+// there is no way to know when it is unregistered, so every later drop is reported
+fn check_ffi_callback(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<FfiCallbackInfo> {
+    let mut ffi_callback_infos = Vec::new();
 
-impl UafSpan {
-    pub fn new(deref_span: Span, drop_span: Span) -> Self {
-        Self {
-            deref_span,
-            drop_span,
+    for (ctx_id, register_span_info) in ctxt.pfg.ffi_registrations.iter() {
+        let register_bb_id =
+            GlobalBasicBlockId::new(register_span_info.def_id, register_span_info.basic_block_id);
+        for drop_obj_id in ctxt.pfg.get_projection_node(*ctx_id).points_to.iter() {
+            let drop_span_infos = &ctxt
+                .pfg
+                .get_projection_node((*drop_obj_id).into())
+                .cs_drop_spans;
+
+            for drop_span_info in drop_span_infos.iter() {
+                let drop_bb_id =
+                    GlobalBasicBlockId::new(drop_span_info.def_id, drop_span_info.basic_block_id);
+                // skip the drops on unwinding
+                let is_cleanup = ctxt
+                    .cfgs
+                    .get(&drop_span_info.def_id)
+                    .and_then(|cfg| cfg.basic_block_infos.get(&drop_span_info.basic_block_id))
+                    .map_or(false, |bb_info| bb_info.is_cleanup);
+                if !is_cleanup
+                    && drop_bb_id != register_bb_id
+                    && reach.can_arrive(register_bb_id, drop_bb_id)
+                {
+                    ffi_callback_infos.push(FfiCallbackInfo::new(
+                        *ctx_id,
+                        register_span_info.clone(),
+                        *drop_obj_id,
+                        drop_span_info.clone(),
+                    ));
+                }
+            }
         }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct DfResult {
-    pub first_drop_span: Span,
-    pub first_drop_var_name: Option<String>,
-    pub then_drop_span: Span,
-    pub then_drop_var_name: Option<String>,
+    ffi_callback_infos
 }
 
-impl DfResult {
-    pub fn new(
-        first_drop_span: Span,
-        first_drop_var_name: Option<String>,
-        then_drop_span: Span,
-        then_drop_var_name: Option<String>,
-    ) -> Self {
-        Self {
-            first_drop_span,
-            first_drop_var_name,
-            then_drop_span,
-            then_drop_var_name,
+// a local used again after it is moved out, directly or through a pointer taken from it before the move,
+// on a path that does not assign the local again. Only the local fns reached from the entry are scanned
+fn check_use_after_move(ctxt: &AnalysisContext) -> Vec<UseAfterMoveInfo> {
+    let mut use_after_move_infos = Vec::new();
+
+    let reachable_def_ids = ctxt.cs_reachable_calls.iter().map(|call_id| call_id.def_id).collect::<HashSet<_>>();
+    for def_id in reachable_def_ids.iter() {
+        if !ctxt.cfgs.get(def_id).map_or(false, |cfg| cfg.is_local_crate) {
+            continue;
         }
-    }
+        let body = utils::get_mir_body(ctxt.tcx, *def_id);
+        let span_info = |location: rustc_middle::mir::Location| {
+            CtxtSenSpanInfo::new(*def_id, location.block, body.source_info(location).span, CallerContext::new(vec![]))
+        };
+        let mut local_pointers = HashMap::<LocalId, HashSet<LocalId>>::new();
+        let mut reinit_locations = None;
+        let mut move_chains = HashMap::new();
 
-    pub fn has_var_name(&self) -> bool {
-        self.first_drop_var_name.is_some() || self.then_drop_var_name.is_some()
+        for (block, bb_data) in body.basic_blocks().iter_enumerated() {
+            if bb_data.is_cleanup {
+                continue;
+            }
+            for statement_index in 0..=bb_data.statements.len() {
+                let move_location = rustc_middle::mir::Location { block, statement_index };
+                let accesses = get_place_accesses(body, move_location);
+                for (place, context) in accesses.iter() {
+                    let moved_local = match place.as_local() {
+                        Some(local) if *context == PlaceContext::NonMutatingUse(NonMutatingUseContext::Move) => local,
+                        _ => continue,
+                    };
+                    // assigned again by the same statement, eg. `_1 = foo(move _1)`
+                    if accesses.iter().any(|(place, context)| place.local == moved_local && is_reinit(place, *context)) {
+                        continue;
+                    }
+
+                    let pointers = local_pointers
+                        .entry(moved_local)
+                        .or_insert_with(|| get_local_pointers(body, moved_local));
+                    for (used_local, use_location) in find_uses_after_move(body, move_location, moved_local, pointers) {
+                        let mut use_after_move_info = UseAfterMoveInfo::new(
+                            GlobalLocalId::new(*def_id, moved_local),
+                            span_info(move_location),
+                            GlobalLocalId::new(*def_id, used_local),
+                            span_info(use_location),
+                        );
+                        let (origin_local, earlier_moves) = move_chains.entry(moved_local).or_insert_with(|| {
+                            let reinit_locations = reinit_locations.get_or_insert_with(|| get_reinit_locations(body));
+                            get_move_chain(body, reinit_locations, moved_local)
+                        });
+                        use_after_move_info.origin_local_id = GlobalLocalId::new(*def_id, *origin_local);
+                        use_after_move_info.earlier_moves = earlier_moves
+                            .iter()
+                            .copied()
+                            .map(|(local, location)| (GlobalLocalId::new(*def_id, local), span_info(location)))
+                            .collect();
+                        use_after_move_infos.push(use_after_move_info);
+                    }
+                }
+            }
+        }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
-pub struct DfSpan {
-    pub first_drop_span: Span,
-    pub then_drop_span: Span,
+    use_after_move_infos
 }
 
-impl DfSpan {
-    pub fn new(first_drop_span: Span, then_drop_span: Span) -> Self {
-        Self {
-            first_drop_span,
-            then_drop_span,
+// the locations writing a new value into each local, see `is_reinit`
+fn get_reinit_locations(body: &Body<'_>) -> HashMap<LocalId, Vec<rustc_middle::mir::Location>> {
+    let mut reinit_locations = HashMap::<LocalId, Vec<_>>::new();
+    for (block, bb_data) in body.basic_blocks().iter_enumerated() {
+        for statement_index in 0..=bb_data.statements.len() {
+            let location = rustc_middle::mir::Location { block, statement_index };
+            for (place, context) in get_place_accesses(body, location) {
+                if is_reinit(&place, context) {
+                    let locations = reinit_locations.entry(place.local).or_default();
+                    if locations.last() != Some(&location) {
+                        locations.push(location);
+                    }
+                }
+            }
         }
     }
+    reinit_locations
 }
 
-trait SameSpan {
-    fn is_same_span(&self, other: &Self) -> bool;
+// the local the value of `local` started in, and the moves it came through, oldest first:
+// back from `local` while it is assigned once, by the move of another local, eg. `_3 = move _1`.
+// The chain is rebuilt from the MIR of this fn only, not from the pfg: a param stops it at the param,
+// a move out of a field at the local it was moved into
+fn get_move_chain(
+    body: &Body<'_>,
+    reinit_locations: &HashMap<LocalId, Vec<rustc_middle::mir::Location>>,
+    local: LocalId,
+) -> (LocalId, Vec<(LocalId, rustc_middle::mir::Location)>) {
+    let mut moves = vec![];
+    let mut local = local;
+    let mut visited = HashSet::from([local]);
+    loop {
+        let location = match reinit_locations.get(&local).map(|locations| locations.as_slice()) {
+            Some([location]) => *location,
+            _ => break,
+        };
+        let source = match body.basic_blocks()[location.block].statements.get(location.statement_index) {
+            Some(rustc_middle::mir::Statement {
+                kind: StatementKind::Assign(box (lvalue, Rvalue::Use(Operand::Move(source)))), ..
+            })
+                if lvalue.as_local() == Some(local) =>
+            {
+                source.as_local()
+            }
+            _ => None,
+        };
+        match source {
+            Some(source) if visited.insert(source) => {
+                moves.push((source, location));
+                local = source;
+            }
+            _ => break,
+        }
+    }
+    moves.reverse();
+    (local, moves)
 }
 
-#[derive(Debug)]
-pub struct UafInfo {
-    pub deref_proj_id: GlobalProjectionId,
-    pub deref_span: CtxtSenSpanInfo,
-    pub drop_obj_id: DropObjectId,
-    pub drop_span: CtxtSenSpanInfo,
+// the places accessed by a statement or terminator, with how they are accessed
+struct PlaceAccessCollector<'tcx> {
+    accesses: Vec<(Place<'tcx>, PlaceContext)>,
 }
 
-impl UafInfo {
-    pub fn new(
-        deref_proj_id: GlobalProjectionId,
-        deref_span: CtxtSenSpanInfo,
-        drop_obj_id: DropObjectId,
-        drop_span: CtxtSenSpanInfo,
-    ) -> Self {
-        Self {
-            deref_proj_id,
-            deref_span,
-            drop_obj_id,
-            drop_span,
-        }
+impl<'tcx> Visitor<'tcx> for PlaceAccessCollector<'tcx> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, _location: rustc_middle::mir::Location) {
+        self.accesses.push((*place, context));
     }
 }
 
-impl SameSpan for UafInfo {
-    fn is_same_span(&self, other: &Self) -> bool {
-        self.deref_span.span == other.deref_span.span && self.drop_span.span == other.drop_span.span
+fn get_place_accesses<'tcx>(
+    body: &Body<'tcx>,
+    location: rustc_middle::mir::Location,
+) -> Vec<(Place<'tcx>, PlaceContext)> {
+    let mut collector = PlaceAccessCollector { accesses: vec![] };
+    let bb_data = &body.basic_blocks()[location.block];
+    match bb_data.statements.get(location.statement_index) {
+        Some(statement) => collector.visit_statement(statement, location),
+        None => collector.visit_terminator(bb_data.terminator(), location),
     }
+    collector.accesses
 }
 
-#[derive(Debug)]
-pub struct DfInfo {
-    pub first_drop_obj_id: DropObjectId,
-    pub first_drop_span: CtxtSenSpanInfo,
-
-    pub then_drop_obj_id: DropObjectId,
-    pub then_drop_span: CtxtSenSpanInfo,
+// a write of a new value into the place, including the field by field initialization of an aggregate
+fn is_reinit(place: &Place<'_>, context: PlaceContext) -> bool {
+    !place.is_indirect()
+        && matches!(
+            context,
+            PlaceContext::MutatingUse(
+                MutatingUseContext::Store
+                    | MutatingUseContext::Call
+                    | MutatingUseContext::AsmOutput
+                    | MutatingUseContext::Yield
+                    | MutatingUseContext::SetDiscriminant
+                    | MutatingUseContext::Deinit
+            )
+        )
 }
 
-impl DfInfo {
-    pub fn new(
-        first_drop_obj_id: DropObjectId,
-        first_drop_span: CtxtSenSpanInfo,
-        then_drop_obj_id: DropObjectId,
-        then_drop_span: CtxtSenSpanInfo,
-    ) -> Self {
-        Self {
-            first_drop_obj_id,
-            first_drop_span,
-            then_drop_obj_id,
-            then_drop_span,
+// the pointers taken from the local by reference or raw address, then reborrowed, copied or cast,
+// not the pointers to what the local itself points to, which a move does not invalidate
+fn get_local_pointers(body: &Body<'_>, local: LocalId) -> HashSet<LocalId> {
+    let mut pointers = HashSet::new();
+    loop {
+        let mut changed = false;
+        for bb_data in body.basic_blocks().iter() {
+            for statement in bb_data.statements.iter() {
+                let (lvalue, rvalue) = match &statement.kind {
+                    StatementKind::Assign(box (lvalue, rvalue)) if lvalue.projection.is_empty() => (lvalue, rvalue),
+                    _ => continue,
+                };
+                let is_pointer = match rvalue {
+                    Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) => {
+                        (place.local == local && !place.is_indirect())
+                            || (pointers.contains(&place.local)
+                                && place.projection.first() == Some(&PlaceElem::Deref)
+                                && !place.projection[1..].contains(&PlaceElem::Deref))
+                    }
+                    Rvalue::Use(Operand::Copy(place) | Operand::Move(place))
+                    | Rvalue::Cast(_, Operand::Copy(place) | Operand::Move(place), _) => {
+                        place.as_local().map_or(false, |place_local| pointers.contains(&place_local))
+                    }
+                    _ => false,
+                };
+                if is_pointer && pointers.insert(lvalue.local) {
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            return pointers;
         }
     }
 }
 
-impl SameSpan for DfInfo {
-    fn is_same_span(&self, other: &Self) -> bool {
-        self.first_drop_span.span == other.first_drop_span.span
-            && self.then_drop_span.span == other.then_drop_span.span
+// the first uses of the moved local, or dereferences of its pointers, on each path from the move
+// until the local is assigned again, the paths of unwinding are not followed
+fn find_uses_after_move(
+    body: &Body<'_>,
+    move_location: rustc_middle::mir::Location,
+    moved_local: LocalId,
+    pointers: &HashSet<LocalId>,
+) -> Vec<(LocalId, rustc_middle::mir::Location)> {
+    let mut uses = Vec::new();
+    let mut visited = HashSet::new();
+    let mut work_list = vec![(move_location.block, move_location.statement_index + 1)];
+    while let Some((block, start)) = work_list.pop() {
+        let bb_data = &body.basic_blocks()[block];
+        if bb_data.is_cleanup {
+            continue;
+        }
+
+        let mut is_path_end = false;
+        for statement_index in start..=bb_data.statements.len() {
+            let location = rustc_middle::mir::Location { block, statement_index };
+            let accesses = get_place_accesses(body, location);
+            // the operands are read before the result is written
+            let used_local = accesses.iter().find_map(|(place, context)| match context {
+                PlaceContext::NonUse(_)
+                | PlaceContext::MutatingUse(MutatingUseContext::Drop | MutatingUseContext::Retag) => None,
+                _ if place.local == moved_local && !is_reinit(place, *context) => Some(place.local),
+                _ if pointers.contains(&place.local) && place.projection.first() == Some(&PlaceElem::Deref) => {
+                    Some(place.local)
+                }
+                _ => None,
+            });
+            if let Some(used_local) = used_local {
+                uses.push((used_local, location));
+                is_path_end = true;
+                break;
+            }
+            if accesses.iter().any(|(place, context)| place.local == moved_local && is_reinit(place, *context)) {
+                is_path_end = true;
+                break;
+            }
+        }
+        if !is_path_end {
+            for successor in bb_data.terminator().successors() {
+                if visited.insert(successor) {
+                    work_list.push((successor, 0));
+                }
+            }
+        }
     }
+    uses
 }
 
-fn check_df(ctxt: &AnalysisContext) -> Vec<DfInfo> {
-    let mut df_infos = Vec::new();
+// an rc owns the value moved into `Rc::new` and the rcs stored behind the pointers derived from it,
+// the rcs owning each other never drop to zero and leak
+fn check_rc_cycle(ctxt: &AnalysisContext) -> Vec<RcCycleInfo> {
+    let creations = &ctxt.pfg.rc_creations;
+    let rc_ids = creations.iter().map(|(rc_id, _, _)| *rc_id).collect::<HashSet<_>>();
+    let mut clones = HashMap::<GlobalProjectionId, Vec<GlobalProjectionId>>::new();
+    for (rc_id, cloned_id) in ctxt.pfg.rc_clones.iter() {
+        clones.entry(*rc_id).or_default().push(*cloned_id);
+    }
 
-    for first_drop_obj_id in ctxt.pfg.multi_drop_objects.iter() {
-        let points_to = &ctxt
-            .pfg
-            .get_projection_node((*first_drop_obj_id).into())
-            .points_to;
-        // If the object is not pointed by any other object, it is not a double free.
-        if points_to.len() <= 1 {
-            continue;
+    // where the rc flows to, without flowing through the other rcs which own it,
+    // the rc values may flow through the bodies of std functions like `Rc::clone`
+    let flows_of = |rc_id: GlobalProjectionId, only_rc_typed: bool| {
+        let mut visited = HashSet::new();
+        let mut work_list = vec![rc_id];
+        while let Some(g_proj_id) = work_list.pop() {
+            if (g_proj_id != rc_id && rc_ids.contains(&g_proj_id))
+                || (only_rc_typed
+                    && g_proj_id.g_local_id.def_id.is_local()
+                    && !get_projection_ty(ctxt, g_proj_id).map_or(false, utils::contains_rc_ty))
+                || !visited.insert(g_proj_id)
+            {
+                continue;
+            }
+            let proj_node = ctxt.pfg.get_projection_node(g_proj_id);
+            work_list.extend(proj_node.neighbors.keys());
+            work_list.extend(clones.get(&g_proj_id).into_iter().flatten());
+            // the nearest place containing the rc holds it too, eg. `_1` of `(_1 as Some).0`,
+            // but not the pointer it is behind
+            if only_rc_typed {
+                let projection = &proj_node.projection;
+                let parent_proj_id = (0..projection.len())
+                    .rev()
+                    .take_while(|len| projection[*len] != PlaceElem::Deref)
+                    .find_map(|len| {
+                        ctxt.pfg
+                            .get_node(g_proj_id.g_local_id)
+                            .try_get_projection_id(&projection[..len], &proj_node.caller_context)
+                    });
+                if let Some(parent_proj_id) = parent_proj_id {
+                    work_list.push(GlobalProjectionId::new(g_proj_id.g_local_id, parent_proj_id));
+                }
+            }
+            for (sub_proj_id, sub_proj_node) in ctxt.pfg.get_node(g_proj_id.g_local_id).projection_nodes.iter() {
+                if *sub_proj_id != g_proj_id.projection_id
+                    && proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                    && proj_node.is_prefix_of(&sub_proj_node.projection)
+                {
+                    work_list.push(GlobalProjectionId::new(g_proj_id.g_local_id, *sub_proj_id));
+                }
+            }
         }
-
-        // exclude itself
-        let then_drop_objs = points_to
+        visited
+    };
+    // the place or one of its fields holds the flowing rc
+    let holds = |flows: &HashSet<GlobalProjectionId>, g_proj_id: GlobalProjectionId| {
+        let proj_node = ctxt.pfg.get_projection_node(g_proj_id);
+        ctxt.pfg
+            .get_node(g_proj_id.g_local_id)
+            .projection_nodes
             .iter()
-            .filter(|&obj_id| obj_id != first_drop_obj_id)
-            .map(|obj_id| *obj_id)
-            .collect::<HashSet<DropObjectId>>();
-
-        let first_drop_span_infos = &ctxt
-            .pfg
-            .get_projection_node((*first_drop_obj_id).into())
-            .cs_drop_spans;
-
-        for then_drop_obj in then_drop_objs.iter() {
-            let then_drop_span_infos = &ctxt
-                .pfg
-                .get_projection_node((*then_drop_obj).into())
-                .cs_drop_spans;
-
-            let product = first_drop_span_infos
-                .iter()
-                .cartesian_product(then_drop_span_infos.iter());
-
-            for (first_drop_span_info, then_drop_span_info) in product {
-                let first_drop_bb_id = GlobalBasicBlockId::new(
-                    first_drop_span_info.def_id,
-                    first_drop_span_info.basic_block_id,
-                );
-                let then_drop_bb_id = GlobalBasicBlockId::new(
-                    then_drop_span_info.def_id,
-                    then_drop_span_info.basic_block_id,
-                );
-
-                // if first drop object can arrive then drop object, it is a double free.
-                if utils::can_basic_block_arrive(
-                    &ctxt.cfgs,
-                    &ctxt.called_infos,
-                    &mut HashSet::new(),
-                    first_drop_bb_id,
-                    then_drop_bb_id,
-                ) {
-                    let target_info = DfInfo::new(
-                        *first_drop_obj_id,
-                        first_drop_span_info.clone(),
-                        *then_drop_obj,
-                        then_drop_span_info.clone(),
-                    );
+            .any(|(sub_proj_id, sub_proj_node)| {
+                proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                    && proj_node.is_prefix_of(&sub_proj_node.projection)
+                    && flows.contains(&GlobalProjectionId::new(g_proj_id.g_local_id, *sub_proj_id))
+            })
+    };
 
-                    // if !contains_same_span(&df_infos, &target_info) {
-                    df_infos.push(target_info);
-                    // }
+    let derived_ptrs = creations
+        .iter()
+        .map(|(rc_id, _, _)| flows_of(*rc_id, false))
+        .collect::<Vec<_>>();
+    // owns[i] contains j if the rc i owns the rc j
+    let mut owns = vec![HashSet::new(); creations.len()];
+    for (j, (rc_id, _, _)) in creations.iter().enumerate() {
+        let rc_values = flows_of(*rc_id, true);
+        for (i, (_, value_id, _)) in creations.iter().enumerate() {
+            if value_id.map_or(false, |value_id| holds(&rc_values, value_id)) {
+                owns[i].insert(j);
+            }
+        }
+        for (ptr_id, stored_id, _) in ctxt.pfg.rc_stores.iter() {
+            if holds(&rc_values, *stored_id) {
+                for (i, ptrs) in derived_ptrs.iter().enumerate() {
+                    if ptrs.contains(ptr_id) {
+                        owns[i].insert(j);
+                    }
                 }
+            }
+        }
+    }
 
-                // if then drop object can arrive first drop object, it is a double free.
-                if utils::can_basic_block_arrive(
-                    &ctxt.cfgs,
-                    &ctxt.called_infos,
-                    &mut HashSet::new(),
-                    then_drop_bb_id,
-                    first_drop_bb_id,
-                ) {
-                    let target_info = DfInfo::new(
-                        *then_drop_obj,
-                        then_drop_span_info.clone(),
-                        *first_drop_obj_id,
-                        first_drop_span_info.clone(),
-                    );
-
-                    // if !contains_same_span(&df_infos, &target_info) {
-                    df_infos.push(target_info);
-                    // }
+    let reachables = (0..creations.len())
+        .map(|i| {
+            let mut visited = HashSet::new();
+            let mut work_list = owns[i].iter().copied().collect::<Vec<_>>();
+            while let Some(j) = work_list.pop() {
+                if visited.insert(j) {
+                    work_list.extend(owns[j].iter());
                 }
             }
+            visited
+        })
+        .collect::<Vec<_>>();
+
+    // one result for each strongly connected component
+    let mut rc_cycle_infos = Vec::new();
+    let mut reported = HashSet::new();
+    for i in 0..creations.len() {
+        if !reachables[i].contains(&i) || reported.contains(&i) {
+            continue;
         }
+        let cycle = reachables[i]
+            .iter()
+            .copied()
+            .filter(|j| reachables[*j].contains(&i))
+            .sorted()
+            .collect::<Vec<_>>();
+        reported.extend(cycle.iter().copied());
+        rc_cycle_infos.push(RcCycleInfo::new(
+            cycle
+                .iter()
+                .map(|j| (creations[*j].0, creations[*j].2.clone()))
+                .collect(),
+        ));
     }
 
-    df_infos
+    rc_cycle_infos
+}
+
+fn get_projection_ty<'tcx>(ctxt: &AnalysisContext<'tcx>, g_proj_id: GlobalProjectionId) -> Option<rustc_middle::ty::Ty<'tcx>> {
+    let cfg = ctxt.cfgs.get(&g_proj_id.g_local_id.def_id)?;
+    let local_ty = cfg.local_infos.get(&g_proj_id.g_local_id.local_id)?.ty;
+    let mut place_ty = PlaceTy::from_ty(local_ty);
+    for elem in ctxt.pfg.get_projection_node(g_proj_id).projection.iter() {
+        place_ty = place_ty.projection_ty(ctxt.tcx, *elem);
+    }
+    Some(place_ty.ty)
 }
 
-fn check_uaf(ctxt: &AnalysisContext) -> Vec<UafInfo> {
+fn check_uaf(ctxt: &AnalysisContext, reach: &ReachabilityCache) -> Vec<UafInfo> {
     let mut uaf_infos = Vec::new();
+    // (object, dereferenced projection) -> how the object flows to the projection
+    let mut alias_path = HashMap::<(DropObjectId, GlobalProjectionId), Vec<ProjectionNeighborInfo>>::new();
+    let mut alloc_spans = HashMap::<DropObjectId, Option<CtxtSenSpanInfo>>::new();
 
     let mut add_uaf_infos = |deref_proj_id: GlobalProjectionId,
                              deref_span_info: &CtxtSenSpanInfo| {
-        for drop_obj_id in ctxt.pfg.get_projection_node(deref_proj_id).points_to.iter() {
+        let deref_bb_id =
+            GlobalBasicBlockId::new(deref_span_info.def_id, deref_span_info.basic_block_id);
+        let points_to = &ctxt.pfg.get_projection_node(deref_proj_id).points_to;
+        // the value shared by rc handles is alive until all of them are dropped
+        let is_rc_alive = points_to
+            .iter()
+            .filter(|obj_id| ctxt.pfg.rc_handle_objects.contains(obj_id))
+            .any(|obj_id| {
+                !ctxt.pfg.get_projection_node((*obj_id).into()).cs_drop_spans.iter().any(|span_info| {
+                    let drop_bb_id = GlobalBasicBlockId::new(span_info.def_id, span_info.basic_block_id);
+                    reach.can_arrive(drop_bb_id, deref_bb_id) && drop_bb_id != deref_bb_id
+                })
+            });
+        for drop_obj_id in points_to.iter() {
+            if is_rc_alive && ctxt.pfg.rc_handle_objects.contains(drop_obj_id) {
+                continue;
+            }
+            if is_taken_from(ctxt, *drop_obj_id, deref_proj_id) {
+                continue;
+            }
             let drop_span_infos = &ctxt
                 .pfg
                 .get_projection_node((*drop_obj_id).into())
@@ -581,48 +3588,56 @@ fn check_uaf(ctxt: &AnalysisContext) -> Vec<UafInfo> {
             for drop_span_info in drop_span_infos.iter() {
                 let drop_bb_id =
                     GlobalBasicBlockId::new(drop_span_info.def_id, drop_span_info.basic_block_id);
-                let deref_bb_id =
-                    GlobalBasicBlockId::new(deref_span_info.def_id, deref_span_info.basic_block_id);
-                if utils::can_basic_block_arrive(
-                    &ctxt.cfgs,
-                    &ctxt.called_infos,
-                    &mut HashSet::new(),
-                    drop_bb_id,
-                    deref_bb_id,
-                ) && drop_bb_id != deref_bb_id
-                {
-                    let target_info = UafInfo::new(
+                if reach.can_arrive(drop_bb_id, deref_bb_id) && drop_bb_id != deref_bb_id {
+                    let mut target_info = UafInfo::new(
                         deref_proj_id,
                         deref_span_info.clone(),
                         *drop_obj_id,
                         drop_span_info.clone(),
                     );
+                    target_info.alias_edge = alias_path
+                        .entry((*drop_obj_id, deref_proj_id))
+                        .or_insert_with(|| {
+                            ctxt.pfg
+                                .flow_path((*drop_obj_id).into(), deref_proj_id)
+                                .unwrap_or_default()
+                        })
+                        .iter()
+                        .find(|edge| {
+                            edge.span_info.span != drop_span_info.span
+                                && edge.span_info.span != deref_span_info.span
+                        })
+                        .cloned();
+                    target_info.alloc_span = alloc_spans
+                        .entry(*drop_obj_id)
+                        .or_insert_with(|| ctxt.pfg.get_alloc_site(*drop_obj_id).cloned())
+                        .clone();
 
                     if utils::has_dbg(&ctxt.options, "bug-path") {
-                        let mut bug_path: Vec<GlobalBasicBlockId> = vec![];
-                        utils::can_basic_block_arrive_with_path_record(
-                            &ctxt.cfgs,
-                            &ctxt.called_infos,
-                            &mut HashSet::new(),
-                            drop_bb_id,
-                            deref_bb_id,
-                            &mut bug_path,
-                        );
+                        let bug_path =
+                            utils::basic_block_path(&ctxt.cfgs, &ctxt.called_infos, drop_bb_id, deref_bb_id)
+                                .unwrap_or_default();
 
                         log::debug!("bug path from drop {:?} to deref {:?}", drop_bb_id, deref_bb_id);
                         log::debug!("from span {:?} to span {:?}", drop_span_info.span, deref_span_info.span);
                         log::debug!("path: {:#?}", bug_path);
                     }
 
-                    // if !contains_same_span(&uaf_infos, &target_info) {
-                    uaf_infos.push(target_info);
-                    // }
+                    // the same drop and dereference in other contexts
+                    if !contains_same_span(&uaf_infos, &target_info) {
+                        uaf_infos.push(target_info);
+                    }
                 }
             }
         }
     };
 
     for deref_edge_info in ctxt.pfg.deref_edges.iter() {
+        // reads through a shared reference or a const pointer
+        if utils::only_mut_deref(&ctxt.options) && !deref_edge_info.is_mut {
+            continue;
+        }
+
         let from = deref_edge_info.from;
         let to = deref_edge_info.to;
 
@@ -645,7 +3660,7 @@ fn check_uaf(ctxt: &AnalysisContext) -> Vec<UafInfo> {
     uaf_infos
 }
 
-fn _contains_same_span<T: SameSpan>(infos: &Vec<T>, target: &T) -> bool {
+fn contains_same_span<T: SameSpan>(infos: &Vec<T>, target: &T) -> bool {
     for info in infos.iter() {
         if info.is_same_span(target) {
             return true;
@@ -696,12 +3711,13 @@ pub fn output_uaf_infos(uaf_infos: &Vec<UafInfo>) {
             let i = i + drop_line_range.0;
             println!("{} {}{}", i.to_string().blue(), "|".blue(), line);
         }
+        let (drop_indent, drop_underline_len) = utils::get_caret_indent(drop_col_range);
         println!(
             "{} {}{}{} {}",
             " ".repeat(drop_line_char_width),
             "|".blue(),
-            " ".repeat(drop_col_range.0 - 1),
-            "^".repeat(drop_col_range.1 - drop_col_range.0).yellow(),
+            " ".repeat(drop_indent),
+            utils::underline(drop_underline_len).yellow(),
             " first drop here".yellow()
         );
         println!("{} {}", " ".repeat(drop_line_char_width), "|".blue());
@@ -724,12 +3740,13 @@ pub fn output_uaf_infos(uaf_infos: &Vec<UafInfo>) {
             let i = i + deref_line_range.0;
             println!("{} {}{}", i.to_string().blue(), "|".blue(), line);
         }
+        let (deref_indent, deref_underline_len) = utils::get_caret_indent(deref_col_range);
         println!(
             "{} {}{}{} {}",
             " ".repeat(deref_line_char_width),
             "|".blue(),
-            " ".repeat(deref_col_range.0 - 1),
-            "^".repeat(deref_col_range.1 - deref_col_range.0).yellow(),
+            " ".repeat(deref_indent),
+            utils::underline(deref_underline_len).yellow(),
             " then dereference here".yellow()
         );
         println!("{} {}", " ".repeat(deref_line_char_width), "|".blue());
@@ -778,12 +3795,13 @@ pub fn output_df_infos(df_infos: &Vec<DfInfo>) {
             let i = i + first_drop_line_range.0;
             println!("{} {}{}", i.to_string().blue(), "|".blue(), line);
         }
+        let (first_drop_indent, first_drop_underline_len) = utils::get_caret_indent(first_drop_col_range);
         println!(
             "{} {}{}{} {}",
             " ".repeat(first_drop_line_char_width),
             "|".blue(),
-            " ".repeat(first_drop_col_range.0 - 1),
-            "^".repeat(first_drop_col_range.1 - first_drop_col_range.0)
+            " ".repeat(first_drop_indent),
+            utils::underline(first_drop_underline_len)
                 .yellow(),
             " first drop here".yellow()
         );
@@ -807,12 +3825,13 @@ pub fn output_df_infos(df_infos: &Vec<DfInfo>) {
             let i = i + then_drop_line_range.0;
             println!("{} {}{}", i.to_string().blue(), "|".blue(), line);
         }
+        let (then_drop_indent, then_drop_underline_len) = utils::get_caret_indent(then_drop_col_range);
         println!(
             "{} {}{}{} {}",
             " ".repeat(then_drop_line_char_width),
             "|".blue(),
-            " ".repeat(then_drop_col_range.0 - 1),
-            "^".repeat(then_drop_col_range.1 - then_drop_col_range.0)
+            " ".repeat(then_drop_indent),
+            utils::underline(then_drop_underline_len)
                 .yellow(),
             " then drop here".yellow()
         );
@@ -823,6 +3842,62 @@ pub fn output_df_infos(df_infos: &Vec<DfInfo>) {
 
 // level print
 // \yellow$level:   \cyan(memroy\scheck)   \normal\s$text
+/// Re-renders a finding saved as a rustc like json diagnostic, eg.
+/// `{"level": "warning", "message": "...", "spans": [{"file_name": "src/main.rs", "line_start": 3,
+/// "line_end": 3, "column_start": 5, "column_end": 6, "label": "first drop here", "text": [{"text": "..."}]}]}`.
+/// The saved `text` of a span is shown if the source file has changed or is missing
+pub fn explain_finding(json: &str) -> Result<(), String> {
+    let finding: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("invalid finding json: {}", e))?;
+    let message = finding["message"].as_str().ok_or("missing `message` of finding")?;
+    // emitted as a rustc diagnostic, eg. with `--error-format=json`
+    let message = message.strip_prefix("(memory check) ").unwrap_or(message);
+    let spans = finding["spans"].as_array().ok_or("missing `spans` of finding")?;
+
+    output_level_text(finding["level"].as_str().unwrap_or("warning"), message);
+    for span in spans.iter() {
+        let get_usize = |key: &str| {
+            span[key]
+                .as_u64()
+                .map(|value| value as usize)
+                .ok_or(format!("missing `{}` of span", key))
+        };
+        let filename = span["file_name"].as_str().ok_or("missing `file_name` of span")?;
+        let line_range = (get_usize("line_start")?, get_usize("line_end")?);
+        let col_range = (get_usize("column_start")?, get_usize("column_end")?);
+        let label = span["label"].as_str().unwrap_or_default();
+
+        let saved_lines = span["text"].as_array().map(|texts| {
+            texts
+                .iter()
+                .filter_map(|text| text["text"].as_str().map(|s| s.to_owned()))
+                .collect::<Vec<_>>()
+        });
+        let lines = match (utils::try_get_lines_in_file(filename, line_range), saved_lines) {
+            (Ok(lines), Some(saved_lines)) if lines != saved_lines => {
+                output_level_text(
+                    "warning",
+                    &format!("{} has changed since the finding was produced, show the saved code", filename),
+                );
+                saved_lines
+            }
+            (Ok(lines), _) => lines,
+            (Err(err), Some(saved_lines)) => {
+                output_level_text(
+                    "warning",
+                    &format!("cannot read {}: {}, show the saved code", filename, err),
+                );
+                saved_lines
+            }
+            (Err(err), None) => return Err(format!("cannot read {}: {}", filename, err)),
+        };
+        output_lines_and_problem_info(filename, &lines, line_range, col_range, label);
+    }
+    utils::println_with_color("", Color::White).unwrap();
+    utils::println_with_color("", Color::White).unwrap();
+    Ok(())
+}
+
 pub fn output_level_text(level: &str, text: &str) {
     let s = format!("{}:", level);
     utils::print_with_color(
@@ -851,7 +3926,7 @@ pub fn output_level_text(level: &str, text: &str) {
 
 \blue$line_nb\s|\s   \normal$line_text
 
-\blue(\s).repeat($max_line_char_width+1)|\s   \yellow(\s).repeat($col_start-1)(^).repeat($col_end-$col_start+1)\s$problem_text(if line_nb==line_hi)
+\blue(\s).repeat($max_line_char_width+1)|\s   \yellow(\s).repeat($col_start-1)($underline_char).repeat($col_end-$col_start+1)\s$problem_text(if line_nb==line_hi)
 
 \blue(\s).repeat($max_line_char_width+1)|\s
 
@@ -861,13 +3936,25 @@ fn output_code_and_problem_info(
     line_range: (usize, usize),
     col_range: (usize, usize),
     problem_text: &str,
+) {
+    let lines = utils::get_lines_in_file(filename, line_range);
+    output_lines_and_problem_info(filename, &lines, line_range, col_range, problem_text);
+}
+
+fn output_lines_and_problem_info(
+    filename: &str,
+    lines: &[String],
+    line_range: (usize, usize),
+    col_range: (usize, usize),
+    problem_text: &str,
 ) {
     let max_line_char_width = std::cmp::max(
         line_range.0.to_string().len(),
         line_range.1.to_string().len(),
     );
 
-    let lines = utils::get_lines_in_file(filename, line_range);
+    // the room left for the code after the line number gutter
+    let code_width = utils::get_terminal_width().saturating_sub(max_line_char_width + 3);
 
     // code and problem
     // print -->
@@ -884,25 +3971,23 @@ fn output_code_and_problem_info(
     for (i, line) in lines.iter().enumerate() {
         let i = i + line_range.0;
 
-        // print line
-        let s = format!("{} | ", i);
+        // print line, long lines are elided around the highlight
+        let col_range = utils::get_underline_cols(line, i, line_range, col_range);
+        let (line, col_range) = utils::elide_line(line, col_range, code_width);
+        let (col_start, col_end) = utils::get_display_cols(&line, col_range);
+
+        let s = format!("{:>width$} | ", i, width = max_line_char_width);
         utils::print_with_color(&s, Color::Blue).unwrap();
         let s = format!("{}", line);
         utils::println_with_color(&s, Color::White).unwrap();
 
         // print ^
-        let col_start = if i == line_range.0 { col_range.0 } else { 1 };
-        let col_end = if i == line_range.1 {
-            col_range.1
-        } else {
-            line.len()
-        };
         let s = format!("{}| ", " ".repeat(max_line_char_width + 1));
         utils::print_with_color(&s, Color::Blue).unwrap();
         let s = format!(
             "{}{} ",
-            " ".repeat(col_start - 1),
-            "^".repeat(col_end - col_start + 1)
+            " ".repeat(col_start.saturating_sub(1)),
+            utils::underline((col_end + 1).saturating_sub(col_start))
         );
         utils::print_with_color(&s, Color::Yellow).unwrap();
         if i == line_range.1 {
@@ -17,10 +17,98 @@ pub mod pfg;
 pub struct AnalysisOptions {
     pub debug_opts: Vec<String>,
     pub entries: Vec<String>,
+    pub entries_regex: Vec<regex::Regex>,
+    // only the findings of the matching entry are reported
+    pub focus_entry: Option<String>,
+    // the findings with all spans in the matching files are not reported
+    pub exclude_paths: globset::GlobSet,
     pub open_dbg: bool,
     pub open_same_level: bool,
     pub let_arg_as_deref: bool,
     pub check_std: bool,
+    pub analyze_std: Vec<String>,
+    pub changed_only: Option<String>,
+    pub rc_weak: bool,
+    pub verbose_df: bool,
+    pub alias_assign: bool,
+    pub format: OutputFormat,
+    pub underline_char: char,
+    pub const_eval: bool,
+    pub object_granularity: ObjectGranularity,
+    pub ffi_callback: bool,
+    pub rc_cycle: bool,
+    pub guard_escape: bool,
+    pub iter_escape: bool,
+    pub use_after_move: bool,
+    pub summary_by_function: bool,
+    pub call_path: bool,
+    pub only_mut_deref: bool,
+    pub warn_unsupported: bool,
+    pub list_entries: bool,
+    pub print_cfgs: Vec<String>,
+    // the pfg of each entry is appended to the file
+    pub dump_pfg: Option<String>,
+    pub report_path: Option<String>,
+    // `-` for stdout
+    pub json_summary: Option<String>,
+    pub context_depth: usize,
+    pub max_findings: Option<usize>,
+    pub timeout: Option<u64>,
+    // the slowest fns printed after the analysis
+    pub profile: Option<usize>,
+    // the size of the pfg of each entry printed after the analysis
+    pub stats: bool,
+    pub quiet: bool,
+    pub no_color: bool,
+    pub models: Vec<(String, ModelKind)>,
+}
+
+impl AnalysisOptions {
+    /// Registers a model for the functions whose def path ends with `name`, eg. `Foo::take`.
+    /// The model replaces the analysis of the function body.
+    pub fn with_model(mut self, name: &str, kind: ModelKind) -> Self {
+        self.models.push((name.to_owned(), kind));
+        self
+    }
+}
+
+/// Which drops share one object
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ObjectGranularity {
+    /// all drops of a place in one calling context, precise
+    Local,
+    /// all drops of a type, fewer objects make it faster but aliases of the same type are missed
+    Type,
+}
+
+/// How the findings are printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// colored text
+    Text,
+    /// plain text without any ANSI escape
+    TextNoColor,
+    /// rustc diagnostics, in json under `--error-format=json`, otherwise rendered as the compiler warnings
+    Json,
+    /// LSP `textDocument/publishDiagnostics` notifications in json
+    Lsp,
+    /// one json array of the uaf, df and dp findings with both spans, for CI
+    JsonReport,
+    /// a SARIF 2.1.0 log of the uaf, df and dp findings, for GitHub code scanning
+    Sarif,
+}
+
+/// How a modeled call affects the ownership, the argument indices start from 0
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelKind {
+    /// the return value aliases all arguments
+    ArgToRet,
+    /// the call is ignored
+    Ignore,
+    /// the objects of `from_arg` are moved into `to_arg`, or into what it points to
+    Transfer { from_arg: usize, to_arg: usize },
+    /// the object of `arg` is dropped by the call
+    Drop { arg: usize },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Copy)]
@@ -50,6 +138,19 @@ impl CallerContext {
         CallerContext { g_bb_ids }
     }
 
+    /// The context of a callee called at `g_bb_id`, which keeps the last `depth` call sites
+    pub fn push(&self, g_bb_id: GlobalBasicBlockId, depth: usize) -> Self {
+        let mut g_bb_ids = self.g_bb_ids.clone();
+        g_bb_ids.push(g_bb_id);
+        let start = g_bb_ids.len().saturating_sub(depth);
+        CallerContext::new(g_bb_ids.split_off(start))
+    }
+
+    /// Whether one of the call sites is in the fn, ie. the fn is already on the call chain
+    pub fn has_caller(&self, def_id: DefId) -> bool {
+        self.g_bb_ids.iter().any(|g_bb_id| g_bb_id.def_id == def_id)
+    }
+
     pub fn is_same(&self, other: &CallerContext) -> bool {
         // log::debug!("left caller context: {:?}", self);
         // log::debug!("right caller context: {:?}", other);
@@ -142,7 +243,7 @@ pub struct GlobalBasicBlockId {
     pub bb_id: BasicBlockId,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CallInfo<'tcx> {
     pub callee_def_id: DefId,
     pub caller_bb_id: BasicBlockId,
@@ -150,6 +251,9 @@ pub struct CallInfo<'tcx> {
     pub args: Vec<Operand<'tcx>>,
     pub destination: Place<'tcx>,
     pub span: rustc_span::Span,
+    // the methods of the impls in the local crate a trait method call may go to, when the
+    // receiver is a `dyn Trait` or a type parameter
+    pub impl_callee_def_ids: Vec<DefId>,
 }
 
 impl<'tcx> CallInfo<'tcx> {
@@ -168,7 +272,28 @@ impl<'tcx> CallInfo<'tcx> {
             args,
             destination,
             span,
+            impl_callee_def_ids: vec![],
+        }
+    }
+
+    // the callee and the impl methods it may go to
+    pub fn callees(&self) -> impl Iterator<Item = DefId> + '_ {
+        std::iter::once(self.callee_def_id).chain(self.impl_callee_def_ids.iter().copied())
+    }
+
+    // the calls to each impl method instead of the trait method, or the call itself
+    pub fn impl_calls(&self) -> Vec<CallInfo<'tcx>> {
+        if self.impl_callee_def_ids.is_empty() {
+            return vec![self.clone()];
         }
+        self.impl_callee_def_ids
+            .iter()
+            .map(|def_id| CallInfo {
+                callee_def_id: *def_id,
+                impl_callee_def_ids: vec![],
+                ..self.clone()
+            })
+            .collect()
     }
 }
 
@@ -215,6 +340,8 @@ pub struct AssignmentInfo<'tcx> {
     pub rvalue: RvalKind<'tcx>,
     pub stat_span: rustc_span::Span,
     pub op: OpKind,
+    // borrowed mutably, eg. `&mut (*_1)` or `&raw mut (*_1)`
+    pub is_mut: bool,
 }
 
 impl<'tcx> AssignmentInfo<'tcx> {
@@ -229,6 +356,7 @@ impl<'tcx> AssignmentInfo<'tcx> {
             rvalue,
             stat_span: span,
             op,
+            is_mut: false,
         }
     }
 }
@@ -1,11 +1,11 @@
 use super::{CtxtSenCallId, CtxtSenSpanInfo, DropObjectId};
-use super::{GlobalProjectionId, ProjectionId};
+use super::{GlobalBasicBlockId, GlobalProjectionId, ProjectionId};
 
 use crate::core::utils;
 use crate::core::CallerContext;
 use crate::core::GlobalLocalId;
-use rustc_middle::mir::{Place, PlaceElem};
-use std::collections::{HashMap, HashSet};
+use rustc_middle::mir::{Local, Place, PlaceElem};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use super::LocalId;
 
@@ -29,11 +29,13 @@ pub struct DerefEdgeInfo {
     pub from: GlobalProjectionId,
     pub to: GlobalProjectionId,
     pub is_deref: (bool, bool),
+    // writes through the pointer or borrows it mutably, eg. `*_1 = move _2` or `&mut (*_1)`
+    pub is_mut: bool,
 }
 
 impl DerefEdgeInfo {
-    pub fn new(from: GlobalProjectionId, to: GlobalProjectionId, is_deref: (bool, bool)) -> Self {
-        DerefEdgeInfo { from, to, is_deref }
+    pub fn new(from: GlobalProjectionId, to: GlobalProjectionId, is_deref: (bool, bool), is_mut: bool) -> Self {
+        DerefEdgeInfo { from, to, is_deref, is_mut }
     }
 }
 #[derive(Debug)]
@@ -83,7 +85,7 @@ impl<'tcx> ProjectionNode<'tcx> {
         }
 
         for i in 0..self.projection.len() {
-            if self.projection[i] != proj[i] {
+            if !is_same_elem(&self.projection[i], &proj[i]) {
                 return false;
             }
         }
@@ -97,7 +99,7 @@ impl<'tcx> ProjectionNode<'tcx> {
         }
 
         for i in 0..self.projection.len() {
-            if self.projection[i] != proj[i] {
+            if !is_same_elem(&self.projection[i], &proj[i]) {
                 return false;
             }
         }
@@ -110,10 +112,48 @@ impl<'tcx> ProjectionNode<'tcx> {
     }
 }
 
+// the index equality can't be proved, so a dynamic index may be any element,
+// all `Index(local)` of the same place share one projection whatever the index local is,
+// a constant index is only the same as the one at its offset, or any counted from the other end
+fn is_same_elem<'tcx>(elem: &PlaceElem<'tcx>, other: &PlaceElem<'tcx>) -> bool {
+    match (elem, other) {
+        (PlaceElem::Index(_), PlaceElem::ConstantIndex { .. })
+        | (PlaceElem::ConstantIndex { .. }, PlaceElem::Index(_)) => true,
+        (
+            PlaceElem::ConstantIndex { from_end, .. },
+            PlaceElem::ConstantIndex { from_end: other_from_end, .. },
+        ) if from_end != other_from_end => true,
+        _ => normalize_elem(elem) == normalize_elem(other),
+    }
+}
+
+// the elem with the index local and the minimum length of a constant index erased,
+// the elems `is_same_elem` tells equal apart from the index wildcards normalize to one elem
+fn normalize_elem<'tcx>(elem: &PlaceElem<'tcx>) -> PlaceElem<'tcx> {
+    match elem {
+        PlaceElem::Index(_) => PlaceElem::Index(Local::from_u32(0)),
+        PlaceElem::ConstantIndex { offset, from_end, .. } => PlaceElem::ConstantIndex {
+            offset: *offset,
+            min_length: 0,
+            from_end: *from_end,
+        },
+        _ => *elem,
+    }
+}
+
+// the key of a projection in `PfgNode::projection_index`, the lookup by it finds the projection
+// `is_same_projection` matches, except a dynamic index against a constant one and the constant
+// indices counted from the other end
+fn projection_key<'tcx>(proj: &[PlaceElem<'tcx>]) -> Vec<PlaceElem<'tcx>> {
+    proj.iter().map(normalize_elem).collect()
+}
+
 #[derive(Debug)]
 pub struct PfgNode<'tcx> {
     pub gid: GlobalLocalId,
     pub projection_nodes: HashMap<ProjectionId, ProjectionNode<'tcx>>,
+    // index of projection nodes by (projection, caller context), keeps lookups O(1)
+    projection_index: HashMap<(Vec<PlaceElem<'tcx>>, CallerContext), ProjectionId>,
 }
 
 impl<'tcx> PfgNode<'tcx> {
@@ -121,35 +161,27 @@ impl<'tcx> PfgNode<'tcx> {
         PfgNode {
             gid,
             projection_nodes: HashMap::new(),
+            projection_index: HashMap::new(),
         }
     }
 
     pub fn try_get_projection_node_mut(
         &mut self,
-        proj: &Vec<PlaceElem<'tcx>>,
+        proj: &[PlaceElem<'tcx>],
         caller_context: &CallerContext,
     ) -> Option<&mut ProjectionNode<'tcx>> {
-        for (_, node) in self.projection_nodes.iter_mut() {
-            if node.is_same_projection(proj) && node.caller_context == *caller_context {
-                return Some(node);
-            }
-        }
-
-        None
+        let id = self.try_get_projection_id(proj, caller_context)?;
+        self.projection_nodes.get_mut(&id)
     }
 
     pub fn try_get_projection_id(
         &self,
-        proj: &Vec<PlaceElem<'tcx>>,
+        proj: &[PlaceElem<'tcx>],
         caller_context: &CallerContext,
     ) -> Option<ProjectionId> {
-        for (id, node) in self.projection_nodes.iter() {
-            if node.is_same_projection(proj) && node.caller_context == *caller_context {
-                return Some(*id);
-            }
-        }
-
-        None
+        self.projection_index
+            .get(&(projection_key(proj), caller_context.clone()))
+            .copied()
     }
 
     pub fn has_projection(&self, proj: &Vec<PlaceElem<'tcx>>) -> bool {
@@ -168,6 +200,8 @@ impl<'tcx> PfgNode<'tcx> {
         caller_context: CallerContext,
     ) -> ProjectionId {
         let id = self.projection_nodes.len() as ProjectionId;
+        self.projection_index
+            .insert((projection_key(&proj), caller_context.clone()), id);
         let node = ProjectionNode::new(id, proj, vec![], caller_context);
         self.projection_nodes.insert(id, node);
         id
@@ -179,6 +213,48 @@ pub struct PointerFlowGraph<'tcx> {
     pub nodes: HashMap<GlobalLocalId, PfgNode<'tcx>>,
     pub deref_edges: HashSet<DerefEdgeInfo>,
     pub multi_drop_objects: HashSet<DropObjectId>,
+    // objects whose ownership is given up by `Box::into_raw`, with the span of the call
+    pub raw_released_objects: HashMap<DropObjectId, CtxtSenSpanInfo>,
+    // weak objects created by `Rc::downgrade`, with the borrowed strong rc
+    pub downgraded_weaks: HashMap<DropObjectId, GlobalProjectionId>,
+    // rcs returned by `Weak::upgrade`, with the borrowed weak
+    pub upgraded_weaks: HashMap<GlobalProjectionId, GlobalProjectionId>,
+    // guard objects created by `RefCell::borrow` or `Mutex::lock` etc., with the borrowed container
+    pub borrowed_guards: HashMap<DropObjectId, GlobalProjectionId>,
+    // pointers to guards passed to `Deref::deref` or `DerefMut::deref_mut`, with the span of the call
+    pub guard_derefs: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>,
+    // pointers to iterators passed to `Iterator::next`, with the span of the call
+    pub iter_nexts: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>,
+    // options unwrapped without checking, with the span of the call
+    pub unchecked_unwraps: HashMap<GlobalProjectionId, CtxtSenSpanInfo>,
+    // moves into an owned place behind a pointer, (the pointer, the moved value, the span of the assignment)
+    pub ptr_assigns: Vec<(GlobalProjectionId, GlobalProjectionId, CtxtSenSpanInfo)>,
+    // pointers passed to `ptr::drop_in_place`, with the span of the call
+    pub manual_drops: HashMap<GlobalProjectionId, CtxtSenSpanInfo>,
+    // values rebuilt from a raw pointer by `Vec::from_raw_parts` etc., (the pointer, the value, the span of the call)
+    pub raw_parts_owners: Vec<(GlobalProjectionId, GlobalProjectionId, CtxtSenSpanInfo)>,
+    // boxes allocated by `Box::new` or `exchange_malloc`, with the span of the call
+    pub allocations: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>,
+    // blocks calling `mem::forget`, the value moved in is never dropped
+    pub forget_calls: HashSet<GlobalBasicBlockId>,
+    // cleanup blocks dropping a local moved into `mem::drop` or `mem::forget`, with the block of the call,
+    // the drop flag of the local is cleared on the paths after the call
+    pub consumed_drops: HashMap<GlobalBasicBlockId, GlobalBasicBlockId>,
+    // `Option::take` and the like, (the container, the return value taking its value, the value moved
+    // in by `replace`)
+    pub taken_values: HashSet<(GlobalProjectionId, GlobalProjectionId, Option<GlobalProjectionId>)>,
+    // the shared object of each dropped type under `--object-granularity=type`
+    pub type_objects: HashMap<rustc_middle::ty::Ty<'tcx>, DropObjectId>,
+    // pointers passed to a foreign function together with a callback, with the span of the call
+    pub ffi_registrations: Vec<(GlobalProjectionId, CtxtSenSpanInfo)>,
+    // rcs created by `Rc::new`, with the value moved in if any, and the span of the call
+    pub rc_creations: Vec<(GlobalProjectionId, Option<GlobalProjectionId>, CtxtSenSpanInfo)>,
+    // rcs stored behind a pointer, by an assignment or a call, (the pointer, the rc, the span)
+    pub rc_stores: Vec<(GlobalProjectionId, GlobalProjectionId, CtxtSenSpanInfo)>,
+    // `Rc::clone` calls, (the borrowed rc, the cloned rc)
+    pub rc_clones: HashSet<(GlobalProjectionId, GlobalProjectionId)>,
+    // objects of dropped `Rc`/`Arc`/`Weak` handles, the value is shared by the clones of a handle
+    pub rc_handle_objects: HashSet<DropObjectId>,
 }
 
 impl<'tcx> PointerFlowGraph<'tcx> {
@@ -187,6 +263,26 @@ impl<'tcx> PointerFlowGraph<'tcx> {
             nodes: HashMap::new(),
             deref_edges: HashSet::new(),
             multi_drop_objects: HashSet::new(),
+            raw_released_objects: HashMap::new(),
+            downgraded_weaks: HashMap::new(),
+            upgraded_weaks: HashMap::new(),
+            borrowed_guards: HashMap::new(),
+            guard_derefs: Vec::new(),
+            iter_nexts: Vec::new(),
+            unchecked_unwraps: HashMap::new(),
+            ptr_assigns: Vec::new(),
+            manual_drops: HashMap::new(),
+            raw_parts_owners: Vec::new(),
+            allocations: Vec::new(),
+            forget_calls: HashSet::new(),
+            consumed_drops: HashMap::new(),
+            taken_values: HashSet::new(),
+            type_objects: HashMap::new(),
+            ffi_registrations: Vec::new(),
+            rc_creations: Vec::new(),
+            rc_stores: Vec::new(),
+            rc_clones: HashSet::new(),
+            rc_handle_objects: HashSet::new(),
         }
     }
 
@@ -204,7 +300,7 @@ impl<'tcx> PointerFlowGraph<'tcx> {
         &mut self,
         call_id: &CtxtSenCallId,
         local_id: LocalId,
-        projection: &Vec<PlaceElem<'tcx>>,
+        projection: &[PlaceElem<'tcx>],
         drop_span: Option<CtxtSenSpanInfo>,
     ) -> GlobalProjectionId {
         // add or update pfg node
@@ -221,7 +317,7 @@ impl<'tcx> PointerFlowGraph<'tcx> {
         let proj_id: ProjectionId =
             match node.try_get_projection_id(projection, &call_id.caller_context) {
                 Some(id) => id,
-                None => node.add_projection(projection.clone(), call_id.caller_context.clone()),
+                None => node.add_projection(projection.to_vec(), call_id.caller_context.clone()),
             };
 
         // add drop span in this context
@@ -301,6 +397,7 @@ impl<'tcx> PointerFlowGraph<'tcx> {
         from: GlobalProjectionId,
         to: GlobalProjectionId,
         span_info: CtxtSenSpanInfo,
+        is_mut_borrow: bool,
     ) {
         let from_node = self.get_projection_node_mut(from);
         from_node.add_neighbor(ProjectionNeighborInfo::new(to, span_info));
@@ -314,8 +411,184 @@ impl<'tcx> PointerFlowGraph<'tcx> {
 
         if from_is_deref || to_is_deref {
             self.deref_edges
-                .insert(DerefEdgeInfo::new(from, to, (from_is_deref, to_is_deref)));
+                .insert(DerefEdgeInfo::new(from, to, (from_is_deref, to_is_deref), is_mut_borrow || to_is_deref));
+        }
+    }
+
+    // the shortest chain of edges along which the objects of `from` flow to `to`
+    pub fn paths_from(
+        &self,
+        from: GlobalProjectionId,
+        to: GlobalProjectionId,
+    ) -> Option<Vec<ProjectionNeighborInfo>> {
+        // node -> (previous node, edge from the previous node)
+        let mut prevs = HashMap::<GlobalProjectionId, (GlobalProjectionId, &ProjectionNeighborInfo)>::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(from);
+        queue.push_back(from);
+
+        while let Some(g_proj_id) = queue.pop_front() {
+            if g_proj_id == to {
+                let mut path = vec![];
+                let mut cur = to;
+                while let Some((prev, edge)) = prevs.get(&cur) {
+                    path.push((*edge).clone());
+                    cur = *prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (neighbor_id, neighbor_info) in self.get_projection_node(g_proj_id).neighbors.iter() {
+                if visited.insert(*neighbor_id) {
+                    prevs.insert(*neighbor_id, (g_proj_id, neighbor_info));
+                    queue.push_back(*neighbor_id);
+                }
+            }
+        }
+
+        None
+    }
+
+    // like `paths_from`, but may also step into the sub-level projections, which add no edge to the path
+    pub fn flow_path(
+        &self,
+        from: GlobalProjectionId,
+        to: GlobalProjectionId,
+    ) -> Option<Vec<ProjectionNeighborInfo>> {
+        // node -> (previous node, edge from the previous node if it is not a sub-level step)
+        let mut prevs =
+            HashMap::<GlobalProjectionId, (GlobalProjectionId, Option<&ProjectionNeighborInfo>)>::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(from);
+        queue.push_back(from);
+
+        while let Some(g_proj_id) = queue.pop_front() {
+            if g_proj_id == to {
+                let mut path = vec![];
+                let mut cur = to;
+                while let Some((prev, edge)) = prevs.get(&cur) {
+                    if let Some(edge) = edge {
+                        path.push((*edge).clone());
+                    }
+                    cur = *prev;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            let proj_node = self.get_projection_node(g_proj_id);
+            for (neighbor_id, neighbor_info) in proj_node.neighbors.iter() {
+                if visited.insert(*neighbor_id) {
+                    prevs.insert(*neighbor_id, (g_proj_id, Some(neighbor_info)));
+                    queue.push_back(*neighbor_id);
+                }
+            }
+            for (sub_proj_id, sub_proj_node) in self.get_node(g_proj_id.g_local_id).projection_nodes.iter() {
+                let sub_id = GlobalProjectionId::new(g_proj_id.g_local_id, *sub_proj_id);
+                if *sub_proj_id != g_proj_id.projection_id
+                    && proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                    && proj_node.is_prefix_of(&sub_proj_node.projection)
+                    && visited.insert(sub_id)
+                {
+                    prevs.insert(sub_id, (g_proj_id, None));
+                    queue.push_back(sub_id);
+                }
+            }
+        }
+
+        None
+    }
+
+    // the allocation of the box that flows along the edges to the dropped place of the object
+    pub fn get_alloc_site(&self, obj_id: DropObjectId) -> Option<&CtxtSenSpanInfo> {
+        self.allocations
+            .iter()
+            .find(|(alloc_id, _)| self.paths_from(*alloc_id, obj_id.into()).is_some())
+            .map(|(_, span_info)| span_info)
+    }
+
+    // whether the objects of `from` may flow to `to`, along the edges or into the sub-level projections
+    pub fn can_flow_to(&self, from: GlobalProjectionId, to: GlobalProjectionId) -> bool {
+        let mut visited = HashSet::new();
+        let mut work_list = vec![from];
+        while let Some(g_proj_id) = work_list.pop() {
+            if g_proj_id == to {
+                return true;
+            }
+            if !visited.insert(g_proj_id) {
+                continue;
+            }
+
+            let proj_node = self.get_projection_node(g_proj_id);
+            work_list.extend(proj_node.neighbors.keys());
+            for (sub_proj_id, sub_proj_node) in self.get_node(g_proj_id.g_local_id).projection_nodes.iter() {
+                if *sub_proj_id != g_proj_id.projection_id
+                    && proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                    && proj_node.is_prefix_of(&sub_proj_node.projection)
+                {
+                    work_list.push(GlobalProjectionId::new(g_proj_id.g_local_id, *sub_proj_id));
+                }
+            }
         }
+        false
+    }
+
+    /// the nodes flowing to each of `targets` along the edges, including the target itself
+    pub fn flow_sources(
+        &self,
+        targets: &[GlobalProjectionId],
+    ) -> HashMap<GlobalProjectionId, HashSet<GlobalProjectionId>> {
+        let mut reverse_edges = HashMap::<GlobalProjectionId, Vec<GlobalProjectionId>>::new();
+        for (g_local_id, node) in self.nodes.iter() {
+            for (proj_id, proj_node) in node.projection_nodes.iter() {
+                for neighbor_id in proj_node.neighbors.keys() {
+                    reverse_edges
+                        .entry(*neighbor_id)
+                        .or_default()
+                        .push(GlobalProjectionId::new(*g_local_id, *proj_id));
+                }
+                // the pointee gets what the pointer points to, but a field does not alias its siblings
+                for (sub_proj_id, sub_proj_node) in node.projection_nodes.iter() {
+                    if sub_proj_node.projection.len() == proj_node.projection.len() + 1
+                        && sub_proj_node.projection.last() == Some(&PlaceElem::Deref)
+                        && proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                        && proj_node.is_prefix_of(&sub_proj_node.projection)
+                    {
+                        reverse_edges
+                            .entry(GlobalProjectionId::new(*g_local_id, *sub_proj_id))
+                            .or_default()
+                            .push(GlobalProjectionId::new(*g_local_id, *proj_id));
+                    }
+                    // a dynamic index may be the element at any constant index
+                    if sub_proj_id != proj_id
+                        && proj_node.caller_context.is_same(&sub_proj_node.caller_context)
+                        && proj_node.is_same_projection(&sub_proj_node.projection)
+                    {
+                        reverse_edges
+                            .entry(GlobalProjectionId::new(*g_local_id, *sub_proj_id))
+                            .or_default()
+                            .push(GlobalProjectionId::new(*g_local_id, *proj_id));
+                    }
+                }
+            }
+        }
+
+        targets
+            .iter()
+            .map(|target| {
+                let mut visited = HashSet::new();
+                let mut work_list = vec![*target];
+                while let Some(g_proj_id) = work_list.pop() {
+                    if visited.insert(g_proj_id) {
+                        work_list.extend(reverse_edges.get(&g_proj_id).into_iter().flatten());
+                    }
+                }
+                (*target, visited)
+            })
+            .collect()
     }
 
     pub fn debug_paths(&self, start: (String, LocalId, ProjectionId)) {
@@ -415,4 +688,84 @@ impl<'tcx> PointerFlowGraph<'tcx> {
             f(proj_node);
         }
     }
+
+    /// The number of drop objects and of projection nodes, an object is counted once however many
+    /// points-to sets it is in
+    pub fn stats(&self) -> (usize, usize) {
+        let projection_nodes = self.nodes.values().flat_map(|node| node.projection_nodes.values());
+        let objects = projection_nodes
+            .clone()
+            .flat_map(|node| node.points_to.iter())
+            .collect::<HashSet<_>>();
+        (objects.len(), projection_nodes.count())
+    }
+
+    /// The graph in Graphviz DOT, a node for each projection with its points-to set and an edge for
+    /// each neighbor, labeled with `is_deref` of the assignment, the drops reached by more than one
+    /// object are red
+    pub fn to_dot(&self) -> String {
+        let name = |g_proj_id: GlobalProjectionId| {
+            format!(
+                "{}::{:?}::{}",
+                utils::parse_def_id(g_proj_id.g_local_id.def_id).join("::"),
+                g_proj_id.g_local_id.local_id,
+                g_proj_id.projection_id
+            )
+        };
+        let escape = |s: String| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let deref_flags = self
+            .deref_edges
+            .iter()
+            .map(|edge| ((edge.from, edge.to), edge.is_deref))
+            .collect::<HashMap<_, _>>();
+
+        // sorted, so that the dumps of two runs can be diffed
+        let mut nodes = vec![];
+        let mut edges = vec![];
+        for (g_local_id, node) in self.nodes.iter() {
+            for (proj_id, proj_node) in node.projection_nodes.iter() {
+                let g_proj_id = GlobalProjectionId::new(*g_local_id, *proj_id);
+                let mut points_to = proj_node
+                    .points_to
+                    .iter()
+                    .map(|obj_id| name((*obj_id).into()))
+                    .collect::<Vec<_>>();
+                points_to.sort();
+                let color = if self.multi_drop_objects.contains(&g_proj_id.into()) {
+                    ", color=red, fontcolor=red"
+                } else {
+                    ""
+                };
+                nodes.push(format!(
+                    "    \"{}\" [label=\"{}\\nprojection: {}\\npoints to: {}\"{}];",
+                    escape(name(g_proj_id)),
+                    escape(name(g_proj_id)),
+                    escape(format!("{:?}", proj_node.projection)),
+                    escape(points_to.join(", ")),
+                    color
+                ));
+                for neighbor_id in proj_node.neighbors.keys() {
+                    let label = deref_flags
+                        .get(&(g_proj_id, *neighbor_id))
+                        .map_or(String::new(), |is_deref| format!(" [label=\"is_deref: {:?}\"]", is_deref));
+                    edges.push(format!(
+                        "    \"{}\" -> \"{}\"{};",
+                        escape(name(g_proj_id)),
+                        escape(name(*neighbor_id)),
+                        label
+                    ));
+                }
+            }
+        }
+        nodes.sort();
+        edges.sort();
+
+        let mut dot = String::from("digraph pfg {\n    node [shape=box];\n");
+        for line in nodes.iter().chain(edges.iter()) {
+            dot.push_str(line);
+            dot.push('\n');
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
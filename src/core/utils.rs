@@ -1,16 +1,24 @@
 use std::io::BufRead;
 use std::{
-    collections::{HashMap, HashSet},
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
     io::{BufReader, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
 };
 
+use itertools::Itertools;
 use rustc_hir::def_id::DefId;
+use rustc_span::hygiene::{ExpnKind, MacroKind};
 use rustc_span::Span;
 
-use crate::core::AnalysisOptions;
+use crate::core::{AnalysisOptions, ModelKind, ObjectGranularity, OutputFormat};
 
-use super::{cfg::ControlFlowGraph, BasicBlockId, GlobalBasicBlockId};
-use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
+use super::{cfg::ControlFlowGraph, BasicBlockId, CtxtSenSpanInfo, GlobalBasicBlockId};
+use termcolor::{Buffer, Color, ColorSpec, WriteColor};
+use unicode_width::UnicodeWidthChar;
 
 pub const LOG4RS_CONFIG_YAML: &str = r#"
 # appender: collect logs to console or file, could have multiple configs
@@ -64,14 +72,92 @@ pub fn has_dbg(opts: &AnalysisOptions, opt_name: &str) -> bool {
     opts.debug_opts.iter().any(|s| s == opt_name)
 }
 
+// `--DBG=panic-entry=PATH` makes the analysis of the matching entries panic, to test their isolation
+pub fn should_panic_in_entry(opts: &AnalysisOptions, def_id: DefId) -> bool {
+    let def_path = parse_def_id(def_id);
+    opts.debug_opts
+        .iter()
+        .filter_map(|opt| opt.strip_prefix("panic-entry="))
+        .any(|path| entry_matches(path, &def_path, def_id.is_local()))
+}
+
 pub fn has_entry(opts: &AnalysisOptions, def_id: DefId) -> bool {
+    get_matched_entry(opts, def_id).is_some()
+}
+
+// the first entry or regex of `--entries-regex` selecting the def id
+pub fn get_matched_entry(opts: &AnalysisOptions, def_id: DefId) -> Option<String> {
+    let def_path = parse_def_id(def_id);
     opts.entries
         .iter()
-        .any(|entry| entry_is_suffix_of(&parse_entry(entry), &parse_def_id(def_id)))
+        .find(|entry| entry_matches(entry, &def_path, def_id.is_local()))
+        .cloned()
+        .or_else(|| {
+            opts.entries_regex
+                .iter()
+                .find(|regex| regex.is_match(&def_path.join("::")))
+                .map(|regex| regex.as_str().to_owned())
+        })
+}
+
+pub fn focus_entry(opts: &AnalysisOptions) -> Option<&str> {
+    opts.focus_entry.as_deref()
+}
+
+// whether the findings of the entry are reported, all entries without `--focus-entry`
+pub fn is_focused_entry(opts: &AnalysisOptions, def_id: DefId) -> bool {
+    opts.focus_entry
+        .as_ref()
+        .map_or(true, |entry| entry_matches(entry, &parse_def_id(def_id), def_id.is_local()))
+}
+
+// the entries and regexes of `--entries-regex` selecting more than one of the def ids, with the selected paths
+pub fn get_ambiguous_entries(opts: &AnalysisOptions, def_ids: &[DefId]) -> Vec<(String, Vec<String>)> {
+    let def_paths = def_ids
+        .iter()
+        .map(|def_id| (parse_def_id(*def_id), def_id.is_local()))
+        .collect::<Vec<_>>();
+    let selected_by_entries = opts.entries.iter().map(|entry| {
+        let selected = def_paths
+            .iter()
+            .filter(|(def_path, is_local)| entry_matches(entry, def_path, *is_local))
+            .map(|(def_path, _)| def_path.join("::"))
+            .collect::<Vec<_>>();
+        (entry.clone(), selected)
+    });
+    let selected_by_regexes = opts.entries_regex.iter().map(|regex| {
+        let selected = def_paths
+            .iter()
+            .map(|(def_path, _)| def_path.join("::"))
+            .filter(|path| regex.is_match(path))
+            .collect::<Vec<_>>();
+        (regex.as_str().to_owned(), selected)
+    });
+    selected_by_entries
+        .chain(selected_by_regexes)
+        .filter(|(_, selected)| selected.len() > 1)
+        .map(|(entry, mut selected)| {
+            selected.sort();
+            (entry, selected)
+        })
+        .collect()
+}
+
+pub fn should_print_cfg(opts: &AnalysisOptions, def_id: DefId) -> bool {
+    let def_path = parse_def_id(def_id);
+    opts.print_cfgs
+        .iter()
+        .any(|path| entry_matches(path, &def_path, def_id.is_local()))
+}
+
+// the file of the span matches a glob of `--exclude-path`
+pub fn is_excluded_path(opts: &AnalysisOptions, span: &Span) -> bool {
+    let (filename, _, _) = parse_span(span);
+    opts.exclude_paths.is_match(filename)
 }
 
 pub fn auto_detect_entries(opts: &AnalysisOptions) -> bool {
-    opts.entries.is_empty()
+    opts.entries.is_empty() && opts.entries_regex.is_empty()
 }
 
 pub fn open_dbg(opts: &AnalysisOptions) -> bool {
@@ -90,17 +176,287 @@ pub fn check_std(opts: &AnalysisOptions) -> bool {
     opts.check_std
 }
 
-pub fn parse_args(args: &[String]) -> (AnalysisOptions, Vec<String>) {
+// whether the body of the std fn is analyzed by `--analyze-std` though the std is skipped
+pub fn should_analyze_std(
+    opts: &AnalysisOptions,
+    tcx: rustc_middle::ty::TyCtxt<'_>,
+    def_id: rustc_hir::def_id::DefId,
+) -> bool {
+    !opts.analyze_std.is_empty()
+        && analyze_std_matches(opts, &parse_def_id(def_id), &tcx.def_path_str(def_id))
+}
+
+// the def path names an inherent method by its impl, eg. `core::option::{impl#0}::insert`, and the
+// visible path by its type, eg. `std::option::Option::<T>::insert`, either may match. `std::`
+// reexports `core::` and `alloc::`, so the crate of a given path is not compared
+pub fn analyze_std_matches(opts: &AnalysisOptions, def_path: &[String], visible_path: &str) -> bool {
+    let visible_path = visible_path
+        .split("::")
+        .filter(|s| !s.starts_with('<'))
+        .map(|s| s.to_owned())
+        .collect::<Vec<_>>();
+    opts.analyze_std.iter().any(|path| {
+        let path = ["std::", "core::", "alloc::"]
+            .iter()
+            .find_map(|krate| path.strip_prefix(krate))
+            .unwrap_or(path);
+        let path = parse_entry(path);
+        def_path.ends_with(&path) || visible_path.ends_with(&path)
+    })
+}
+
+pub fn changed_only(opts: &AnalysisOptions) -> Option<&str> {
+    opts.changed_only.as_deref()
+}
+
+pub fn model_rc_weak(opts: &AnalysisOptions) -> bool {
+    opts.rc_weak
+}
+
+pub fn verbose_df(opts: &AnalysisOptions) -> bool {
+    opts.verbose_df
+}
+
+pub fn check_alias_assign(opts: &AnalysisOptions) -> bool {
+    opts.alias_assign
+}
+
+pub fn analyze_const_eval(opts: &AnalysisOptions) -> bool {
+    opts.const_eval
+}
+
+pub fn object_granularity(opts: &AnalysisOptions) -> ObjectGranularity {
+    opts.object_granularity
+}
+
+pub fn check_ffi_callback(opts: &AnalysisOptions) -> bool {
+    opts.ffi_callback
+}
+
+pub fn check_rc_cycle(opts: &AnalysisOptions) -> bool {
+    opts.rc_cycle
+}
+
+pub fn check_use_after_move(opts: &AnalysisOptions) -> bool {
+    opts.use_after_move
+}
+
+pub fn call_path(opts: &AnalysisOptions) -> bool {
+    opts.call_path
+}
+
+pub fn only_mut_deref(opts: &AnalysisOptions) -> bool {
+    opts.only_mut_deref
+}
+
+pub fn warn_unsupported(opts: &AnalysisOptions) -> bool {
+    opts.warn_unsupported
+}
+
+pub fn list_entries(opts: &AnalysisOptions) -> bool {
+    opts.list_entries
+}
+
+pub fn check_guard_escape(opts: &AnalysisOptions) -> bool {
+    opts.guard_escape
+}
+
+pub fn check_iter_escape(opts: &AnalysisOptions) -> bool {
+    opts.iter_escape
+}
+
+pub fn summary_by_function(opts: &AnalysisOptions) -> bool {
+    opts.summary_by_function
+}
+
+pub fn dump_pfg_path(opts: &AnalysisOptions) -> Option<&str> {
+    opts.dump_pfg.as_deref()
+}
+
+pub fn report_path(opts: &AnalysisOptions) -> Option<&str> {
+    opts.report_path.as_deref()
+}
+
+pub fn json_summary_path(opts: &AnalysisOptions) -> Option<&str> {
+    opts.json_summary.as_deref()
+}
+
+pub fn context_depth(opts: &AnalysisOptions) -> usize {
+    opts.context_depth
+}
+
+// the most uaf and df findings printed each, the rest are counted in one notice
+pub fn max_findings(opts: &AnalysisOptions) -> Option<usize> {
+    opts.max_findings
+}
+
+pub fn timeout(opts: &AnalysisOptions) -> Option<std::time::Duration> {
+    opts.timeout.map(std::time::Duration::from_secs)
+}
+
+pub fn profile(opts: &AnalysisOptions) -> Option<usize> {
+    opts.profile
+}
+
+pub fn stats(opts: &AnalysisOptions) -> bool {
+    opts.stats
+}
+
+// the time since `start` is added to the fn, `start` is only taken under `--profile`
+pub fn add_elapsed(times: &mut HashMap<DefId, std::time::Duration>, def_id: DefId, start: Option<std::time::Instant>) {
+    if let Some(start) = start {
+        *times.entry(def_id).or_default() += start.elapsed();
+    }
+}
+
+pub fn quiet(opts: &AnalysisOptions) -> bool {
+    opts.quiet
+}
+
+pub fn no_color(opts: &AnalysisOptions) -> bool {
+    opts.no_color
+}
+
+pub fn output_format(opts: &AnalysisOptions) -> OutputFormat {
+    opts.format
+}
+
+pub fn underline_char(opts: &AnalysisOptions) -> char {
+    opts.underline_char
+}
+
+// the model registered for the def path, the latest registered wins
+pub fn get_model<'a>(opts: &'a AnalysisOptions, def_path: &[String]) -> Option<&'a ModelKind> {
+    opts.models
+        .iter()
+        .rev()
+        .find(|(name, _)| def_path.ends_with(&parse_entry(name)))
+        .map(|(_, kind)| kind)
+}
+
+pub const MC_CONFIG_FILENAME: &str = "mc.toml";
+
+// the crate root under cargo, or the current directory
+fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+}
+
+// the options set in `mc.toml` of `dir`, as the args on the command line
+pub fn load_config(dir: &Path) -> Vec<String> {
+    let path = dir.join(MC_CONFIG_FILENAME);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return vec![],
+    };
+    let table = match text.parse::<toml::Value>() {
+        Ok(toml::Value::Table(table)) => table,
+        Ok(_) => return vec![],
+        Err(err) => {
+            eprintln!("warning: invalid config `{}`, ignored: {}", path.display(), err);
+            return vec![];
+        }
+    };
+
+    let mut args = vec![];
+    for (key, value) in table.iter() {
+        let name = match key.as_str() {
+            "entries" => "--entries",
+            "exclude_path" => "--exclude-path",
+            "debug_opts" => "--DBG",
+            "context_depth" => "--context-depth",
+            "format" => "--format",
+            "output_format" => "--output-format",
+            _ => {
+                eprintln!("warning: unknown option `{}` in `{}`, ignored", key, path.display());
+                continue;
+            }
+        };
+        let value = match value {
+            toml::Value::String(s) => s.clone(),
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Array(values) if values.iter().all(|value| value.is_str()) => {
+                values.iter().filter_map(|value| value.as_str()).join(",")
+            }
+            _ => {
+                eprintln!("warning: unsupported value of `{}` in `{}`, ignored", key, path.display());
+                continue;
+            }
+        };
+        args.push(format!("{}={}", name, value));
+    }
+    args
+}
+
+// an option the analysis can't run with is an error, the unsupported values of the others are warned about
+pub fn parse_args(args: &[String]) -> Result<(AnalysisOptions, Vec<String>), String> {
+    // the options in `mc.toml` come after the args, so the args on the command line win
+    let config_args = config_dir().map_or_else(Vec::new, |dir| load_config(&dir));
+    let all_args = args.iter().cloned().chain(config_args).collect::<Vec<_>>();
     let mut index_removed = vec![];
     let mut debug_opts = vec![];
     let mut entries = vec![];
+    let mut entries_regex = vec![];
+    let mut exclude_paths = globset::GlobSet::empty();
     let mut open_dbg = false;
     let mut open_same_level = false;
     let mut let_arg_as_deref = false;
     let mut check_std = false;
+    let mut analyze_std = vec![];
+    let mut changed_only = None;
+    let mut rc_weak = false;
+    let mut verbose_df = false;
+    let mut alias_assign = false;
+    let mut format = OutputFormat::Text;
+    let mut underline_char = '^';
+    let mut const_eval = false;
+    let mut object_granularity = ObjectGranularity::Local;
+    let mut ffi_callback = false;
+    let mut rc_cycle = false;
+    let mut guard_escape = false;
+    let mut use_after_move = false;
+    let mut call_path = false;
+    let mut only_mut_deref = false;
+    let mut warn_unsupported = false;
+    let mut list_entries = false;
+    let mut summary_by_function = false;
+    let mut print_cfgs = vec![];
+    let mut dump_pfg = None;
+    let mut report_path = None;
+    let mut json_summary = None;
+    let mut focus_entry = None;
+    let mut context_depth = 1;
+    let mut max_findings = None;
+    let mut timeout = None;
+    let mut profile = None;
+    // https://no-color.org, set and not empty
+    let mut no_color = std::env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+    let mut models = vec![];
+
+    // a flag given without a value
+    let mut has_flag = |name: &str| match args.iter().position(|arg| arg == name) {
+        Some(i) => {
+            index_removed.push(i);
+            true
+        }
+        None => false,
+    };
+
+    // the bare flag prints the summary to stdout
+    if has_flag("--json-summary") {
+        json_summary = Some("-".to_owned());
+    }
+
+    let mut iter_escape = has_flag("--iter-escape");
+    let mut stats = has_flag("--stats");
+    let mut quiet = has_flag("--quiet");
+    if has_flag("--no-color") {
+        no_color = true;
+    }
 
     let mut try_get_arg_value = |name: &str| {
-        for (i, arg) in args.iter().enumerate() {
+        for (i, arg) in all_args.iter().enumerate() {
             if !arg.starts_with(name) {
                 continue;
             }
@@ -121,6 +477,31 @@ pub fn parse_args(args: &[String]) -> (AnalysisOptions, Vec<String>) {
         entries.extend(arg.split(',').map(|s| s.to_owned()));
     }
 
+    if let Some(arg) = try_get_arg_value("--entries-regex") {
+        for pattern in arg.split(',') {
+            match regex::Regex::new(pattern) {
+                Ok(regex) => entries_regex.push(regex),
+                Err(err) => eprintln!("warning: invalid entry regex `{}`, ignored: {}", pattern, err),
+            }
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--exclude-path") {
+        let mut builder = globset::GlobSetBuilder::new();
+        for pattern in arg.split(',') {
+            match globset::Glob::new(pattern) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(err) => eprintln!("warning: invalid exclude path `{}`, ignored: {}", pattern, err),
+            }
+        }
+        match builder.build() {
+            Ok(glob_set) => exclude_paths = glob_set,
+            Err(err) => eprintln!("warning: invalid exclude paths, ignored: {}", err),
+        }
+    }
+
     if let Some(arg) = try_get_arg_value("--open-dbg") {
         open_dbg = arg == "1";
     }
@@ -137,6 +518,191 @@ pub fn parse_args(args: &[String]) -> (AnalysisOptions, Vec<String>) {
         check_std = arg == "1";
     }
 
+    if let Some(arg) = try_get_arg_value("--analyze-std") {
+        analyze_std.extend(arg.split(',').filter(|s| !s.is_empty()).map(|s| s.to_owned()));
+    }
+
+    if let Some(arg) = try_get_arg_value("--changed-only") {
+        changed_only = Some(arg);
+    }
+
+    if let Some(arg) = try_get_arg_value("--rc-weak") {
+        rc_weak = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--verbose-df") {
+        verbose_df = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--alias-assign") {
+        alias_assign = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--const-eval") {
+        const_eval = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--object-granularity") {
+        match arg.as_str() {
+            "local" => object_granularity = ObjectGranularity::Local,
+            "type" => object_granularity = ObjectGranularity::Type,
+            _ => return Err(format!("unsupported object granularity `{}`, use `local` or `type`", arg)),
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--ffi-callback") {
+        ffi_callback = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--rc-cycle") {
+        rc_cycle = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--guard-escape") {
+        guard_escape = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--iter-escape") {
+        iter_escape = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--use-after-move") {
+        use_after_move = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--call-path") {
+        call_path = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--only-mut-deref") {
+        only_mut_deref = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--warn-unsupported") {
+        warn_unsupported = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--list-entries") {
+        list_entries = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--summary-by-function") {
+        summary_by_function = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--print-cfg") {
+        print_cfgs.extend(arg.split(',').map(|s| s.to_owned()));
+    }
+
+    if let Some(arg) = try_get_arg_value("--dump-pfg") {
+        dump_pfg = Some(arg);
+    }
+
+    if let Some(arg) = try_get_arg_value("--report-path") {
+        report_path = Some(arg);
+    }
+
+    if let Some(arg) = try_get_arg_value("--json-summary") {
+        json_summary = Some(arg);
+    }
+
+    if let Some(arg) = try_get_arg_value("--focus-entry") {
+        focus_entry = Some(arg);
+    }
+
+    if let Some(arg) = try_get_arg_value("--context-depth") {
+        match arg.parse::<usize>() {
+            Ok(depth) => context_depth = depth,
+            Err(_) => eprintln!("warning: invalid context depth `{}`, use 1", arg),
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--max-findings") {
+        match arg.parse::<usize>() {
+            Ok(max) => max_findings = Some(max),
+            Err(_) => eprintln!("warning: invalid max findings `{}`, print all findings", arg),
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--timeout") {
+        match arg.parse::<u64>() {
+            Ok(seconds) => timeout = Some(seconds),
+            Err(_) => eprintln!("warning: invalid timeout `{}`, analyze without a timeout", arg),
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--profile") {
+        match arg.parse::<usize>() {
+            Ok(top) => profile = Some(top),
+            Err(_) => eprintln!("warning: invalid profile count `{}`, analyze without profiling", arg),
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--stats") {
+        stats = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--quiet") {
+        quiet = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--no-color") {
+        no_color = arg == "1";
+    }
+
+    if let Some(arg) = try_get_arg_value("--drop-fns") {
+        // `PATH` drops the first argument, `PATH:ARG` drops the argument at index `ARG`
+        for drop_fn in arg.split(',').filter(|s| !s.is_empty()) {
+            let (path, arg) = match drop_fn.rsplit_once(':') {
+                Some((path, index)) if !path.ends_with(':') => match index.parse::<usize>() {
+                    Ok(index) => (path, index),
+                    Err(_) => {
+                        eprintln!("warning: invalid argument index of drop fn `{}`, ignored", drop_fn);
+                        continue;
+                    }
+                },
+                _ => (drop_fn, 0),
+            };
+            models.push((path.to_owned(), ModelKind::Drop { arg }));
+        }
+    }
+
+    if let Some(arg) = try_get_arg_value("--underline-char") {
+        // a blank underline marks nothing without colors
+        let mut chars = arg.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if !c.is_whitespace() && !c.is_control() => underline_char = c,
+            _ => eprintln!("warning: unsupported underline char `{}`, use `^`", arg),
+        }
+    }
+
+    // eg. passed by `cargo check --message-format=json`, the arg is kept for rustc
+    if args.iter().enumerate().any(|(i, arg)| {
+        arg == "--error-format=json"
+            || (arg == "--error-format" && args.get(i + 1).map(|s| s.as_str()) == Some("json"))
+    }) {
+        format = OutputFormat::Json;
+    }
+
+    // asked explicitly, wins over the rustc diagnostics above, `--format` is an alias
+    let output_format = try_get_arg_value("--output-format");
+    let format_alias = try_get_arg_value("--format");
+    if let Some(arg) = output_format.or(format_alias) {
+        match arg.as_str() {
+            "json" => format = OutputFormat::JsonReport,
+            "sarif" => format = OutputFormat::Sarif,
+            // the same rendering as the compiler warnings, json under `--error-format=json`
+            "rustc" => format = OutputFormat::Json,
+            "text-no-color" => format = OutputFormat::TextNoColor,
+            "lsp" => format = OutputFormat::Lsp,
+            "text" => {}
+            _ => eprintln!(
+                "warning: unsupported output format `{}`, use `text`, `text-no-color`, `json`, `sarif`, `rustc` or `lsp`",
+                arg
+            ),
+        }
+    }
+
     // add additional args
     let mut additional_args: Vec<String> = vec![];
 
@@ -154,17 +720,117 @@ pub fn parse_args(args: &[String]) -> (AnalysisOptions, Vec<String>) {
         .map(|(_, s)| s.to_owned())
         .chain(additional_args.into_iter())
         .collect::<Vec<String>>();
-    (
+    Ok((
         AnalysisOptions {
             debug_opts,
             entries,
+            entries_regex,
+            focus_entry,
+            exclude_paths,
             open_dbg,
             open_same_level,
             let_arg_as_deref,
-            check_std
+            check_std,
+            analyze_std,
+            changed_only,
+            rc_weak,
+            verbose_df,
+            alias_assign,
+            format,
+            underline_char,
+            const_eval,
+            object_granularity,
+            ffi_callback,
+            rc_cycle,
+            guard_escape,
+            iter_escape,
+            use_after_move,
+            summary_by_function,
+            call_path,
+            only_mut_deref,
+            warn_unsupported,
+            list_entries,
+            print_cfgs,
+            dump_pfg,
+            report_path,
+            json_summary,
+            context_depth,
+            max_findings,
+            timeout,
+            profile,
+            stats,
+            quiet,
+            no_color,
+            models,
         },
         new_args,
-    )
+    ))
+}
+
+// the initializers of consts and statics only have the MIR for const eval
+pub fn get_mir_body(
+    tcx: rustc_middle::ty::TyCtxt<'_>,
+    def_id: rustc_hir::def_id::DefId,
+) -> &rustc_middle::mir::Body<'_> {
+    use rustc_hir::def::DefKind;
+    match tcx.def_kind(def_id) {
+        DefKind::Const
+        | DefKind::AssocConst
+        | DefKind::Static(_)
+        | DefKind::AnonConst
+        | DefKind::InlineConst => tcx.mir_for_ctfe(def_id),
+        _ => tcx.optimized_mir(def_id),
+    }
+}
+
+// `Rc` or `Arc`
+pub fn is_rc_ty(ty: rustc_middle::ty::Ty) -> bool {
+    ty.ty_adt_def().map_or(false, |adt_def| {
+        let def_name = parse_def_id(adt_def.did()).join("::");
+        def_name == "alloc::rc::Rc" || def_name == "alloc::sync::Arc"
+    })
+}
+
+// `Rc`, `Arc` or one of their `Weak`s, a handle sharing the value with its clones
+pub fn is_rc_handle_ty(ty: rustc_middle::ty::Ty) -> bool {
+    ty.ty_adt_def().map_or(false, |adt_def| {
+        let def_name = parse_def_id(adt_def.did()).join("::");
+        matches!(
+            def_name.as_str(),
+            "alloc::rc::Rc" | "alloc::sync::Arc" | "alloc::rc::Weak" | "alloc::sync::Weak"
+        )
+    })
+}
+
+// whether the type or one of its generic arguments is an rc, eg. `&Option<Rc<T>>`
+pub fn contains_rc_ty(ty: rustc_middle::ty::Ty) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        rustc_middle::ty::subst::GenericArgKind::Type(ty) => is_rc_ty(ty),
+        _ => false,
+    })
+}
+
+// whether the type borrows its container while alive, eg. `RefMut<T>` or `MutexGuard<T>`
+pub fn is_guard_ty(ty: rustc_middle::ty::Ty) -> bool {
+    ty.ty_adt_def().map_or(false, |adt_def| {
+        let def_name = parse_def_id(adt_def.did()).join("::");
+        [
+            "core::cell::Ref",
+            "core::cell::RefMut",
+            "std::sync::mutex::MutexGuard",
+            "std::sync::rwlock::RwLockReadGuard",
+            "std::sync::rwlock::RwLockWriteGuard",
+        ]
+        .contains(&def_name.as_str())
+    })
+}
+
+// whether the type or one of its generic arguments is a guard, eg. `LockResult<MutexGuard<T>>`
+pub fn contains_guard_ty(ty: rustc_middle::ty::Ty) -> bool {
+    ty.walk().any(|arg| match arg.unpack() {
+        rustc_middle::ty::subst::GenericArgKind::Type(ty) => is_guard_ty(ty),
+        _ => false,
+    })
 }
 
 pub fn get_ty_from_place<'tcx>(
@@ -172,7 +838,7 @@ pub fn get_ty_from_place<'tcx>(
     def_id: rustc_hir::def_id::DefId,
     place: &rustc_middle::mir::Place<'tcx>,
 ) -> rustc_middle::ty::Ty<'tcx> {
-    let body = tcx.optimized_mir(def_id);
+    let body = get_mir_body(tcx, def_id);
     let place_ty = place.ty(&body.local_decls, tcx);
     if place_ty.variant_index.is_some() {
         log::debug!(
@@ -186,245 +852,207 @@ pub fn get_ty_from_place<'tcx>(
 pub fn can_call_arrive(
     cfgs: &HashMap<DefId, ControlFlowGraph>,
     called_infos: &HashMap<DefId, HashSet<GlobalBasicBlockId>>,
-    visited: &mut HashSet<DefId>,
     from: DefId,
     to: DefId,
 ) -> bool {
-    if from == to {
-        return true;
-    }
-
-    if visited.contains(&from) {
-        return false;
-    }
-
-    visited.insert(from);
-
-    let from_cfg = cfgs.get(&from).unwrap();
-    for (_, call_info) in from_cfg.call_infos.iter() {
-        let next_id = call_info.callee_def_id;
-        if cfgs.contains_key(&next_id) {
-            if can_call_arrive(cfgs, called_infos, visited, next_id, to) {
-                return true;
-            }
+    let mut visited = HashSet::new();
+    let mut worklist = VecDeque::from([from]);
+    while let Some(def_id) = worklist.pop_front() {
+        if def_id == to {
+            return true;
         }
-    }
-
-    if let Some(called_info) = called_infos.get(&from) {
-        for ret_g_bb_id in called_info {
-            let ret_def_id = ret_g_bb_id.def_id;
-            if cfgs.contains_key(&ret_def_id) {
-                if can_call_arrive(cfgs, called_infos, visited, ret_def_id, to) {
-                    return true;
-                }
-            }
+        if !visited.insert(def_id) {
+            continue;
         }
+
+        // a fn without cfg is external, it arrives nowhere
+        let cfg = match cfgs.get(&def_id) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
+        // into the callees, and back to the callers
+        let callees = cfg.call_infos.values().flat_map(|call_info| call_info.callees());
+        let callers = called_infos.get(&def_id).into_iter().flatten().map(|g_bb_id| g_bb_id.def_id);
+        worklist.extend(callees.chain(callers).filter(|next_id| cfgs.contains_key(next_id)));
     }
 
-    return false;
+    false
 }
 
 
-// rewrite can basic block arrive with path recorded
-pub fn can_basic_block_arrive_with_path_record(
+
+/// Like `can_basic_block_arrive`, but returns the blocks on a path from `from` to `to`, both included.
+/// A call site is followed by the entry of the callee, and a block that returns by the call sites of its fn
+pub fn basic_block_path(
     cfgs: &HashMap<DefId, ControlFlowGraph>,
     called_infos: &HashMap<DefId, HashSet<GlobalBasicBlockId>>,
-    visited: &mut HashSet<GlobalBasicBlockId>,
     from: GlobalBasicBlockId,
     to: GlobalBasicBlockId,
-    path: &mut Vec<GlobalBasicBlockId>,
-) -> bool{
-    if visited.contains(&from) {
-        return false;
-    }
-
-    if !can_call_arrive(
-        cfgs,
-        called_infos,
-        &mut HashSet::new(),
-        from.def_id,
-        to.def_id,
-    ) {
-        return false;
-    }
-
-    visited.insert(from);
-
-    if from.def_id == to.def_id {
-        return can_inner_basic_block_arrive_with_path_record(
-            cfgs.get(&from.def_id).unwrap(),
-            &mut HashSet::new(),
-            from.bb_id,
-            to.bb_id,
-            path,
-        );
-    } else {
-        path.push(from);
-
-        let from_cfg = cfgs.get(&from.def_id).unwrap();
-        for (bb_id, call_info) in from_cfg.call_infos.iter() {
-            // register call
-            if cfgs.contains_key(&call_info.callee_def_id) {
-                // can internal transit to callsite
-                if can_inner_basic_block_arrive_with_path_record(from_cfg, &mut HashSet::new(), from.bb_id, *bb_id, path) {
-                    // walk to caller site, then transfer to the begin of callee, check next
-                    path.push(GlobalBasicBlockId::new(from.def_id, call_info.caller_bb_id));
-                    if can_basic_block_arrive_with_path_record(
-                        cfgs,
-                        called_infos,
-                        visited,
-                        GlobalBasicBlockId::new(
-                            call_info.callee_def_id,
-                            BasicBlockId::from_usize(0),
-                        ),
-                        to,
-                        path
-                    ) {
-                        return true;
-                    }
-                    path.pop();
-                }
+) -> Option<Vec<GlobalBasicBlockId>> {
+    // the block each block is first reached from, to walk the path back
+    let mut parents = HashMap::from([(from, from)]);
+    let mut can_call_arrive_to = HashMap::new();
+    let mut worklist = VecDeque::from([from]);
+    while let Some(g_bb_id) = worklist.pop_front() {
+        if g_bb_id == to {
+            let mut path = vec![to];
+            let mut g_bb_id = to;
+            while g_bb_id != from {
+                g_bb_id = parents[&g_bb_id];
+                path.push(g_bb_id);
             }
+            path.reverse();
+            return Some(path);
         }
-
-        // assume all basic block can return
-        if let Some(call_info) = called_infos.get(&from.def_id) {
-            for ret_g_bb_id in call_info {
-                if can_basic_block_arrive_with_path_record(cfgs, called_infos, visited, *ret_g_bb_id, to, path) {
-                    // we not ensure where it return
-                    // path.push(ret id);
-                    return true;
-                }
+        let can_arrive = *can_call_arrive_to
+            .entry(g_bb_id.def_id)
+            .or_insert_with(|| can_call_arrive(cfgs, called_infos, g_bb_id.def_id, to.def_id));
+        let cfg = match cfgs.get(&g_bb_id.def_id) {
+            Some(cfg) if can_arrive => cfg,
+            _ => continue,
+        };
+
+        // the start block of a callee without blocks
+        let bb_info = match cfg.basic_block_infos.get(&g_bb_id.bb_id) {
+            Some(bb_info) => bb_info,
+            None => continue,
+        };
+        let mut nexts = bb_info
+            .successors
+            .iter()
+            .map(|bb_id| GlobalBasicBlockId::new(g_bb_id.def_id, *bb_id))
+            .collect::<Vec<_>>();
+        // in the fn of `to`, only the paths inside the fn are followed
+        if g_bb_id.def_id != to.def_id {
+            // transfer to the begin of the callee at the call site
+            if let Some(call_info) = cfg.call_infos.get(&g_bb_id.bb_id) {
+                let callees = call_info.callees().filter(|callee_def_id| cfgs.contains_key(callee_def_id));
+                nexts.extend(callees.map(|callee_def_id| GlobalBasicBlockId::new(callee_def_id, BasicBlockId::from_usize(0))));
+            }
+            // assume all basic block can return
+            if let Some(ret_g_bb_ids) = called_infos.get(&g_bb_id.def_id) {
+                nexts.extend(ret_g_bb_ids.iter().copied());
+            }
+        }
+        for next in nexts {
+            if let std::collections::hash_map::Entry::Vacant(entry) = parents.entry(next) {
+                entry.insert(g_bb_id);
+                worklist.push_back(next);
             }
         }
-
-        path.pop();
-        return false;
     }
-} 
 
-fn can_inner_basic_block_arrive_with_path_record(
-    cfg: &ControlFlowGraph,
-    visited: &mut HashSet<BasicBlockId>,
-    from: BasicBlockId,
-    to: BasicBlockId,
-    path: &mut Vec<GlobalBasicBlockId>,
-) -> bool{
-    if from == to {
-        path.push(GlobalBasicBlockId::new(cfg.def_id, from));
-        return true;
-    }
+    None
+}
 
-    if visited.contains(&from) {
-        return false;
+/// Memoized `can_basic_block_arrive` over the cfgs of one analysis context
+pub struct ReachabilityCache<'a, 'tcx> {
+    cfgs: &'a HashMap<DefId, ControlFlowGraph<'tcx>>,
+    called_infos: &'a HashMap<DefId, HashSet<GlobalBasicBlockId>>,
+    results: RefCell<HashMap<(GlobalBasicBlockId, GlobalBasicBlockId), bool>>,
+}
+
+impl<'a, 'tcx> ReachabilityCache<'a, 'tcx> {
+    pub fn new(
+        cfgs: &'a HashMap<DefId, ControlFlowGraph<'tcx>>,
+        called_infos: &'a HashMap<DefId, HashSet<GlobalBasicBlockId>>,
+    ) -> Self {
+        Self {
+            cfgs,
+            called_infos,
+            results: RefCell::new(HashMap::new()),
+        }
     }
 
-    visited.insert(from);
-    path.push(GlobalBasicBlockId::new(cfg.def_id, from));
+    pub fn can_arrive(&self, from: GlobalBasicBlockId, to: GlobalBasicBlockId) -> bool {
+        if let Some(result) = self.results.borrow().get(&(from, to)) {
+            return *result;
+        }
+        let result = can_basic_block_arrive(self.cfgs, self.called_infos, from, to);
+        self.results.borrow_mut().insert((from, to), result);
+        result
+    }
 
-    for successor in cfg.basic_block_infos.get(&from).unwrap().successors.iter() {
-        if can_inner_basic_block_arrive_with_path_record(cfg, visited, *successor, to, path) {
-            return true;
+    /// Like `can_arrive`, but the points in callees are first lifted to their call sites in the
+    /// outermost fn of their contexts, when that fn has no callers and so runs once: the calls
+    /// on exclusive branches of it never both run, though they reach the same callee
+    pub fn can_arrive_in_context(&self, from: &CtxtSenSpanInfo, to: &CtxtSenSpanInfo) -> bool {
+        let from_bb_id = GlobalBasicBlockId::new(from.def_id, from.basic_block_id);
+        let to_bb_id = GlobalBasicBlockId::new(to.def_id, to.basic_block_id);
+        let outermost = |info: &CtxtSenSpanInfo, bb_id| info.caller_context.g_bb_ids.first().copied().unwrap_or(bb_id);
+        let (outer_from, outer_to) = (outermost(from, from_bb_id), outermost(to, to_bb_id));
+        let runs_once = self.called_infos.get(&outer_from.def_id).map_or(true, |callers| callers.is_empty());
+        if outer_from != outer_to && outer_from.def_id == outer_to.def_id && runs_once {
+            return self.can_arrive(outer_from, outer_to);
         }
+        self.can_arrive(from_bb_id, to_bb_id)
     }
-    path.pop();
-    return false;
-} 
+}
 
 pub fn can_basic_block_arrive(
     cfgs: &HashMap<DefId, ControlFlowGraph>,
     called_infos: &HashMap<DefId, HashSet<GlobalBasicBlockId>>,
-    visited: &mut HashSet<GlobalBasicBlockId>,
     from: GlobalBasicBlockId,
     to: GlobalBasicBlockId,
 ) -> bool {
-    if from == to {
-        return true;
-    }
-
-    if visited.contains(&from) {
-        return false;
-    }
-
-    if !can_call_arrive(
-        cfgs,
-        called_infos,
-        &mut HashSet::new(),
-        from.def_id,
-        to.def_id,
-    ) {
-        return false;
-    }
+    let mut visited = HashSet::new();
+    let mut worklist = VecDeque::from([from]);
+    while let Some(g_bb_id) = worklist.pop_front() {
+        if g_bb_id == to {
+            return true;
+        }
+        if visited.contains(&g_bb_id) || !can_call_arrive(cfgs, called_infos, g_bb_id.def_id, to.def_id) {
+            continue;
+        }
+        visited.insert(g_bb_id);
 
-    visited.insert(from);
+        let cfg = match cfgs.get(&g_bb_id.def_id) {
+            Some(cfg) => cfg,
+            None => continue,
+        };
 
-    if from.def_id == to.def_id {
-        return can_inner_basic_block_arrive(
-            cfgs.get(&from.def_id).unwrap(),
-            &mut HashSet::new(),
-            from.bb_id,
-            to.bb_id,
-        );
-    } else {
-        let from_cfg = cfgs.get(&from.def_id).unwrap();
-        for (bb_id, call_info) in from_cfg.call_infos.iter() {
-            // register call
-            if cfgs.contains_key(&call_info.callee_def_id) {
-                // can internal transit to callsite
-                if can_inner_basic_block_arrive(from_cfg, &mut HashSet::new(), from.bb_id, *bb_id) {
-                    // walk to caller site, then transfer to the begin of callee, check next
-                    if can_basic_block_arrive(
-                        cfgs,
-                        called_infos,
-                        visited,
-                        GlobalBasicBlockId::new(
-                            call_info.callee_def_id,
-                            BasicBlockId::from_usize(0),
-                        ),
-                        to,
-                    ) {
-                        return true;
-                    }
-                }
+        // in the fn of `to`, only the paths inside the fn are followed
+        if g_bb_id.def_id == to.def_id {
+            if can_inner_basic_block_arrive(cfg, g_bb_id.bb_id, to.bb_id) {
+                return true;
             }
+            continue;
         }
 
-        // assume all basic block can return
-        if let Some(call_info) = called_infos.get(&from.def_id) {
-            for ret_g_bb_id in call_info {
-                if can_basic_block_arrive(cfgs, called_infos, visited, *ret_g_bb_id, to) {
-                    return true;
+        for (bb_id, call_info) in cfg.call_infos.iter() {
+            // walk to caller site, then transfer to the begin of callee
+            for callee_def_id in call_info.callees() {
+                if cfgs.contains_key(&callee_def_id) && can_inner_basic_block_arrive(cfg, g_bb_id.bb_id, *bb_id) {
+                    worklist.push_back(GlobalBasicBlockId::new(callee_def_id, BasicBlockId::from_usize(0)));
                 }
             }
         }
 
-        return false;
-    }
-}
-
-pub fn can_inner_basic_block_arrive(
-    cfg: &ControlFlowGraph,
-    visited: &mut HashSet<BasicBlockId>,
-    from: BasicBlockId,
-    to: BasicBlockId,
-) -> bool {
-    if from == to {
-        return true;
-    }
-
-    if visited.contains(&from) {
-        return false;
+        // assume all basic block can return
+        if let Some(ret_g_bb_ids) = called_infos.get(&g_bb_id.def_id) {
+            worklist.extend(ret_g_bb_ids.iter().copied());
+        }
     }
 
-    visited.insert(from);
+    false
+}
 
-    for successor in cfg.basic_block_infos.get(&from).unwrap().successors.iter() {
-        if can_inner_basic_block_arrive(cfg, visited, *successor, to) {
+pub fn can_inner_basic_block_arrive(cfg: &ControlFlowGraph, from: BasicBlockId, to: BasicBlockId) -> bool {
+    let mut visited = HashSet::new();
+    let mut worklist = VecDeque::from([from]);
+    while let Some(bb_id) = worklist.pop_front() {
+        if bb_id == to {
             return true;
         }
+        if !visited.insert(bb_id) {
+            continue;
+        }
+        if let Some(bb_info) = cfg.basic_block_infos.get(&bb_id) {
+            worklist.extend(bb_info.successors.iter().copied());
+        }
     }
 
-    return false;
+    false
 }
 
 // eg：src/main.rs:1:2: 3:4 (#0)
@@ -456,12 +1084,178 @@ pub fn parse_span(span: &Span) -> (String, (usize, usize), (usize, usize)) {
     (filename, line_range, column_range)
 }
 
+// changed line ranges since `base_ref`, keyed by the absolute path of the file
+pub fn get_changed_lines(base_ref: &str) -> Result<HashMap<PathBuf, Vec<(usize, usize)>>, String> {
+    let run_git = |args: &[&str]| -> Result<String, String> {
+        let output = Command::new("git")
+            .args(args)
+            .output()
+            .map_err(|err| format!("failed to run git: {}", err))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+
+    let top_level = PathBuf::from(run_git(&["rev-parse", "--show-toplevel"])?.trim());
+    let diff = run_git(&[
+        "diff",
+        "--unified=0",
+        "--no-color",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+        base_ref,
+    ])?;
+
+    Ok(parse_unified_diff(&diff)
+        .into_iter()
+        .map(|(path, ranges)| (top_level.join(path), ranges))
+        .collect())
+}
+
+// eg: `+++ b/src/main.rs` then `@@ -10,2 +12,3 @@`
+// parse: src/main.rs -> [(12, 14)]
+pub fn parse_unified_diff(diff: &str) -> HashMap<String, Vec<(usize, usize)>> {
+    let mut changed_lines = HashMap::<String, Vec<(usize, usize)>>::new();
+    let mut current_file = None;
+    // the old and new lines left in the current hunk, its body may look like a header,
+    // eg. an added `++ x` is `+++ x`
+    let mut hunk_left = (0usize, 0usize);
+
+    for line in diff.lines() {
+        if hunk_left != (0, 0) {
+            let (old_left, new_left) = hunk_left;
+            let is_body = match line.chars().next() {
+                Some('-') => Some((old_left.saturating_sub(1), new_left)),
+                Some('+') => Some((old_left, new_left.saturating_sub(1))),
+                Some(' ') => Some((old_left.saturating_sub(1), new_left.saturating_sub(1))),
+                // `\ No newline at end of file`
+                Some('\\') => Some(hunk_left),
+                _ => None,
+            };
+            // not a body line, the hunk was cut short
+            hunk_left = is_body.unwrap_or((0, 0));
+            if is_body.is_some() {
+                continue;
+            }
+        }
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current_file = path.strip_prefix("b/").map(|s| s.to_owned());
+        } else if line.starts_with("@@ ") {
+            let range = |prefix: char| {
+                let hunk = &line.split_whitespace().find(|s| s.starts_with(prefix))?[1..];
+                let mut hunk_iter = hunk.split(',');
+                let start = hunk_iter.next().and_then(|s| s.parse::<usize>().ok()).unwrap_or(0);
+                let count = hunk_iter.next().and_then(|s| s.parse::<usize>().ok()).unwrap_or(1);
+                Some((start, count))
+            };
+            let (old_count, (start, count)) = match (range('-'), range('+')) {
+                (old, Some(new)) => (old.map_or(0, |(_, count)| count), new),
+                (_, None) => continue,
+            };
+            hunk_left = (old_count, count);
+            let file = match &current_file {
+                Some(file) => file,
+                None => continue,
+            };
+            // pure deletion, mark the line before it
+            let range = if count == 0 {
+                (start.max(1), start.max(1))
+            } else {
+                (start, start + count - 1)
+            };
+            changed_lines.entry(file.clone()).or_default().push(range);
+        }
+    }
+
+    changed_lines
+}
+
+pub fn span_intersects_changed_lines(
+    span: &Span,
+    changed_lines: &HashMap<PathBuf, Vec<(usize, usize)>>,
+) -> bool {
+    let (filename, (line_lo, line_hi), _) = parse_span(span);
+    let path = match std::fs::canonicalize(&filename) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    changed_lines.get(&path).map_or(false, |ranges| {
+        ranges.iter().any(|(lo, hi)| *lo <= line_hi && line_lo <= *hi)
+    })
+}
+
+// `mem::drop` drops its arg at the end of its body, which is modeled as a drop of the arg at the call
+const MEM_DROP_PATH: &str = "core::mem::drop";
+// `mem::forget` never unwinds, the drops in the cleanup of its call never run
+const MEM_FORGET_PATH: &str = "core::mem::forget";
+
+pub fn is_mem_drop(def_path: &[String]) -> bool {
+    def_path.join("::") == MEM_DROP_PATH
+}
+
+pub fn is_mem_forget(def_path: &[String]) -> bool {
+    def_path.join("::") == MEM_FORGET_PATH
+}
+
+// the arg dropped by a call, by a registered drop model or by `mem::drop` without any model
+pub fn get_dropped_arg(opts: &AnalysisOptions, def_path: &[String]) -> Option<usize> {
+    match get_model(opts, def_path) {
+        Some(ModelKind::Drop { arg }) => Some(*arg),
+        Some(_) => None,
+        None if is_mem_drop(def_path) => Some(0),
+        None => None,
+    }
+}
+
+const ASSERT_MACRO_NAMES: [&str; 6] = [
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+];
+
+// the call site of the outermost `assert!`-like macro whose desugaring contains the span
+pub fn get_assert_call_site(span: Span) -> Option<Span> {
+    let mut cur_span = span;
+    let mut call_site = None;
+    while cur_span.from_expansion() {
+        let expn_data = cur_span.ctxt().outer_expn_data();
+        if let ExpnKind::Macro(MacroKind::Bang, name) = expn_data.kind {
+            if ASSERT_MACRO_NAMES.contains(&name.as_str()) {
+                call_site = Some(expn_data.call_site);
+            }
+        }
+        cur_span = expn_data.call_site;
+    }
+    call_site
+}
+
+// point the spans inside an assertion at the assertion written by the user
+pub fn get_user_span(span: Span) -> Span {
+    get_assert_call_site(span).unwrap_or(span)
+}
+
 // DefId(0:4 ~ test02[fd64]::utils::foo)
 pub fn parse_def_id(def_id: DefId) -> Vec<String> {
-    let def_id_str = format!("{:?}", def_id);
+    parse_def_id_str(&format!("{:?}", def_id))
+}
+
+// the joined def path is one of the `(path prefix, name)`, eg. `("core::ptr::", "::read")`
+pub fn is_def_named(def_name: &str, def_names: &[(&str, &str)]) -> bool {
+    def_names
+        .iter()
+        .any(|(prefix, name)| def_name.starts_with(prefix) && def_name.ends_with(name))
+}
+
+// eg. `DefId(0:5 ~ krate[abcd]::f::{closure#0})` is `["krate", "f", "closure"]`. The closures and
+// anonymous consts of a fn are named without their disambiguators, as in `f::{{closure}}` of older rustc
+pub fn parse_def_id_str(def_id_str: &str) -> Vec<String> {
     let mut def_id_str_iter = def_id_str.split(" ~ ");
     let _ = def_id_str_iter.next();
-    let def_id_str_iter = def_id_str_iter.next().unwrap().split("::");
+    let def_id_str_iter = def_id_str_iter.next().unwrap_or(def_id_str).split("::");
 
     def_id_str_iter
         .map(|s| {
@@ -471,7 +1265,12 @@ pub fn parse_def_id(def_id: DefId) -> Vec<String> {
             let mut s_iter = s.split(')');
             let s = s_iter.next().unwrap();
 
-            s.to_string()
+            let name = s.trim_start_matches('{').trim_end_matches('}');
+            let name = name.split('#').next().unwrap();
+            match name {
+                "closure" | "constant" if s.starts_with('{') => name.to_string(),
+                _ => s.to_string(),
+            }
         })
         .collect()
 }
@@ -481,6 +1280,18 @@ pub fn parse_entry(entry: &str) -> Vec<String> {
     entry_iter.map(|s| s.to_string()).collect()
 }
 
+// `crate::a::f` is the full path of a fn in the analyzed crate, `::krate::a::f` is the full path
+// of a fn in any crate, otherwise the entry is a suffix of the path
+pub fn entry_matches(entry: &str, def_path: &[String], is_local: bool) -> bool {
+    if let Some(path) = entry.strip_prefix("crate::") {
+        is_local && def_path.get(1..).map_or(false, |rest| rest.iter().eq(path.split("::")))
+    } else if let Some(path) = entry.strip_prefix("::") {
+        def_path.iter().eq(path.split("::"))
+    } else {
+        entry_is_suffix_of(&parse_entry(entry), &def_path.to_vec())
+    }
+}
+
 pub fn entry_is_suffix_of(entry: &Vec<String>, def_id: &Vec<String>) -> bool {
     if entry.len() > def_id.len() {
         return false;
@@ -495,58 +1306,467 @@ pub fn entry_is_suffix_of(entry: &Vec<String>, def_id: &Vec<String>) -> bool {
     return true;
 }
 
-// get nodes with indegree is 0, which means they are the entry of the call graph. Loop edges are not considered.
+// get the entries of the call graph: the fns without callers, and for a cycle of fns calling each other
+// without callers outside of it, eg. mutual recursion, one fn of the cycle. Loop edges are not considered.
 pub fn get_top_def_ids(cfgs: &HashMap<DefId, ControlFlowGraph>) -> Vec<DefId> {
-    let mut in_degree = cfgs
-        .keys()
-        .map(|k| (*k, 0))
-        .collect::<HashMap<DefId, usize>>();
-
-    for (def_id, cfg) in cfgs.iter() {
-        for (_, call_info) in cfg.call_infos.iter() {
-            if cfgs.contains_key(&call_info.callee_def_id) && call_info.callee_def_id != *def_id {
-                in_degree
-                    .entry(call_info.callee_def_id)
-                    .and_modify(|e| *e += 1);
+    let call_graph = cfgs
+        .iter()
+        .map(|(def_id, cfg)| {
+            let callees = cfg
+                .call_infos
+                .values()
+                .flat_map(|call_info| call_info.callees())
+                .filter(|callee_def_id| cfgs.contains_key(callee_def_id))
+                .collect::<Vec<_>>();
+            (*def_id, callees)
+        })
+        .collect::<HashMap<_, _>>();
+    get_top_nodes(&call_graph)
+}
+
+// the least node of each strongly connected component without edges from the other components,
+// found by Tarjan's algorithm, a component of one node has no edges from other nodes
+pub fn get_top_nodes<T: Copy + Eq + Hash + Ord>(graph: &HashMap<T, Vec<T>>) -> Vec<T> {
+    struct Tarjan<'a, T> {
+        graph: &'a HashMap<T, Vec<T>>,
+        index: HashMap<T, usize>,
+        low_link: HashMap<T, usize>,
+        stack: Vec<T>,
+        on_stack: HashSet<T>,
+        components: Vec<Vec<T>>,
+    }
+
+    impl<'a, T: Copy + Eq + Hash + Ord> Tarjan<'a, T> {
+        fn visit(&mut self, node: T) {
+            let index = self.index.len();
+            self.index.insert(node, index);
+            self.low_link.insert(node, index);
+            self.stack.push(node);
+            self.on_stack.insert(node);
+
+            for succ in self.graph.get(&node).into_iter().flatten() {
+                if !self.index.contains_key(succ) {
+                    self.visit(*succ);
+                    let low_link = self.low_link[&node].min(self.low_link[succ]);
+                    self.low_link.insert(node, low_link);
+                } else if self.on_stack.contains(succ) {
+                    let low_link = self.low_link[&node].min(self.index[succ]);
+                    self.low_link.insert(node, low_link);
+                }
+            }
+
+            if self.low_link[&node] == self.index[&node] {
+                let mut component = vec![];
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: vec![],
+        on_stack: HashSet::new(),
+        components: vec![],
+    };
+    for node in graph.keys().sorted() {
+        if !tarjan.index.contains_key(node) {
+            tarjan.visit(*node);
+        }
+    }
+
+    let component_of = tarjan
+        .components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, component)| component.iter().map(move |node| (*node, i)))
+        .collect::<HashMap<_, _>>();
+    let mut has_caller = vec![false; tarjan.components.len()];
+    for (node, succs) in graph.iter() {
+        for succ in succs.iter() {
+            if let Some(succ_component) = component_of.get(succ) {
+                if *succ_component != component_of[node] {
+                    has_caller[*succ_component] = true;
+                }
             }
         }
     }
 
-    in_degree
+    tarjan
+        .components
         .iter()
-        .filter(|(_, v)| **v == 0)
-        .map(|(k, _)| *k)
+        .zip(has_caller)
+        .filter(|(_, has_caller)| !has_caller)
+        .filter_map(|(component, _)| component.iter().min().copied())
+        .sorted()
         .collect()
 }
 
+static COLORED: AtomicBool = AtomicBool::new(true);
+
+// the only switch of the colors, both `termcolor` and `colored` follow it
+pub fn set_colored(colored: bool) {
+    COLORED.store(colored, Ordering::Relaxed);
+    colored::control::set_override(colored);
+}
+
+static UNDERLINE_CHAR: AtomicU32 = AtomicU32::new('^' as u32);
+
+pub fn set_underline_char(underline_char: char) {
+    UNDERLINE_CHAR.store(underline_char as u32, Ordering::Relaxed);
+}
+
+// the mark under the highlighted columns, an empty span still gets one char
+pub fn underline(width: usize) -> String {
+    let underline_char = char::from_u32(UNDERLINE_CHAR.load(Ordering::Relaxed)).unwrap_or('^');
+    underline_char.to_string().repeat(width.max(1))
+}
+
+thread_local! {
+    // a panic in `catch_panic` is reported by its caller, not by the panic hook
+    static CATCHING_PANIC: Cell<bool> = Cell::new(false);
+}
+
+static QUIET_PANIC_HOOK: std::sync::Once = std::sync::Once::new();
+
+// run `f` and turn its panic into the panic message, rustc fatal errors are still raised.
+// The panic hook, eg. the ICE banner of rustc, is skipped for the panics caught here
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    QUIET_PANIC_HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if CATCHING_PANIC.with(|catching| catching.get()) {
+                log::debug!("caught {}", info);
+            } else {
+                hook(info);
+            }
+        }));
+    });
+    let was_catching = CATCHING_PANIC.with(|catching| catching.replace(true));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CATCHING_PANIC.with(|catching| catching.set(was_catching));
+    result.map_err(|payload| {
+        if payload.is::<rustc_errors::FatalErrorMarker>() {
+            std::panic::resume_unwind(payload);
+        }
+        if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic".to_string()
+        }
+    })
+}
+
+pub fn format_with_color(text: &str, color: Color) -> Result<String, std::io::Error> {
+    let mut buffer = if COLORED.load(Ordering::Relaxed) {
+        Buffer::ansi()
+    } else {
+        Buffer::no_color()
+    };
+    buffer.set_color(ColorSpec::new().set_fg(Some(color)))?;
+    write!(&mut buffer, "{}", text)?;
+    buffer.reset()?;
+    Ok(String::from_utf8_lossy(buffer.as_slice()).into_owned())
+}
+
+thread_local! {
+    // the plain text printed by `capture_plain_output`
+    static CAPTURED_OUTPUT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+// run `f` and return what it prints with colors as plain text instead of printing it,
+// the lines are elided to the default width so the text doesn't depend on the terminal
+pub fn capture_plain_output(f: impl FnOnce()) -> String {
+    CAPTURED_OUTPUT.with(|captured| *captured.borrow_mut() = Some(String::new()));
+    f();
+    CAPTURED_OUTPUT.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+fn try_capture(text: &str) -> bool {
+    CAPTURED_OUTPUT.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(captured) => {
+            captured.push_str(text);
+            true
+        }
+        None => false,
+    })
+}
+
 pub fn print_with_color(text: &str, color: Color) -> Result<(), std::io::Error> {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
-    write!(&mut stdout, "{}", text)?;
-    stdout.reset()?;
-    Ok(())
+    if try_capture(text) {
+        return Ok(());
+    }
+    let text = format_with_color(text, color)?;
+    write!(std::io::stdout(), "{}", text)
 }
 
 pub fn println_with_color(text: &str, color: Color) -> Result<(), std::io::Error> {
-    let mut stdout = StandardStream::stdout(ColorChoice::Always);
-    stdout.set_color(ColorSpec::new().set_fg(Some(color)))?;
-    writeln!(&mut stdout, "{}", text)?;
-    stdout.reset()?;
-    Ok(())
+    if try_capture(&format!("{}\n", text)) {
+        return Ok(());
+    }
+    let text = format_with_color(text, color)?;
+    writeln!(std::io::stdout(), "{}", text)
+}
+
+// used when stdout is not a terminal
+const DEFAULT_TERMINAL_WIDTH: usize = 120;
+const ELLIPSIS: &str = "...";
+
+pub fn get_terminal_width() -> usize {
+    if CAPTURED_OUTPUT.with(|captured| captured.borrow().is_some()) {
+        return DEFAULT_TERMINAL_WIDTH;
+    }
+    try_get_terminal_width().unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+#[cfg(unix)]
+fn try_get_terminal_width() -> Option<usize> {
+    unsafe {
+        if libc::isatty(libc::STDOUT_FILENO) == 0 {
+            return None;
+        }
+        let mut size: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) == 0 && size.ws_col > 0 {
+            Some(size.ws_col as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn try_get_terminal_width() -> Option<usize> {
+    None
+}
+
+// elide a line longer than `width` chars around the highlighted columns,
+// returns the elided line and the columns shifted to it
+pub fn elide_line(line: &str, col_range: (usize, usize), width: usize) -> (String, (usize, usize)) {
+    let chars = line.chars().collect::<Vec<_>>();
+    if chars.len() <= width {
+        return (line.to_owned(), col_range);
+    }
+
+    let budget = std::cmp::max(width.saturating_sub(2 * ELLIPSIS.len()), 1);
+    let lo = col_range.0.saturating_sub(1).min(chars.len());
+    let hi = col_range.1.clamp(lo, chars.len());
+    // center the highlight, or show its beginning if it doesn't fit
+    let start = if hi - lo >= budget {
+        lo
+    } else {
+        lo.saturating_sub((budget - (hi - lo)) / 2)
+    };
+    let start = start.min(chars.len().saturating_sub(budget));
+    let end = std::cmp::min(start + budget, chars.len());
+
+    let mut elided = String::new();
+    if start > 0 {
+        elided.push_str(ELLIPSIS);
+    }
+    elided.extend(&chars[start..end]);
+    if end < chars.len() {
+        elided.push_str(ELLIPSIS);
+    }
+
+    let prefix_len = if start > 0 { ELLIPSIS.len() } else { 0 };
+    let shift = |col: usize| col.clamp(start + 1, end) - start + prefix_len;
+    (elided, (shift(col_range.0), shift(col_range.1)))
+}
+
+// the 1-based, inclusive columns to underline on `line` at `line_no` of a span: the tail of the
+// first line, the whole lines in between and the head of the last line, without the indentation
+pub fn get_underline_cols(
+    line: &str,
+    line_no: usize,
+    line_range: (usize, usize),
+    col_range: (usize, usize),
+) -> (usize, usize) {
+    let line_len = line.chars().count();
+    let col_start = if line_no == line_range.0 {
+        col_range.0.max(1)
+    } else {
+        line.chars().take_while(|c| c.is_whitespace()).count() + 1
+    };
+    let col_end = if line_no == line_range.1 { col_range.1 } else { line_len };
+    // a wrapped span ending before its start marks the start only
+    (col_start, col_end.max(col_start))
+}
+
+// the indent and the underline length of a highlight at `col_range`, the column low of a synthesized
+// span may be 0
+pub fn get_caret_indent(col_range: (usize, usize)) -> (usize, usize) {
+    let col_start = col_range.0.max(1);
+    (col_start - 1, col_range.1.saturating_sub(col_start))
+}
+
+// the 1-based, inclusive terminal columns of the 1-based, inclusive char columns on `line`,
+// wide characters such as CJK take two terminal columns
+pub fn get_display_cols(line: &str, col_range: (usize, usize)) -> (usize, usize) {
+    let line_len = line.chars().count();
+    // columns past the end of the line are one terminal column each
+    let width = |cols: usize| {
+        line.chars().take(cols).map(|c| c.width().unwrap_or(1)).sum::<usize>() + cols.saturating_sub(line_len)
+    };
+    let col_start = width(col_range.0.saturating_sub(1)) + 1;
+    (col_start, width(col_range.1).max(col_start))
 }
 
 pub fn get_lines_in_file(file_path: &str, line_range: (usize, usize)) -> Vec<String> {
+    get_lines_in_file_cached(file_path, line_range).unwrap()
+}
+
+lazy_static! {
+// file path -> all lines of the file, so that each file is read once for all findings,
+// behind a mutex so that the entries may be analyzed in parallel
+static ref SOURCE_CACHE: std::sync::Mutex<HashMap<String, std::sync::Arc<Vec<String>>>> = Default::default();
+}
+
+// the lines in `line_range` (1-based, inclusive) from the cached file, a file that fails to read is not cached
+pub fn get_lines_in_file_cached(
+    file_path: &str,
+    line_range: (usize, usize),
+) -> Result<Vec<String>, std::io::Error> {
+    let lines = {
+        // a panic of another entry doesn't break the cache
+        let mut cache = SOURCE_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let cached = cache.get(file_path).cloned();
+        match cached {
+            Some(lines) => lines,
+            None => {
+                let lines = std::sync::Arc::new(try_get_lines_in_file(file_path, (1, usize::MAX))?);
+                cache.insert(file_path.to_owned(), lines.clone());
+                lines
+            }
+        }
+    };
+    let end = line_range.1.min(lines.len());
+    let start = line_range.0.saturating_sub(1).min(end);
+    Ok(lines[start..end].to_vec())
+}
+
+pub fn try_get_lines_in_file(
+    file_path: &str,
+    line_range: (usize, usize),
+) -> Result<Vec<String>, std::io::Error> {
     let mut lines = Vec::new();
 
-    let file = std::fs::File::open(file_path).unwrap();
+    let file = std::fs::File::open(file_path)?;
     let reader = BufReader::new(file);
 
     for (i, line) in reader.lines().enumerate() {
         let i = i + 1;
         if i >= line_range.0 && i <= line_range.1 {
-            lines.push(line.unwrap());
+            lines.push(line?);
+        }
+    }
+
+    Ok(lines)
+}
+
+// LSP positions are 0-based, and the character counts the UTF-16 code units of the line,
+// `line` and `column` are 1-based as in the spans, the column counts chars
+pub fn to_lsp_position(line_text: &str, line: usize, column: usize) -> (usize, usize) {
+    let character = line_text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    (line.saturating_sub(1), character)
+}
+
+fn to_absolute_path(path: &std::path::Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| match std::env::current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    })
+}
+
+fn percent_encode_path(path: &str) -> String {
+    use std::fmt::Write;
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            write!(encoded, "%{:02X}", byte).unwrap();
         }
     }
+    encoded
+}
+
+pub fn path_to_uri(path: &std::path::Path) -> String {
+    format!("file://{}", percent_encode_path(&to_absolute_path(path).to_string_lossy()))
+}
 
-    lines
+// the sarif paths are relative to the manifest directory under cargo, otherwise to the current directory
+pub fn sarif_base_dir() -> PathBuf {
+    let base_dir = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+    to_absolute_path(&base_dir)
+}
+
+// the sarif `artifactLocation` of a span file, relative to the `%SRCROOT%` base `base_dir` if the file is in it,
+// otherwise an absolute file uri, eg. the std sources
+pub fn to_sarif_artifact_location(filename: &str, base_dir: &std::path::Path) -> serde_json::Value {
+    let path = to_absolute_path(std::path::Path::new(filename));
+    match path.strip_prefix(base_dir) {
+        Ok(relative) => {
+            let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+            serde_json::json!({ "uri": percent_encode_path(&relative), "uriBaseId": "%SRCROOT%" })
+        }
+        Err(_) => serde_json::json!({ "uri": path_to_uri(&path) }),
+    }
+}
+
+pub fn uri_to_path(uri: &str) -> Result<PathBuf, String> {
+    let path = uri
+        .strip_prefix("file://")
+        .ok_or(format!("only file uris are supported: {}", uri))?;
+    let mut bytes = vec![];
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next(), iter.next()]
+                .iter()
+                .map(|b| b.map(|b| b as char))
+                .collect::<Option<String>>()
+                .ok_or(format!("invalid uri: {}", uri))?;
+            bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| format!("invalid uri: {}", uri))?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes)
+        .map(PathBuf::from)
+        .map_err(|_| format!("invalid uri: {}", uri))
+}
+
+// the (workspace root, file) of the request read by `mc --lsp`, eg. `{"rootUri": ..., "textDocument": {"uri": ...}}`,
+// which may be the params of a json-rpc message, with or without the `Content-Length` header
+pub fn parse_lsp_request(request: &str) -> Result<(Option<PathBuf>, PathBuf), String> {
+    let request = match request.find("\r\n\r\n") {
+        Some(i) if request.starts_with("Content-Length:") => &request[i + 4..],
+        _ => request,
+    };
+    let request: serde_json::Value =
+        serde_json::from_str(request).map_err(|e| format!("invalid lsp request: {}", e))?;
+    let params = if request["params"].is_object() { &request["params"] } else { &request };
+
+    let root = match params["rootUri"].as_str() {
+        Some(uri) => Some(uri_to_path(uri)?),
+        None => None,
+    };
+    let file = params["textDocument"]["uri"]
+        .as_str()
+        .ok_or("missing `textDocument.uri` of lsp request")?;
+    Ok((root, uri_to_path(file)?))
 }
@@ -4,54 +4,234 @@
 #[macro_use]
 extern crate lazy_static;
 
+extern crate rustc_ast;
 extern crate rustc_driver;
 extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_index;
 extern crate rustc_interface;
 extern crate rustc_middle;
+extern crate rustc_session;
 extern crate rustc_span;
 
 pub mod core;
 use crate::core::check;
-use crate::core::{analysis, pfg::PointerFlowGraph, AnalysisOptions, CallerContext, CtxtSenCallId};
+use crate::core::{
+    analysis, pfg::PointerFlowGraph, AnalysisOptions, CallerContext, CtxtSenCallId, OutputFormat,
+};
 use termcolor::{Color};
 
 use crate::core::GlobalBasicBlockId;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use rustc_hir::def_id::DefId;
+use rustc_session::config::Input;
 
 use crate::core::{cfg::ControlFlowGraph, utils};
 use crate::core::cfg;
 
-pub fn analysis_then_check() -> Result<(), rustc_errors::ErrorGuaranteed> {
-    rustc_driver::catch_fatal_errors(move || {
+/// Exit codes of `mc` and `cargo mc`
+pub const EXIT_CLEAN: i32 = 0;
+/// the tool failed or was used wrongly, including compilation errors of the analyzed crate
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_FINDINGS: i32 = 2;
+/// the analysis stopped at `--timeout` before it found anything
+pub const EXIT_ABORTED: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalysisOutcome {
+    Clean,
+    Findings,
+}
+
+impl AnalysisOutcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            AnalysisOutcome::Clean => EXIT_CLEAN,
+            AnalysisOutcome::Findings => EXIT_FINDINGS,
+        }
+    }
+}
+
+/// Why the analysis gave no outcome, the rustc errors are not exposed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// the crate failed to compile, the errors are already reported by rustc
+    CompileError,
+    /// none of the functions given by `--entries` or `--entries-regex` exists in the crate
+    NoEntries,
+    /// the analysis was stopped before it finished
+    Aborted { reason: String },
+    /// the analysis stopped at `--timeout` without findings, there may be some it didn't reach
+    TimedOut,
+    /// an option has a value the analysis can't run with, eg. `--object-granularity=site`
+    InvalidOption { reason: String },
+}
+
+impl AnalysisError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AnalysisError::TimedOut => EXIT_ABORTED,
+            _ => EXIT_ERROR,
+        }
+    }
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::CompileError => write!(f, "the crate failed to compile"),
+            AnalysisError::NoEntries => write!(f, "no entry found, check the paths given by `--entries` or `--entries-regex`"),
+            AnalysisError::Aborted { reason } => write!(f, "the analysis was aborted: {}", reason),
+            AnalysisError::TimedOut => write!(f, "the analysis timed out, results may be incomplete"),
+            AnalysisError::InvalidOption { reason } => write!(f, "invalid option: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+pub fn analysis_then_check() -> Result<AnalysisOutcome, AnalysisError> {
+    analysis_then_check_with(|options| options)
+}
+
+/// Like `analysis_then_check`, but lets the caller adjust the parsed options,
+/// eg. register custom function models with `AnalysisOptions::with_model`
+pub fn analysis_then_check_with<F>(
+    customize: F,
+) -> Result<AnalysisOutcome, AnalysisError>
+where
+    F: FnOnce(AnalysisOptions) -> AnalysisOptions,
+{
+    analysis_then_check_with_args(std::env::args().collect(), customize)
+}
 
-        // behaviour like the real rustc
-        if std::env::var_os("MEMORY_CHECK_BE_RUSTC").is_some() {
-            let rustc_args = get_rustc_args(true);
+/// Like `analysis_then_check_with`, but with the given command line instead of the one of the process
+pub fn analysis_then_check_with_args<F>(
+    args: Vec<String>,
+    customize: F,
+) -> Result<AnalysisOutcome, AnalysisError>
+where
+    F: FnOnce(AnalysisOptions) -> AnalysisOptions,
+{
+    // behaviour like the real rustc
+    if std::env::var_os("MEMORY_CHECK_BE_RUSTC").is_some() {
+        return rustc_driver::catch_fatal_errors(move || {
+            let rustc_args = get_rustc_args(args, true);
             // log::debug!("rustc args: {:?}", rustc_args);
-            let (_, rustc_args) = utils::parse_args(&rustc_args);
+            let (_, rustc_args) = match utils::parse_args(&rustc_args) {
+                Ok(parsed) => parsed,
+                Err(reason) => return Ok(Err(AnalysisError::InvalidOption { reason })),
+            };
             rustc_driver::init_rustc_env_logger();
             let mut callbacks = rustc_driver::TimePassesCallbacks::default();
-            rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks).run()
+            rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks)
+                .run()
+                .map(|_| Ok(AnalysisOutcome::Clean))
+        })
+        .and_then(|result| result)
+        .map_err(|_| AnalysisError::CompileError)
+        .and_then(|result| result);
+    }
+    run_memory_check(args, customize, false).map(|(outcome, _)| outcome)
+}
+
+/// The findings of an analysis, returned by `run_analysis` instead of being printed.
+/// The spans only live as long as the compiler, so the findings are resolved to locations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnalysisReport {
+    /// the merged findings of all entries, sorted by their locations
+    pub findings: Vec<check::ResolvedFinding>,
+    /// the entries whose analysis panicked, their findings are lost
+    pub failed_entries: Vec<String>,
+    /// the analysis stopped at `--timeout`, the findings may be incomplete
+    pub timed_out: bool,
+    /// the drop objects and the pfg nodes of all entries, under `--stats`
+    pub stats: Option<(usize, usize)>,
+}
+
+impl AnalysisReport {
+    pub fn outcome(&self) -> AnalysisOutcome {
+        if self.findings.is_empty() {
+            AnalysisOutcome::Clean
         } else {
-            let rustc_args = get_rustc_args(false);
-            // log::debug!("rustc args: {:?}", rustc_args);
-            let (options, rustc_args) = utils::parse_args(&rustc_args);
-            if utils::open_dbg(&options) {
-                utils::init_log(log::Level::Debug).expect("init log failed");
-            }
-            let mut callbacks = MemoryCheckCallbacks { options };
-            rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks).run()
+            AnalysisOutcome::Findings
         }
+    }
+
+    /// The exit code `mc` would give for the report
+    pub fn exit_code(&self) -> i32 {
+        if !self.findings.is_empty() {
+            EXIT_FINDINGS
+        } else if self.timed_out {
+            EXIT_ABORTED
+        } else if !self.failed_entries.is_empty() {
+            EXIT_ERROR
+        } else {
+            EXIT_CLEAN
+        }
+    }
+}
+
+/// Runs the analysis with the given command line like `analysis_then_check_with_args`,
+/// but returns the findings instead of printing them, for embedding the checker in another tool.
+/// Only the warnings about the options and the entries go to stderr.
+/// This is the library API only, `mc` still prints through `analysis_then_check`: the rustc and lsp
+/// formats render the spans with the compiler, which is gone once the report is returned
+pub fn run_analysis(args: &[String]) -> Result<AnalysisReport, AnalysisError> {
+    run_memory_check(args.to_vec(), |options| options, true)
+        .map(|(_, report)| report.expect("the report of a quiet analysis"))
+}
+
+// the report is kept instead of printed if `quiet`
+fn run_memory_check<F>(
+    args: Vec<String>,
+    customize: F,
+    quiet: bool,
+) -> Result<(AnalysisOutcome, Option<AnalysisReport>), AnalysisError>
+where
+    F: FnOnce(AnalysisOptions) -> AnalysisOptions,
+{
+    rustc_driver::catch_fatal_errors(move || {
+        let rustc_args = get_rustc_args(args, false);
+        // log::debug!("rustc args: {:?}", rustc_args);
+        let (options, mut rustc_args) = match utils::parse_args(&rustc_args) {
+            Ok(parsed) => parsed,
+            Err(reason) => return Ok(Err(AnalysisError::InvalidOption { reason })),
+        };
+        // `#[mc::allow(..)]` is a known tool attribute, `#[cfg_attr(mc, ..)]` keeps it out of normal builds
+        rustc_args.extend(
+            ["-Zcrate-attr=feature(register_tool)", "-Zcrate-attr=register_tool(mc)", "--cfg=mc"]
+                .map(str::to_owned),
+        );
+        let options = customize(options);
+        utils::set_colored(utils::output_format(&options) == OutputFormat::Text && !utils::no_color(&options));
+        utils::set_underline_char(utils::underline_char(&options));
+        if utils::open_dbg(&options) {
+            utils::init_log(log::Level::Debug).expect("init log failed");
+        }
+        let mut callbacks = MemoryCheckCallbacks {
+            options,
+            quiet,
+            outcome: Ok(AnalysisOutcome::Clean),
+            report: None,
+        };
+        rustc_driver::RunCompiler::new(&rustc_args, &mut callbacks)
+            .run()
+            .map(|_| callbacks.outcome.map(|outcome| (outcome, callbacks.report)))
     })
     .and_then(|result| result)
+    .map_err(|_| AnalysisError::CompileError)
+    .and_then(|result| result)
 }
 
 struct MemoryCheckCallbacks {
     options: AnalysisOptions,
+    // keep the findings in `report` instead of printing them
+    quiet: bool,
+    outcome: Result<AnalysisOutcome, AnalysisError>,
+    report: Option<AnalysisReport>,
 }
 
 impl rustc_driver::Callbacks for MemoryCheckCallbacks {
@@ -63,29 +243,24 @@ impl rustc_driver::Callbacks for MemoryCheckCallbacks {
         compiler.session().abort_if_errors();
 
         queries.global_ctxt().unwrap().peek_mut().enter(|tcx| {
-            let mut cfgs: HashMap<DefId, ControlFlowGraph> = HashMap::new();
-
-            let mut called_infos = HashMap::<DefId, HashSet<GlobalBasicBlockId>>::new();
-
-            // create control flow graphs
-            tcx.hir().body_owners().for_each(|local_def_id| {
-                let def_id = local_def_id.to_def_id();
-
-                if let Some(cfg) = cfg::try_create_cfg(&self.options, tcx, def_id, true) {
-                    assert!(!cfgs.contains_key(&def_id));
-                    cfg::add_called_info(&self.options, &mut called_infos, &cfg);
-                    cfgs.insert(def_id, cfg);
-                }
-            });
+            let deadline = utils::timeout(&self.options).map(|timeout| Instant::now() + timeout);
+            let mut cfg_times = HashMap::new();
+            let (mut cfgs, mut called_infos) = create_cfgs(&self.options, tcx, &mut cfg_times);
 
             if utils::has_dbg(&self.options, "defid") {
                 let def_ids = cfgs.keys().collect::<Vec<_>>();
                 log::debug!("def ids: {:#?}", def_ids);
             }
 
+            // only the findings are emitted as diagnostics
+            let format = utils::output_format(&self.options);
+            let is_text = !self.quiet && (format == OutputFormat::Text || format == OutputFormat::TextNoColor);
+
             // auto or manual detect entries
             let entry_def_ids = if utils::auto_detect_entries(&self.options) {
-                check::output_level_text("info", "auto detect entries");
+                if is_text {
+                    check::output_level_text("info", "auto detect entries");
+                }
                 utils::get_top_def_ids(&cfgs)
             } else {
                 cfgs.keys()
@@ -95,18 +270,62 @@ impl rustc_driver::Callbacks for MemoryCheckCallbacks {
             };
 
             // output entries 
-            if !entry_def_ids.is_empty() {
+            if utils::list_entries(&self.options) {
+                for entry_def_id in entry_def_ids.iter() {
+                    let path = utils::parse_def_id(*entry_def_id).join("::");
+                    match utils::get_matched_entry(&self.options, *entry_def_id) {
+                        Some(entry) => println!("{} (matched `{}`)", path, entry),
+                        None => println!("{} (auto detected)", path),
+                    }
+                }
+            } else if is_text && !entry_def_ids.is_empty() {
                 check::output_level_text("info", "analysis from entries:");
                 for entry_def_id in entry_def_ids.iter() {
                     utils::print_with_color(" - ", Color::Blue).unwrap();
                     utils::println_with_color(&utils::parse_def_id(*entry_def_id).join("::"), Color::White).unwrap();
                 }
-            } else {
+            } else if is_text {
                 check::output_level_text("warning", "without entry");
             }
+            // a suffix or a regex may select more fns than intended
+            for (entry, selected) in utils::get_ambiguous_entries(&self.options, &entry_def_ids) {
+                let msg = format!("entry `{}` matches {} fns: {}", entry, selected.len(), selected.join(", "));
+                log::warn!("{}", msg);
+                if is_text {
+                    check::output_level_text("warning", &msg);
+                } else {
+                    eprintln!("warning: {}", msg);
+                }
+            }
+            if !utils::auto_detect_entries(&self.options) && entry_def_ids.is_empty() {
+                self.outcome = Err(AnalysisError::NoEntries);
+                return;
+            }
+            // a dry run, nothing is analyzed
+            if utils::list_entries(&self.options) {
+                if self.quiet {
+                    self.report = Some(AnalysisReport {
+                        findings: vec![],
+                        failed_entries: vec![],
+                        timed_out: false,
+                        stats: None,
+                    });
+                }
+                return;
+            }
+
+            if let Some(path) = utils::dump_pfg_path(&self.options) {
+                if let Err(err) = std::fs::write(path, "") {
+                    eprintln!("warning: failed to dump the pfg to `{}`: {}", path, err);
+                }
+            }
 
             // collect check infos
             let mut check_infos = HashMap::new();
+            let mut failed_entries = vec![];
+            let mut timed_out = false;
+            let mut analysis_times = HashMap::new();
+            let mut entry_stats = HashMap::new();
 
             for entry_def_id in entry_def_ids.iter() {
                 log::debug!("entry def id: {:?}", entry_def_id);
@@ -119,36 +338,201 @@ impl rustc_driver::Callbacks for MemoryCheckCallbacks {
                     pfg: PointerFlowGraph::new(),
                     cs_reachable_calls: HashSet::new(),
                     worklist: VecDeque::new(),
+                    deadline,
+                    timed_out,
+                    cfg_times: std::mem::take(&mut cfg_times),
                 };
 
-                let ctxt = analysis::alias_analysis(
-                    ctxt,
-                    CtxtSenCallId::new(*entry_def_id, CallerContext::new(vec![])),
-                );
+                // an analyzer bug in one entry must not lose the results of the others
+                let result = utils::catch_panic(|| {
+                    if utils::should_panic_in_entry(&self.options, *entry_def_id) {
+                        panic!("panic in entry {:?} for `--DBG=panic-entry`", entry_def_id);
+                    }
+                    let start = utils::profile(&self.options).map(|_| Instant::now());
+                    let ctxt = analysis::alias_analysis(
+                        ctxt,
+                        CtxtSenCallId::new(*entry_def_id, CallerContext::new(vec![])),
+                    );
+                    let mut entry_times = HashMap::new();
+                    utils::add_elapsed(&mut entry_times, *entry_def_id, start);
+                    let check_info = check::check_memory_bug(&ctxt);
+                    let pfg_stats = utils::stats(&self.options).then(|| ctxt.pfg.stats());
+                    (ctxt.cfgs, ctxt.called_infos, ctxt.timed_out, ctxt.cfg_times, entry_times, pfg_stats, check_info)
+                });
 
-                let check_info = check::check_memory_bug(&ctxt);
+                match result {
+                    Ok((entry_cfgs, entry_called_infos, entry_timed_out, entry_cfg_times, entry_times, pfg_stats, check_info)) => {
+                        cfgs = entry_cfgs;
+                        called_infos = entry_called_infos;
+                        timed_out = entry_timed_out;
+                        cfg_times = entry_cfg_times;
+                        analysis_times.extend(entry_times);
+                        if let Some(pfg_stats) = pfg_stats {
+                            entry_stats.insert(*entry_def_id, pfg_stats);
+                        }
+                        check_infos.insert(*entry_def_id, check_info);
+                    }
+                    Err(msg) => {
+                        let path = utils::parse_def_id(*entry_def_id).join("::");
+                        log::error!("analysis of entry {} panicked: {}", path, msg);
+                        failed_entries.push(path);
+                        // the moved graphs are lost with the panic, so are the cfg times
+                        (cfgs, called_infos) = create_cfgs(&self.options, tcx, &mut cfg_times);
+                    }
+                }
+            }
 
-                cfgs = ctxt.cfgs;
-                called_infos = ctxt.called_infos;
+            if timed_out {
+                log::warn!("analysis timed out");
+                if is_text {
+                    check::output_level_text("warning", "analysis timed out, results may be incomplete");
+                } else {
+                    eprintln!("warning: analysis timed out, results may be incomplete");
+                }
+            }
 
-                check_infos.insert(*entry_def_id, check_info);
+            if utils::warn_unsupported(&self.options) {
+                let mut unmodeled_assigns = cfgs.values().flat_map(|cfg| cfg.unmodeled_assigns.iter().copied()).collect::<Vec<_>>();
+                unmodeled_assigns.sort();
+                if utils::has_dbg(&self.options, "assign") {
+                    for (span, kind) in unmodeled_assigns.iter() {
+                        log::debug!("pointer operation not modeled: {} at {:?}", kind, span);
+                    }
+                }
+                if let Some(summary) = check::unmodeled_summary(&unmodeled_assigns) {
+                    if is_text {
+                        check::output_level_text("warning", &summary);
+                    } else {
+                        eprintln!("warning: {}", summary);
+                    }
+                }
             }
 
             if utils::has_dbg(&self.options, "check-info") {
                 log::debug!("check infos: {:#?}", check_infos);
             }
-            let check_result = check::merge_check_info(&cfgs, &check_infos);
+            // the other entries are still analyzed, they may fill the shared graphs
+            if let Some(entry) = utils::focus_entry(&self.options) {
+                check_infos.retain(|entry_def_id, _| utils::is_focused_entry(&self.options, *entry_def_id));
+                if check_infos.is_empty() {
+                    let msg = format!("focus entry `{}` matches no analyzed entry", entry);
+                    if is_text {
+                        check::output_level_text("warning", &msg);
+                    } else {
+                        eprintln!("warning: {}", msg);
+                    }
+                }
+            }
+            let check_result = check::merge_check_info(tcx, &self.options, &cfgs, &check_infos);
             if utils::has_dbg(&self.options, "check-result") {
                 log::debug!("check result: {:#?}", check_result);
             }
-            check::output_merged_result(&check_result);
+            if self.quiet {
+                let findings = check::resolve_findings(&check_result);
+                let stats = utils::stats(&self.options).then(|| check::total_stats(&entry_stats));
+                self.report = Some(AnalysisReport { findings, failed_entries, timed_out, stats });
+                return;
+            }
+            match format {
+                OutputFormat::Json => check::emit_merged_result(compiler.session(), &check_result),
+                OutputFormat::Lsp => {
+                    let root_file = match compiler.input() {
+                        Input::File(path) => Some(path.as_path()),
+                        Input::Str { .. } => None,
+                    };
+                    check::output_lsp_diagnostics(&check_result, root_file);
+                }
+                // only the summary below under `--quiet`
+                OutputFormat::Text | OutputFormat::TextNoColor if utils::quiet(&self.options) => {}
+                OutputFormat::Text | OutputFormat::TextNoColor => {
+                    check::output_merged_result(&check_result, utils::max_findings(&self.options))
+                }
+                OutputFormat::JsonReport => check::output_check_result_json(&check_result),
+                OutputFormat::Sarif => check::output_check_result_sarif(&check_result),
+            }
+            if let Some(path) = utils::report_path(&self.options) {
+                if let Err(err) = check::write_report(&check_result, path) {
+                    eprintln!("warning: failed to write the report to `{}`: {}", path, err);
+                }
+            }
+            if let Some(path) = utils::json_summary_path(&self.options) {
+                let summary = check::json_summary(cfgs.len(), entry_def_ids.len(), &check_result);
+                if let Err(err) = check::write_json_summary(&summary, path) {
+                    eprintln!("warning: failed to write the json summary to `{}`: {}", path, err);
+                }
+            }
+            if utils::summary_by_function(&self.options) {
+                let summary = check::summary_by_function(&check_result);
+                match format {
+                    OutputFormat::Json | OutputFormat::JsonReport => check::output_function_summary_json(&summary),
+                    OutputFormat::Text | OutputFormat::TextNoColor => check::output_function_summary(&summary),
+                    // the notifications are read by the editor and a sarif log is a single document,
+                    // a table doesn't fit in
+                    OutputFormat::Lsp | OutputFormat::Sarif => {}
+                }
+            }
+            if !failed_entries.is_empty() {
+                if is_text {
+                    check::output_level_text("warning", "analysis failed for entries:");
+                    for path in failed_entries.iter() {
+                        utils::print_with_color(" - ", Color::Blue).unwrap();
+                        utils::println_with_color(path, Color::White).unwrap();
+                    }
+                } else {
+                    eprintln!("warning: analysis failed for entries: {}", failed_entries.join(", "));
+                }
+            }
+            if is_text {
+                let summary = check::analysis_summary(cfgs.len(), entry_def_ids.len(), &check_result);
+                check::output_level_text("info", &summary);
+            }
+            if let Some(top) = utils::profile(&self.options) {
+                check::output_profile("cfg construction", "fns", &cfg_times, top);
+                check::output_profile("alias analysis", "entries", &analysis_times, top);
+            }
+            if utils::stats(&self.options) {
+                check::output_stats(&entry_stats);
+            }
+            if !check_result.is_empty() {
+                self.outcome = Ok(AnalysisOutcome::Findings);
+            } else if timed_out {
+                self.outcome = Err(AnalysisError::TimedOut);
+            } else if !failed_entries.is_empty() {
+                self.outcome = Err(AnalysisError::Aborted {
+                    reason: format!("analysis failed for {} entries", failed_entries.len()),
+                });
+            }
         });
         rustc_driver::Compilation::Continue
     }
 }
 
-fn get_rustc_args(is_rustc: bool) -> Vec<String> {
-    let mut rustc_args = std::env::args().into_iter().collect::<Vec<String>>();
+fn create_cfgs<'tcx>(
+    options: &AnalysisOptions,
+    tcx: rustc_middle::ty::TyCtxt<'tcx>,
+    cfg_times: &mut HashMap<DefId, Duration>,
+) -> (HashMap<DefId, ControlFlowGraph<'tcx>>, HashMap<DefId, HashSet<GlobalBasicBlockId>>) {
+    let mut cfgs: HashMap<DefId, ControlFlowGraph> = HashMap::new();
+
+    let mut called_infos = HashMap::<DefId, HashSet<GlobalBasicBlockId>>::new();
+
+    // create control flow graphs
+    tcx.hir().body_owners().for_each(|local_def_id| {
+        let def_id = local_def_id.to_def_id();
+
+        let start = utils::profile(options).map(|_| Instant::now());
+        let cfg = cfg::try_create_cfg(options, tcx, def_id, true);
+        utils::add_elapsed(cfg_times, def_id, start);
+        if let Some(cfg) = cfg {
+            assert!(!cfgs.contains_key(&def_id));
+            cfg::add_called_info(options, &mut called_infos, &cfg);
+            cfgs.insert(def_id, cfg);
+        }
+    });
+    (cfgs, called_infos)
+}
+
+fn get_rustc_args(mut rustc_args: Vec<String>, is_rustc: bool) -> Vec<String> {
 
     // Get MIR code for all code related to the crate (including the dependencies and standard library)
     let always_encode_mir = "-Zalways_encode_mir";
@@ -217,14 +601,1169 @@ mod tests {
             "--DBG=cfg,body".to_owned(),
         ];
 
-        let (options, _) = utils::parse_args(&args);
+        let (options, _) = utils::parse_args(&args).unwrap();
         assert_eq!(options.debug_opts, vec!["cfg", "body"]);
     }
 
+    #[test]
+    fn test_object_granularity_arg() {
+        use crate::core::ObjectGranularity;
+
+        let parse = |arg: &str| {
+            utils::parse_args(&["mc".to_owned(), arg.to_owned()]).map(|(options, _)| utils::object_granularity(&options))
+        };
+        assert_eq!(parse("--open-dbg=0"), Ok(ObjectGranularity::Local));
+        assert_eq!(parse("--object-granularity=type"), Ok(ObjectGranularity::Type));
+        assert!(parse("--object-granularity=site").is_err());
+    }
+
     #[test]
     fn test_entry_is_suffix_of() {
         let entry = vec!["b".to_string(), "c".to_string()];
         let def_id = vec!["a".to_string(), "b".to_string(), "c".to_string()];
         assert_eq!(utils::entry_is_suffix_of(&entry, &def_id), true);
     }
+
+    #[test]
+    fn test_top_nodes() {
+        use std::collections::HashMap;
+
+        // fn a() { b() } fn b() { a() }, neither is called from outside
+        let graph = HashMap::from([("a", vec!["b"]), ("b", vec!["a"])]);
+        assert_eq!(utils::get_top_nodes(&graph), ["a"]);
+
+        // the cycle called from `main` has no entry of its own, a self call is not a caller
+        let graph = HashMap::from([
+            ("main", vec!["a", "main"]),
+            ("a", vec!["b"]),
+            ("b", vec!["a", "c"]),
+            ("c", vec![]),
+            ("d", vec!["d"]),
+        ]);
+        assert_eq!(utils::get_top_nodes(&graph), ["d", "main"]);
+    }
+
+    #[test]
+    fn test_entry_matches() {
+        let def_path = vec!["krate".to_string(), "a".to_string(), "foo".to_string()];
+        assert!(utils::entry_matches("a::foo", &def_path, true));
+        assert!(utils::entry_matches("crate::a::foo", &def_path, true));
+        assert!(!utils::entry_matches("crate::a::foo", &def_path, false));
+        assert!(!utils::entry_matches("crate::foo", &def_path, true));
+        assert!(utils::entry_matches("::krate::a::foo", &def_path, false));
+        assert!(!utils::entry_matches("::a::foo", &def_path, true));
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--entries-regex=^krate::a::,(".to_owned()]).unwrap();
+        assert_eq!(options.entries_regex.len(), 1);
+        assert!(!utils::auto_detect_entries(&options));
+    }
+
+    #[test]
+    fn test_analyze_std() {
+        let args = ["mc", "--analyze-std=std::mem::swap,Option::insert"].map(str::to_owned);
+        let (options, _) = utils::parse_args(&args).unwrap();
+        assert_eq!(options.analyze_std, vec!["std::mem::swap", "Option::insert"]);
+
+        let def_path = |path: &str| utils::parse_entry(path);
+        assert!(utils::analyze_std_matches(&options, &def_path("core::mem::swap"), "std::mem::swap"));
+        assert!(utils::analyze_std_matches(
+            &options,
+            &def_path("core::option::{impl#0}::insert"),
+            "std::option::Option::<T>::insert"
+        ));
+        assert!(!utils::analyze_std_matches(&options, &def_path("core::ptr::swap"), "std::ptr::swap"));
+        assert!(!utils::analyze_std_matches(&options, &def_path("core::mem::replace"), "std::mem::replace"));
+    }
+
+    #[test]
+    fn test_parse_def_id_str() {
+        assert_eq!(utils::parse_def_id_str("DefId(0:4 ~ krate[abcd]::a::f)"), ["krate", "a", "f"]);
+        assert_eq!(utils::parse_def_id_str("DefId(0:5 ~ krate[abcd]::f::{closure#1})"), ["krate", "f", "closure"]);
+        assert_eq!(utils::parse_def_id_str("krate::f::{{closure}}"), ["krate", "f", "closure"]);
+        assert_eq!(utils::parse_def_id_str("DefId(0:6 ~ krate[abcd]::N::{constant#0})"), ["krate", "N", "constant"]);
+        // an impl is named by its disambiguator, the std models match it
+        assert_eq!(
+            utils::parse_def_id_str("DefId(2:7 ~ core[efgh]::option::{impl#0}::insert)"),
+            ["core", "option", "{impl#0}", "insert"]
+        );
+    }
+
+    #[test]
+    fn test_register_transfer_model() {
+        use crate::core::ModelKind;
+
+        let (options, _) = utils::parse_args(&["mc".to_owned()]).unwrap();
+        assert!(options.models.is_empty());
+
+        let options = options.with_model("pool::put", ModelKind::Transfer { from_arg: 1, to_arg: 0 });
+        let def_path = |path: &str| utils::parse_entry(path);
+        assert_eq!(
+            utils::get_model(&options, &def_path("demo::pool::put")),
+            Some(&ModelKind::Transfer { from_arg: 1, to_arg: 0 })
+        );
+        assert_eq!(utils::get_model(&options, &def_path("demo::other::put")), None);
+
+        // the latest registered model wins
+        let options = options.with_model("put", ModelKind::Ignore);
+        assert_eq!(utils::get_model(&options, &def_path("demo::pool::put")), Some(&ModelKind::Ignore));
+    }
+
+    #[test]
+    fn test_drop_fns_arg() {
+        use crate::core::ModelKind;
+
+        let args = ["mc".to_owned(), "--drop-fns=my_free,ffi::pool_free:1,libc::free:x".to_owned()];
+        let (options, _) = utils::parse_args(&args).unwrap();
+        let def_path = |path: &str| utils::parse_entry(path);
+        assert_eq!(utils::get_model(&options, &def_path("demo::my_free")), Some(&ModelKind::Drop { arg: 0 }));
+        assert_eq!(
+            utils::get_model(&options, &def_path("demo::ffi::pool_free")),
+            Some(&ModelKind::Drop { arg: 1 })
+        );
+        // invalid index
+        assert_eq!(utils::get_model(&options, &def_path("libc::free")), None);
+    }
+
+    #[test]
+    fn test_text_no_color() {
+        use crate::core::OutputFormat;
+        use colored::Colorize;
+        use termcolor::Color;
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--format=text-no-color".to_owned()]).unwrap();
+        assert_eq!(utils::output_format(&options), OutputFormat::TextNoColor);
+
+        // both `termcolor` and `colored` output are plain
+        utils::set_colored(false);
+        let text = utils::format_with_color("warning:", Color::Yellow).unwrap()
+            + &"(memory check)".cyan().bold().to_string();
+        assert!(!text.contains('\x1b'));
+        assert_eq!(text, "warning:(memory check)");
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--no-color=1".to_owned()]).unwrap();
+        assert!(utils::no_color(&options));
+        assert_eq!(utils::output_format(&options), OutputFormat::Text);
+        let (options, rustc_args) = utils::parse_args(&["mc".to_owned(), "--no-color".to_owned()]).unwrap();
+        assert!(utils::no_color(&options));
+        assert_eq!(rustc_args, ["mc"]);
+    }
+
+    #[test]
+    fn test_load_config() {
+        let dir = std::env::temp_dir().join(format!("mc-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(utils::load_config(&dir).is_empty());
+
+        let config = "entries = [\"krate::a\", \"krate::b\"]\ncontext_depth = 2\nformat = \"text-no-color\"\nunknown = 1\n";
+        std::fs::write(dir.join(utils::MC_CONFIG_FILENAME), config).unwrap();
+        assert_eq!(
+            utils::load_config(&dir),
+            ["--context-depth=2", "--entries=krate::a,krate::b", "--format=text-no-color"]
+        );
+        std::fs::remove_dir_all(&dir).ok();
+
+        // the args of the config come last, the first one wins
+        let args = ["mc", "--context-depth=3", "--context-depth=2"].map(str::to_owned);
+        assert_eq!(utils::context_depth(&utils::parse_args(&args).unwrap().0), 3);
+    }
+
+    #[test]
+    fn test_underline_char() {
+        let parse = |arg: &str| utils::underline_char(&utils::parse_args(&["mc".to_owned(), arg.to_owned()]).unwrap().0);
+        assert_eq!(parse("--open-dbg=0"), '^');
+        assert_eq!(parse("--underline-char=~"), '~');
+        assert_eq!(parse("--underline-char=ab"), '^');
+        assert_eq!(parse("--underline-char= "), '^');
+
+        utils::set_underline_char('~');
+        assert_eq!(utils::underline(3), "~~~");
+        assert_eq!(utils::underline(0), "~");
+        utils::set_underline_char('^');
+    }
+
+    #[test]
+    fn test_entry_panic_isolation() {
+        // the second entry hits an analyzer bug, the others still report
+        let entries = ["first", "buggy", "third"];
+        let mut reported = vec![];
+        let mut failed = vec![];
+        for entry in entries {
+            match utils::catch_panic(|| {
+                if entry == "buggy" {
+                    panic!("analyzer bug in {}", entry);
+                }
+                entry.len()
+            }) {
+                Ok(n) => reported.push((entry, n)),
+                Err(msg) => failed.push((entry, msg)),
+            }
+        }
+        assert_eq!(reported, vec![("first", 5), ("third", 5)]);
+        assert_eq!(failed, vec![("buggy", "analyzer bug in buggy".to_owned())]);
+        assert_eq!(utils::catch_panic(|| panic!("static")), Err::<(), _>("static".to_owned()));
+    }
+
+    #[test]
+    fn test_error_format_json() {
+        use crate::core::OutputFormat;
+
+        // the arg is kept for rustc to emit json diagnostics
+        let args = vec!["mc".to_owned(), "--error-format".to_owned(), "json".to_owned()];
+        let (options, rustc_args) = utils::parse_args(&args).unwrap();
+        assert_eq!(utils::output_format(&options), OutputFormat::Json);
+        assert_eq!(rustc_args, args);
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--error-format=human".to_owned()]).unwrap();
+        assert_eq!(utils::output_format(&options), OutputFormat::Text);
+
+        let args = ["mc", "--error-format=json", "--output-format=json"].map(str::to_owned);
+        let (options, _) = utils::parse_args(&args).unwrap();
+        assert_eq!(utils::output_format(&options), OutputFormat::JsonReport);
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--output-format=rustc".to_owned()]).unwrap();
+        assert_eq!(utils::output_format(&options), OutputFormat::Json);
+
+        // `--format` is an alias, the explicit `--output-format` wins
+        let parse = |args: &[&str]| {
+            let args = std::iter::once("mc").chain(args.iter().copied()).map(str::to_owned).collect::<Vec<_>>();
+            let (options, rustc_args) = utils::parse_args(&args).unwrap();
+            assert_eq!(rustc_args, ["mc"]);
+            utils::output_format(&options)
+        };
+        assert_eq!(parse(&["--output-format=text-no-color"]), OutputFormat::TextNoColor);
+        assert_eq!(parse(&["--format=sarif"]), OutputFormat::Sarif);
+        assert_eq!(parse(&["--format=json", "--output-format=lsp"]), OutputFormat::Lsp);
+        assert_eq!(parse(&["--format=txt"]), OutputFormat::Text);
+    }
+
+    #[test]
+    fn test_source_cache() {
+        let path = std::env::temp_dir().join(format!("mc-source-cache-{}.rs", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "a\nb\nc\n").unwrap();
+        assert_eq!(utils::get_lines_in_file_cached(path, (2, 3)).unwrap(), vec!["b", "c"]);
+
+        // read once, the ranges past the end are cut
+        std::fs::write(path, "changed\n").unwrap();
+        assert_eq!(utils::get_lines_in_file_cached(path, (1, 1)).unwrap(), vec!["a"]);
+        assert_eq!(utils::get_lines_in_file_cached(path, (3, 10)).unwrap(), vec!["c"]);
+        assert!(utils::get_lines_in_file_cached(path, (5, 10)).unwrap().is_empty());
+        std::fs::remove_file(path).unwrap();
+
+        assert!(utils::get_lines_in_file_cached("/nonexistent/mc.rs", (1, 1)).is_err());
+    }
+
+    #[test]
+    fn test_context_depth() {
+        use crate::core::{BasicBlockId, CallerContext, GlobalBasicBlockId};
+
+        let def_id = rustc_hir::def_id::CRATE_DEF_ID.to_def_id();
+        let call_site = |bb: usize| GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(bb));
+        let ctxt = CallerContext::new(vec![call_site(0), call_site(1)]);
+        assert_eq!(ctxt.push(call_site(2), 2).g_bb_ids, vec![call_site(1), call_site(2)]);
+        assert_eq!(ctxt.push(call_site(2), 3).g_bb_ids, vec![call_site(0), call_site(1), call_site(2)]);
+        assert!(ctxt.push(call_site(2), 0).is_same(&CallerContext::new(vec![])));
+        assert!(ctxt.has_caller(def_id));
+        assert!(!CallerContext::new(vec![]).has_caller(def_id));
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--context-depth=0".to_owned()]).unwrap();
+        assert_eq!(utils::context_depth(&options), 0);
+    }
+
+    #[test]
+    fn test_sarif_artifact_location() {
+        // relative to the base directory if the file is in it, the tests run in the manifest directory
+        let base_dir = utils::sarif_base_dir();
+        assert_eq!(
+            utils::to_sarif_artifact_location("src/core/../lib.rs", &base_dir),
+            serde_json::json!({ "uri": "src/lib.rs", "uriBaseId": "%SRCROOT%" })
+        );
+        assert_eq!(
+            utils::to_sarif_artifact_location("/rustc/library/core/src/my ptr.rs", &base_dir),
+            serde_json::json!({ "uri": "file:///rustc/library/core/src/my%20ptr.rs" })
+        );
+    }
+
+    #[test]
+    fn test_lsp_position_and_request() {
+        // `é` is one UTF-16 code unit, `😀` is two
+        let line = "let s = \"é😀\"; *p";
+        assert_eq!(utils::to_lsp_position(line, 3, 1), (2, 0));
+        assert_eq!(utils::to_lsp_position(line, 3, 15), (2, 15));
+        // past the end of the line
+        assert_eq!(utils::to_lsp_position("}", 1, 3), (0, 1));
+
+        let request = "Content-Length: 80\r\n\r\n{\"params\": {\"rootUri\": \"file:///work\",
+            \"textDocument\": {\"uri\": \"file:///work/src/my%20lib.rs\"}}}";
+        let (root, file) = utils::parse_lsp_request(request).unwrap();
+        assert_eq!(root, Some("/work".into()));
+        assert_eq!(file, std::path::PathBuf::from("/work/src/my lib.rs"));
+        assert!(utils::parse_lsp_request(r#"{"textDocument": {"uri": "untitled:1"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_explain_finding_errors() {
+        use crate::core::check;
+
+        assert!(check::explain_finding("not json").is_err());
+        assert!(check::explain_finding(r#"{"spans": []}"#).is_err());
+
+        // no saved code to fall back to
+        let finding = r#"{"message": "m", "spans": [{"file_name": "/nonexistent/main.rs",
+            "line_start": 1, "line_end": 1, "column_start": 1, "column_end": 2}]}"#;
+        assert!(check::explain_finding(finding).unwrap_err().starts_with("cannot read"));
+    }
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "diff --git a/src/main.rs b/src/main.rs\n\
+                    --- a/src/main.rs\n\
+                    +++ b/src/main.rs\n\
+                    @@ -10,2 +12,3 @@ fn main() {\n\
+                    @@ -20 +23 @@ fn foo() {\n\
+                    @@ -30,2 +32,0 @@ fn bar() {\n\
+                    diff --git a/old.rs b/old.rs\n\
+                    --- a/old.rs\n\
+                    +++ /dev/null\n\
+                    @@ -1,3 +0,0 @@\n";
+        let changed_lines = utils::parse_unified_diff(diff);
+        assert_eq!(changed_lines.len(), 1);
+        assert_eq!(changed_lines["src/main.rs"], vec![(12, 14), (23, 23), (32, 32)]);
+
+        // under `--unified=0`, the body lines `-- a` and `++ b` look like file headers
+        let diff = "--- a/src/lib.rs\n\
+                    +++ b/src/lib.rs\n\
+                    @@ -3 +3,2 @@\n\
+                    --- a\n\
+                    +++ b\n\
+                    +c\n\
+                    \\ No newline at end of file\n\
+                    @@ -9 +10 @@\n\
+                    -d\n\
+                    +e\n";
+        let changed_lines = utils::parse_unified_diff(diff);
+        assert_eq!(changed_lines.len(), 1);
+        assert_eq!(changed_lines["src/lib.rs"], vec![(3, 4), (10, 10)]);
+    }
+
+    #[test]
+    fn test_elide_line() {
+        let line = "0123456789".repeat(10);
+        assert_eq!(utils::elide_line(&line, (5, 8), 100), (line.clone(), (5, 8)));
+
+        // the highlight stays under the shifted columns
+        let (elided, (col_start, col_end)) = utils::elide_line(&line, (51, 53), 20);
+        assert_eq!(elided.chars().count(), 20);
+        assert!(elided.starts_with("...") && elided.ends_with("..."));
+        assert_eq!(&elided[col_start - 1..col_end], &line[50..53]);
+
+        let (elided, (col_start, _)) = utils::elide_line(&line, (1, 2), 20);
+        assert!(!elided.starts_with("..."));
+        assert_eq!(col_start, 1);
+    }
+
+    #[test]
+    fn test_underline_cols() {
+        // the tail of the first line, the whole middle lines and the head of the last line
+        assert_eq!(utils::get_underline_cols("    let x = foo(", 3, (3, 5), (13, 2)), (13, 16));
+        assert_eq!(utils::get_underline_cols("        a, b,", 4, (3, 5), (13, 2)), (9, 13));
+        assert_eq!(utils::get_underline_cols("    );", 5, (3, 5), (13, 6)), (5, 6));
+        assert_eq!(utils::get_underline_cols("    x", 1, (1, 1), (5, 6)), (5, 6));
+
+        // a wrapped span never underflows
+        assert_eq!(utils::get_underline_cols("ab", 1, (1, 1), (9, 3)), (9, 9));
+        assert_eq!(utils::get_underline_cols("", 2, (1, 3), (0, 0)), (1, 1));
+        assert_eq!(utils::get_underline_cols("ab", 1, (1, 2), (0, 1)), (1, 2));
+    }
+
+    #[test]
+    fn test_display_cols() {
+        assert_eq!(utils::get_display_cols("    let x = *p;", (13, 14)), (13, 14));
+        // each CJK character before or in the highlight takes two columns
+        assert_eq!(utils::get_display_cols("let 名字 = *p;", (10, 11)), (12, 13));
+        assert_eq!(utils::get_display_cols("let 名字 = *p;", (5, 6)), (5, 8));
+        // past the end of the line, e.g. the drop at a closing brace
+        assert_eq!(utils::get_display_cols("    }", (5, 6)), (5, 6));
+    }
+
+    #[test]
+    fn test_zero_column_span() {
+        use crate::core::check;
+
+        assert_eq!(utils::get_caret_indent((5, 8)), (4, 3));
+        assert_eq!(utils::get_caret_indent((0, 0)), (0, 0));
+        assert_eq!(utils::get_caret_indent((0, 3)), (0, 2));
+
+        // a synthesized span starting at column 0 is rendered from the saved code
+        let finding = r#"{"level": "warning", "message": "double free detected", "spans": [
+            {"file_name": "/nonexistent/zero_column.rs", "line_start": 1, "line_end": 1,
+             "column_start": 0, "column_end": 0, "label": "first drop here", "text": [{"text": "}"}]}]}"#;
+        let text = utils::capture_plain_output(|| check::explain_finding(finding).unwrap());
+        assert!(text.contains("/nonexistent/zero_column.rs:1:0"));
+        assert!(text.contains("first drop here"));
+    }
+
+    #[test]
+    fn test_pfg_node_projection_index() {
+        use crate::core::pfg::PfgNode;
+        use crate::core::{BasicBlockId, CallerContext, GlobalBasicBlockId, GlobalLocalId, LocalId};
+        use rustc_middle::mir::PlaceElem;
+
+        let def_id = rustc_hir::def_id::CRATE_DEF_ID.to_def_id();
+        let mut node = PfgNode::new(GlobalLocalId::new(def_id, LocalId::from_usize(1)));
+        let ctxt = CallerContext::new(vec![]);
+
+        // a wide local, the linear scan made this quadratic
+        let n = 20000;
+        let projections = (0..n)
+            .map(|i| {
+                vec![PlaceElem::ConstantIndex {
+                    offset: i,
+                    min_length: n,
+                    from_end: false,
+                }]
+            })
+            .collect::<Vec<_>>();
+        for proj in projections.iter() {
+            node.add_projection(proj.clone(), ctxt.clone());
+        }
+
+        for (i, proj) in projections.iter().enumerate() {
+            assert_eq!(node.try_get_projection_id(proj, &ctxt), Some(i as u32));
+        }
+
+        // the lookup by key finds what the linear scan finds, whatever the minimum length
+        let linear_scan = |proj: &Vec<PlaceElem>| {
+            node.projection_nodes
+                .values()
+                .filter(|proj_node| proj_node.caller_context == ctxt && proj_node.is_same_projection(proj))
+                .map(|proj_node| proj_node.id)
+                .collect::<Vec<_>>()
+        };
+        for offset in (0..n).step_by(1000) {
+            let proj = vec![PlaceElem::ConstantIndex { offset, min_length: n + 1, from_end: false }];
+            let id = node.try_get_projection_id(&proj, &ctxt);
+            assert_eq!(id, Some(offset as u32));
+            assert_eq!(linear_scan(&proj), [id.unwrap()]);
+        }
+        let proj = vec![PlaceElem::ConstantIndex { offset: n, min_length: n + 1, from_end: false }];
+        assert_eq!(node.try_get_projection_id(&proj, &ctxt), None);
+        assert!(linear_scan(&proj).is_empty());
+
+        let other_ctxt =
+            CallerContext::new(vec![GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(0))]);
+        assert_eq!(node.try_get_projection_id(&projections[0], &other_ctxt), None);
+        assert!(node.try_get_projection_node_mut(&projections[1], &ctxt).is_some());
+
+        // the dynamic indices may be equal, they share one projection
+        let index = |local| vec![PlaceElem::Index(rustc_middle::mir::Local::from_u32(local))];
+        let index_id = node.add_projection(index(3), ctxt.clone());
+        assert_eq!(node.try_get_projection_id(&index(5), &ctxt), Some(index_id));
+        assert!(node.projection_nodes[&index_id].is_same_projection(&index(5)));
+    }
+
+    #[test]
+    fn test_reachability_without_cfg() {
+        use crate::core::{BasicBlockId, GlobalBasicBlockId};
+        use std::collections::{HashMap, HashSet};
+
+        // the callee is referenced by a call, but it has no cfg
+        let def_id = rustc_hir::def_id::CRATE_DEF_ID.to_def_id();
+        let callee_bb = GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(0));
+        let caller_bb = GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(1));
+        let cfgs = HashMap::new();
+        let called_infos = HashMap::from([(def_id, HashSet::from([caller_bb]))]);
+
+        let reach = utils::ReachabilityCache::new(&cfgs, &called_infos);
+        assert!(reach.can_arrive(callee_bb, callee_bb));
+        assert!(!reach.can_arrive(callee_bb, caller_bb));
+        assert_eq!(utils::basic_block_path(&cfgs, &called_infos, callee_bb, caller_bb), None);
+    }
+
+    #[test]
+    fn test_missing_callee_cfg() {
+        // the pointer returned by the callee without a cfg still aliases the freed box
+        let report = run("examples/use_after_free/missing_callee_cfg.rs", &[]);
+        assert!(report.failed_entries.is_empty());
+        assert_eq!(kinds_and_fns(&report), [("uaf", "read_after_forward")]);
+    }
+
+    #[test]
+    fn test_std_callee_without_mir() {
+        // `process::id` is selected by `--analyze-std` but has no MIR, its call is skipped
+        let report = run("examples/use_after_free/std_callee_without_mir.rs", &["--analyze-std=process::id"]);
+        assert!(report.failed_entries.is_empty());
+        assert_eq!(kinds_and_fns(&report), [("uaf", "main")]);
+    }
+
+    #[test]
+    fn test_reachability_long_chain() {
+        use crate::core::{cfg::ControlFlowGraph, BasicBlockId, BasicBlockInfo, GlobalBasicBlockId};
+        use rustc_middle::mir::{SourceInfo, Terminator, TerminatorKind};
+        use std::collections::{HashMap, HashSet};
+
+        // a chain of blocks much longer than the stack allows to recurse through
+        let n = 200000;
+        let def_id = rustc_hir::def_id::CRATE_DEF_ID.to_def_id();
+        let basic_block_infos = (0..n)
+            .map(|i| {
+                let id = BasicBlockId::from_usize(i);
+                let successors = (i + 1..n.min(i + 2)).map(BasicBlockId::from_usize).collect();
+                let terminator = Terminator {
+                    source_info: SourceInfo::outermost(rustc_span::DUMMY_SP),
+                    kind: TerminatorKind::Return,
+                };
+                let info = BasicBlockInfo { id, is_cleanup: false, successors, assignment_infos: vec![], terminator };
+                (id, info)
+            })
+            .collect();
+        let cfg = ControlFlowGraph {
+            options: utils::parse_args(&["mc".to_owned()]).unwrap().0,
+            def_id,
+            local_infos: HashMap::new(),
+            basic_block_infos,
+            call_infos: HashMap::new(),
+            ret_bbs: HashSet::new(),
+            assert_failure_bbs: HashSet::new(),
+            loop_drop_bbs: HashSet::new(),
+            allowed_bugs: HashSet::new(),
+            address_locals: HashSet::new(),
+            unmodeled_assigns: vec![],
+            is_local_crate: true,
+        };
+        let cfgs = HashMap::from([(def_id, cfg)]);
+        let called_infos = HashMap::new();
+
+        let reach = utils::ReachabilityCache::new(&cfgs, &called_infos);
+        let bb = |i| GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(i));
+        assert!(reach.can_arrive(bb(0), bb(n - 1)));
+        assert!(!reach.can_arrive(bb(n - 1), bb(0)));
+        let path = utils::basic_block_path(&cfgs, &called_infos, bb(0), bb(n - 1)).unwrap();
+        assert_eq!(path, (0..n).map(bb).collect::<Vec<_>>());
+        assert_eq!(utils::basic_block_path(&cfgs, &called_infos, bb(n - 1), bb(0)), None);
+    }
+
+    #[test]
+    fn test_cfg_without_blocks() {
+        use crate::core::{cfg, cfg::ControlFlowGraph, BasicBlockId, GlobalBasicBlockId};
+        use std::collections::{HashMap, HashSet};
+
+        // a synthetic body, eg. generated by a proc macro, may have neither locals nor blocks
+        let def_id = rustc_hir::def_id::CRATE_DEF_ID.to_def_id();
+        let cfg = ControlFlowGraph {
+            options: utils::parse_args(&["mc".to_owned()]).unwrap().0,
+            def_id,
+            local_infos: HashMap::new(),
+            basic_block_infos: HashMap::new(),
+            call_infos: HashMap::new(),
+            ret_bbs: HashSet::new(),
+            assert_failure_bbs: HashSet::new(),
+            loop_drop_bbs: HashSet::new(),
+            allowed_bugs: HashSet::new(),
+            address_locals: HashSet::new(),
+            unmodeled_assigns: vec![],
+            is_local_crate: true,
+        };
+        let bb = |i| GlobalBasicBlockId::new(def_id, BasicBlockId::from_usize(i));
+        assert!(cfg::get_reachable_bbs(&cfg.basic_block_infos, [bb(0).bb_id]).is_empty());
+        assert!(!utils::can_inner_basic_block_arrive(&cfg, bb(0).bb_id, bb(1).bb_id));
+
+        let cfgs = HashMap::from([(def_id, cfg)]);
+        let called_infos = HashMap::new();
+        assert_eq!(utils::basic_block_path(&cfgs, &called_infos, bb(0), bb(1)), None);
+    }
+
+    #[test]
+    fn test_summary_by_function() {
+        use crate::core::check::{self, CheckResult, UnitResult};
+        use rustc_span::{BytePos, Span};
+
+        let mut check_result = CheckResult::new();
+        for (i, fn_path) in ["b", "a", "b", "c", "a", "b"].iter().enumerate() {
+            let span = Span::with_root_ctxt(BytePos(i as u32), BytePos(i as u32 + 1));
+            check_result
+                .leak_results
+                .insert(span, UnitResult::new(span, None, fn_path.to_string()));
+        }
+        assert_eq!(
+            check::summary_by_function(&check_result),
+            vec![("b".to_owned(), 3), ("a".to_owned(), 2), ("c".to_owned(), 1)]
+        );
+        assert!(check::summary_by_function(&CheckResult::new()).is_empty());
+    }
+
+    #[test]
+    fn test_slowest_functions() {
+        use crate::core::check;
+        use std::time::Duration;
+
+        let times = [("b", 2), ("a", 3), ("c", 2), ("d", 1)]
+            .map(|(fn_path, millis)| (fn_path.to_owned(), Duration::from_millis(millis)));
+        assert_eq!(
+            check::slowest_functions(times.clone(), 3),
+            vec![
+                ("a".to_owned(), Duration::from_millis(3)),
+                ("b".to_owned(), Duration::from_millis(2)),
+                ("c".to_owned(), Duration::from_millis(2))
+            ]
+        );
+        assert!(check::slowest_functions(times, 0).is_empty());
+    }
+
+    #[test]
+    fn test_report_path() {
+        use crate::core::check::{self, CheckResult};
+
+        let text = utils::capture_plain_output(|| check::output_level_text("warning", "captured"));
+        assert_eq!(text, "warning:(memory check) captured\n");
+
+        let path = std::env::temp_dir().join(format!("mc-report-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        check::write_report(&CheckResult::new(), path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "info:(memory check) total: 0 uaf bugs, 0 df bugs, 0 dp bugs, 0 leak bugs, 0 alias assign bugs, 0 ffi callback bugs, 0 rc cycle bugs, 0 use after move bugs\n"
+        );
+        std::fs::remove_file(path).unwrap();
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--report-path=out.txt".to_owned()]).unwrap();
+        assert_eq!(utils::report_path(&options), Some("out.txt"));
+    }
+
+    #[test]
+    fn test_warn_unsupported() {
+        use crate::core::check;
+        use rustc_span::DUMMY_SP;
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--warn-unsupported=1".to_owned()]).unwrap();
+        assert!(utils::warn_unsupported(&options));
+        let (options, _) = utils::parse_args(&["mc".to_owned()]).unwrap();
+        assert!(!utils::warn_unsupported(&options));
+
+        assert_eq!(check::unmodeled_summary(&[]), None);
+        assert_eq!(
+            check::unmodeled_summary(&[(DUMMY_SP, "Len"), (DUMMY_SP, "BinaryOp"), (DUMMY_SP, "BinaryOp")]).unwrap(),
+            "3 pointer operations were not modeled (BinaryOp: 2, Len: 1); results may be incomplete; \
+             rerun with --open-dbg=1 --DBG=assign for details"
+        );
+    }
+
+    #[test]
+    fn test_max_findings() {
+        use crate::core::check;
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--max-findings=2".to_owned()]).unwrap();
+        assert_eq!(utils::max_findings(&options), Some(2));
+        let (options, _) = utils::parse_args(&["mc".to_owned()]).unwrap();
+        assert_eq!(utils::max_findings(&options), None);
+
+        assert_eq!(check::suppressed_findings(5, 1, Some(2)), 3);
+        assert_eq!(check::suppressed_findings(5, 1, None), 0);
+        assert_eq!(check::suppressed_findings(0, 0, Some(0)), 0);
+    }
+
+    #[test]
+    fn test_quiet_summary() {
+        use crate::core::check::{self, CheckResult};
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--quiet=1".to_owned()]).unwrap();
+        assert!(utils::quiet(&options));
+        let (options, rustc_args) = utils::parse_args(&["mc".to_owned(), "--quiet".to_owned()]).unwrap();
+        assert!(utils::quiet(&options));
+        assert_eq!(rustc_args, ["mc"]);
+        let (options, _) = utils::parse_args(&["mc".to_owned()]).unwrap();
+        assert!(!utils::quiet(&options));
+
+        assert_eq!(
+            check::analysis_summary(12, 3, &CheckResult::new()),
+            "analyzed 12 functions, 3 entries, found 0 use-after-free and 0 double-free issues"
+        );
+
+        let (options, _) = utils::parse_args(&["mc".to_owned(), "--json-summary".to_owned()]).unwrap();
+        assert_eq!(utils::json_summary_path(&options), Some("-"));
+        let (options, args) = utils::parse_args(&["mc".to_owned(), "--json-summary=summary.json".to_owned()]).unwrap();
+        assert_eq!(utils::json_summary_path(&options), Some("summary.json"));
+        assert_eq!(args, ["mc"]);
+        assert_eq!(
+            check::json_summary(12, 3, &CheckResult::new()).to_string(),
+            r#"{"df":0,"entries":3,"functions_analyzed":12,"uaf":0}"#
+        );
+
+        assert_eq!(CheckResult::new().to_string(), "");
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_value(CheckResult::new()).unwrap(),
+            serde_json::json!({"uaf": [], "df": []})
+        );
+    }
+
+    // the analysis of `file` in an out dir of its own, so that the tests may run in parallel
+    fn run(file: &str, extra_args: &[&str]) -> crate::AnalysisReport {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static RUN_ID: AtomicUsize = AtomicUsize::new(0);
+        let out_dir = std::env::temp_dir().join(format!(
+            "mc-run-analysis-{}-{}",
+            std::process::id(),
+            RUN_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        let mut args = ["mc", file, "--out-dir", out_dir.to_str().unwrap()].map(str::to_owned).to_vec();
+        args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        let report = crate::run_analysis(&args).unwrap();
+        std::fs::remove_dir_all(out_dir).ok();
+        report
+    }
+
+    // the kind and the fn of each finding
+    fn kinds_and_fns(report: &crate::AnalysisReport) -> Vec<(&'static str, &str)> {
+        report.findings.iter().map(|finding| (finding.kind, finding.fn_path.as_str())).collect()
+    }
+
+    // the fns of the findings of one kind, the fixtures of an optional check may have findings of the default checks too
+    fn fns_of_kind<'a>(report: &'a crate::AnalysisReport, kind: &str) -> Vec<&'a str> {
+        report
+            .findings
+            .iter()
+            .filter(|finding| finding.kind == kind)
+            .map(|finding| finding.fn_path.as_str())
+            .collect()
+    }
+
+    fn fn_paths(report: &crate::AnalysisReport) -> Vec<&str> {
+        report.findings.iter().map(|finding| finding.fn_path.as_str()).collect()
+    }
+
+    // the findings of each fixture, only those of one kind if given, eg. for an optional check whose
+    // fixture has findings of the default checks too
+    #[test]
+    fn test_fixtures() {
+        let fixtures: &[(&str, &[&str], Option<&str>, &[(&str, &str)])] = &[
+            // the allow on the callee `allowed` suppresses its double free when called from `main` too
+            ("examples/double_free/allowed.rs", &["--entries=main"], None, &[("df", "reported")]),
+            // the fields left after a partial move are dropped on their own, each is checked apart
+            (
+                "examples/double_free/partial_move.rs",
+                &[],
+                None,
+                &[("df", "drop_a_then_move_b"), ("df", "drop_b_then_move_a")],
+            ),
+            // the value read out of a pointer is owned twice, and the write through a pointer is a use
+            (
+                "examples/double_free/ptr_read_write.rs",
+                &[],
+                None,
+                &[("df", "read_then_drop_both"), ("df", "replace_with_read"), ("uaf", "write_after_free")],
+            ),
+            // `mem::drop` drops its arg at the call, a value given to `mem::forget` is never dropped,
+            // not even by the cleanup after the call
+            ("examples/double_free/explicit_drop.rs", &[], None, &[("df", "drop_both"), ("df", "drop_then_scope")]),
+            (
+                "examples/double_free/explicit_drop.rs",
+                &["--ignore-unwind=1"],
+                None,
+                &[("df", "drop_both"), ("df", "drop_then_scope")],
+            ),
+            // the pointer transmuted from another one points to the same box
+            ("examples/double_free/transmute_alias.rs", &[], None, &[("df", "main")]),
+            // the value taken out of an option or a cell is no longer owned by the container
+            (
+                "examples/double_free/take_value.rs",
+                &[],
+                None,
+                &[("df", "take_then_double_from_raw"), ("uaf", "take_then_read")],
+            ),
+            // the ownership given up by `into_raw` is reclaimed once in `reclaim`, never in `leak`
+            ("examples/memory_leak/box_into_raw_leak.rs", &[], None, &[("leak", "leak")]),
+            ("examples/double_free/box_double_from_raw.rs", &[], None, &[("df", "main")]),
+            // `*pa` and `*pb` are one box in `main`, a new box is assigned in `assign_new_value`
+            (
+                "examples/double_free/alias_assign.rs",
+                &["--alias-assign=1"],
+                Some("alias_assign"),
+                &[("alias_assign", "main")],
+            ),
+            ("examples/double_free/alias_assign.rs", &[], Some("alias_assign"), &[]),
+            // only the value read out of the same place in every iteration is freed again
+            ("examples/double_free/loop_drop.rs", &[], Some("df"), &[("df", "consume_in_loop")]),
+            // the forgotten struct and the `ManuallyDrop` field are not dropped again
+            ("examples/double_free/field_drop_in_place.rs", &[], Some("df"), &[("df", "drop_field")]),
+            // the state leaked to the foreign side, or passed without a callback, is not reported
+            (
+                "examples/use_after_free/ffi_callback.rs",
+                &["--ffi-callback=1"],
+                Some("ffi_callback"),
+                &[("ffi_callback", "dropped_while_registered"), ("ffi_callback", "dropped_while_optional_registered")],
+            ),
+            ("examples/use_after_free/ffi_callback.rs", &[], Some("ffi_callback"), &[]),
+            // the pointers to different values or to different fields of one value don't alias
+            (
+                "examples/double_free/aliased_drop_in_place.rs",
+                &[],
+                Some("df"),
+                &[("df", "copied_pointer"), ("df", "two_borrows")],
+            ),
+            // the calls are only drops when registered
+            (
+                "examples/double_free/custom_free.rs",
+                &["--drop-fns=my_free,pool_free:1"],
+                None,
+                &[("df", "free_then_drop"), ("df", "pool_free_then_drop")],
+            ),
+            ("examples/double_free/custom_free.rs", &[], Some("df"), &[]),
+            // a weak back edge or a chain is no cycle
+            (
+                "examples/memory_leak/rc_cycle.rs",
+                &["--rc-cycle=1"],
+                Some("rc_cycle"),
+                &[("rc_cycle", "parent_child_cycle"), ("rc_cycle", "self_cycle")],
+            ),
+            ("examples/memory_leak/rc_cycle.rs", &[], Some("rc_cycle"), &[]),
+            // a dynamic index may be any element of its own array, not of another one
+            (
+                "examples/double_free/dynamic_index.rs",
+                &[],
+                None,
+                &[("df", "two_dynamic_indices"), ("df", "same_dynamic_index")],
+            ),
+            // the guard in scope of its container is not reported
+            (
+                "examples/use_after_free/guard_escape.rs",
+                &["--guard-escape=1"],
+                Some("uaf"),
+                &[("uaf", "escaped_ref"), ("uaf", "escaped_ref_mut"), ("uaf", "escaped_mutex_guard")],
+            ),
+            // a buffer given back to one vec, or one buffer per vec, is freed once
+            ("examples/double_free/vec_from_raw_parts.rs", &[], None, &[("df", "two_vecs_from_one_buffer")]),
+            // the box returned by `return_box` moves its ownership to the caller
+            (
+                "examples/dangling_pointer/return_local.rs",
+                &[],
+                Some("dp"),
+                &[("dp", "return_local_ptr"), ("dp", "return_local_ref"), ("dp", "store_to_out")],
+            ),
+            // the drop flag guards the drop in `conditional_move`, the other boxes leave their fns
+            (
+                "examples/memory_leak/conditional_drop.rs",
+                &[],
+                None,
+                &[("leak", "leak_in_one_arm"), ("leak", "leak_always")],
+            ),
+            // the captured pointers are dereferenced in the closure bodies, after the boxes are dropped
+            (
+                "examples/use_after_free/closure_capture.rs",
+                &[],
+                None,
+                &[("uaf", "deref_in_fn"), ("uaf", "deref_in_fn_once")],
+            ),
+            // the offset and the untagged addresses still point to the dropped boxes
+            (
+                "examples/use_after_free/pointer_arithmetic.rs",
+                &[],
+                None,
+                &[("uaf", "address_offset"), ("uaf", "tagged_pointer")],
+            ),
+            // no fn outside the cycle calls `ping` or `pong`, one of them is still picked as the entry
+            ("examples/use_after_free/uncalled_recursion.rs", &[], None, &[("uaf", "ping")]),
+        ];
+        for (file, args, kind, expected) in fixtures {
+            let report = run(file, args);
+            let findings = kinds_and_fns(&report)
+                .into_iter()
+                .filter(|(finding_kind, _)| kind.map_or(true, |kind| *finding_kind == kind))
+                .collect::<Vec<_>>();
+            assert_eq!(findings, *expected, "{} {:?}", file, args);
+        }
+    }
+
+    #[test]
+    fn test_run_analysis() {
+        assert_eq!(run("examples/double_free/rc_clone.rs", &[]).outcome(), crate::AnalysisOutcome::Clean);
+
+        let report = run("examples/use_after_free/rc_clone.rs", &[]);
+        assert_eq!(report.outcome(), crate::AnalysisOutcome::Findings);
+        assert!(report.failed_entries.is_empty());
+        let finding = &report.findings[0];
+        assert_eq!(finding.kind, "uaf");
+        assert_eq!(finding.fn_path, "deref_after_all_dropped");
+        assert_eq!(finding.location.file, "examples/use_after_free/rc_clone.rs");
+        assert!(finding.labels.iter().any(|(location, _)| location.line_range == (24, 24)));
+
+        // the box reclaimed from its elements is allocated by `Box::new`
+        let report = run("examples/double_free/boxed_slice.rs", &[]);
+        let finding = report.findings.iter().find(|finding| finding.fn_path == "from_raw_of_elements").unwrap();
+        assert!(finding
+            .labels
+            .iter()
+            .any(|(location, label)| label == "allocated here." && location.line_range == (12, 12)));
+    }
+
+    #[test]
+    fn test_assert_desugaring() {
+        // the box dropped only when the assertion fails is not a double free with the returned box,
+        // the dereference inside `assert_eq!` is reported at the assertion
+        let report = run("examples/use_after_free/assert_desugaring.rs", &[]);
+        assert_eq!(kinds_and_fns(&report), [("uaf", "main")]);
+        let finding = &report.findings[0];
+        assert_eq!(finding.location.file, "examples/use_after_free/assert_desugaring.rs");
+        assert!(finding.labels.iter().any(|(location, _)| location.line_range == (20, 20)));
+    }
+
+    #[test]
+    fn test_use_after_move() {
+        // the reads through the pointers taken before the moves, but not after the reassignment,
+        // the string moved into `drop` is freed too
+        let report = run("examples/use_after_free/use_after_move.rs", &["--use-after-move=1"]);
+        assert_eq!(
+            kinds_and_fns(&report).into_iter().filter(|(kind, _)| *kind != "use_after_move").collect::<Vec<_>>(),
+            [("uaf", "moved_on_both_branches")]
+        );
+        assert_eq!(
+            kinds_and_fns(&report)
+                .into_iter()
+                .filter(|(kind, _)| *kind == "use_after_move")
+                .map(|(_, fn_path)| fn_path)
+                .collect::<Vec<_>>(),
+            [
+                "read_through_pointer_after_move",
+                "read_after_move_into_call",
+                "moved_on_both_branches",
+                "moved_on_both_branches",
+                "read_after_move_chain"
+            ]
+        );
+
+        // the declaration, the earlier move and the reported move of the value, then the read
+        let chain = report.findings.iter().find(|finding| finding.fn_path == "read_after_move_chain").unwrap();
+        assert_eq!(
+            chain.labels.iter().map(|(location, label)| (location.line_range.0, label.as_str())).collect::<Vec<_>>(),
+            [
+                (74, "declared here, relative variable: s"),
+                (75, "moved here, relative variable: s"),
+                (77, "moved out here, relative variable: t"),
+                (79, "then used here, relative variable: p"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constant_index() {
+        // the elements at distinct constant indices are tracked apart, a dynamic index may be any of them
+        let report = run("examples/double_free/constant_index.rs", &[]);
+        assert_eq!(fn_paths(&report), ["same_constant_index", "dynamic_and_constant_index", "read_element"]);
+    }
+
+    #[test]
+    fn test_exclusive_branches() {
+        // the calls on exclusive branches of an entry never both free the box
+        let file = "examples/double_free/exclusive_branches.rs";
+        assert!(run(file, &["--entries=free_on_either_branch"]).findings.is_empty());
+        assert_eq!(run(file, &["--entries=free_twice"]).findings.len(), 1);
+        // with both entries auto detected, only the findings of the focused one
+        assert!(run(file, &["--focus-entry=free_on_either_branch"]).findings.is_empty());
+        assert_eq!(run(file, &["--focus-entry=free_twice"]).findings.len(), 1);
+    }
+
+    #[test]
+    fn test_trait_dispatch() {
+        // a trait method call on an unknown receiver may go to any impl, a known impl is called alone
+        let report = run("examples/double_free/trait_dispatch.rs", &[]);
+        let fn_paths = fn_paths(&report);
+        assert!(fn_paths.contains(&"give_dyn") && fn_paths.contains(&"give_generic"));
+        assert!(!fn_paths.contains(&"give_keeper"));
+    }
+
+    #[test]
+    fn test_cross_fn_labels() {
+        // the drops in different fns name their fns, those in the same fn do not
+        let report = run("examples/double_free/cross_fn.rs", &[]);
+        let labels = report.findings[0].labels.iter().map(|(_, label)| label.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                "first drop here in fn a::release, relative variable: _v",
+                "then drop here in fn b::release, relative variable: _v"
+            ]
+        );
+        let report = run("examples/double_free/partial_move.rs", &[]);
+        assert!(report.findings[0].labels.iter().all(|(_, label)| !label.contains(" in fn ")));
+    }
+
+    #[test]
+    fn test_exclude_path() {
+        // only the finding entirely in the excluded file is left out
+        let report = run("examples/use_after_free/exclude_path.rs", &["--exclude-path=**/generated.rs"]);
+        assert_eq!(fn_paths(&report), ["read_after_free"]);
+    }
+
+    #[test]
+    fn test_ignore_unwind() {
+        // the paths of unwinding are analyzed unless `--ignore-unwind` compiles with panic=abort
+        assert_eq!(run("examples/double_free/unwind.rs", &[]).findings.len(), 1);
+        assert!(run("examples/double_free/unwind.rs", &["--ignore-unwind=1"]).findings.is_empty());
+    }
+
+    #[test]
+    fn test_object_granularity() {
+        // the boxes of the same type are merged into one object, dropped in both fns
+        let local = run("examples/double_free/object_granularity.rs", &["--object-granularity=local", "--stats"]);
+        assert!(local.findings.is_empty());
+        let by_type = run("examples/double_free/object_granularity.rs", &["--object-granularity=type", "--stats"]);
+        assert_eq!(kinds_and_fns(&by_type), [("df", "first")]);
+        assert!(by_type.stats.unwrap().0 < local.stats.unwrap().0);
+    }
+
+    #[test]
+    fn test_call_path() {
+        // the drop in a callee shows the call sites it is reached through
+        let report = run("examples/use_after_free/drop_in_callee.rs", &["--call-path=1", "--context-depth=2"]);
+        let finding = report.findings.iter().find(|finding| finding.kind == "uaf").unwrap();
+        let call_path = finding
+            .labels
+            .iter()
+            .filter(|(_, label)| label.starts_with("through call path"))
+            .map(|(location, _)| location.line_range.0)
+            .collect::<Vec<_>>();
+        assert_eq!(call_path, [12, 7]);
+    }
+
+    #[test]
+    fn test_only_mut_deref() {
+        // only the write through the pointer is reported, the read is left out
+        let report = run("examples/use_after_free/mut_deref.rs", &["--only-mut-deref=1"]);
+        assert_eq!(fn_paths(&report), ["write_after_free"]);
+    }
+
+    #[test]
+    fn test_timeout() {
+        // the deadline has passed before the first entry, nothing is analyzed
+        let report = run("examples/use_after_free/rc_clone.rs", &["--timeout=0"]);
+        assert!(report.timed_out);
+        assert!(report.findings.is_empty());
+        assert_eq!(report.exit_code(), crate::EXIT_ABORTED);
+    }
+
+    #[test]
+    fn test_dump_pfg() {
+        // one digraph for each entry, the drop reached by both objects is red
+        let dot_path = std::env::temp_dir().join(format!("mc-pfg-{}.dot", std::process::id()));
+        let dump_pfg = format!("--dump-pfg={}", dot_path.display());
+        run("examples/double_free/explicit_drop.rs", &["--entries=drop_both,forget_then_drop", &dump_pfg]);
+        let dot = std::fs::read_to_string(&dot_path).unwrap();
+        std::fs::remove_file(&dot_path).ok();
+        assert_eq!(dot.matches("digraph pfg {").count(), 2);
+        assert!(dot.contains("// entry: explicit_drop::drop_both\n"));
+        let dropped_twice = dot.lines().find(|line| line.starts_with("    \"explicit_drop::drop_both::_8::0\" [")).unwrap();
+        assert!(dropped_twice.contains("\\nprojection: []\\npoints to: "));
+        assert!(dropped_twice.ends_with(", color=red, fontcolor=red];"));
+        assert!(dot.contains("[label=\"is_deref: (true, false)\"];"));
+    }
+
+    #[test]
+    fn test_entry_panic() {
+        // the panicked entry is listed, the findings of the other entries are still reported
+        let report = run("examples/double_free/explicit_drop.rs", &["--DBG=panic-entry=drop_both"]);
+        assert_eq!(report.failed_entries, ["explicit_drop::drop_both"]);
+        assert_eq!(kinds_and_fns(&report), [("df", "drop_then_scope")]);
+    }
+
+    #[test]
+    fn test_const_eval() {
+        // the const fn and the static initializer are only analyzed with `--const-eval=1`
+        let functions_analyzed = |const_eval: &str| {
+            let summary_path = std::env::temp_dir().join(format!("mc-const-eval-{}-{}.json", std::process::id(), const_eval));
+            let json_summary = format!("--json-summary={}", summary_path.display());
+            let report = run("examples/const_eval/unsafe_const_fn.rs", &[const_eval, &json_summary]);
+            assert_eq!(report.outcome(), crate::AnalysisOutcome::Clean);
+            let summary = std::fs::read_to_string(&summary_path).unwrap();
+            std::fs::remove_file(&summary_path).ok();
+            serde_json::from_str::<serde_json::Value>(&summary).unwrap()["functions_analyzed"].as_u64().unwrap()
+        };
+        assert!(functions_analyzed("--const-eval=1") > functions_analyzed("--const-eval=0"));
+    }
+
+    #[test]
+    fn test_iter_escape() {
+        // `items` is dropped in `escaped_map`, the references it yields are read in both callers, not in `alive`
+        let deref_lines = |report: &crate::AnalysisReport| {
+            report.findings[0]
+                .labels
+                .iter()
+                .filter(|(_, label)| label.starts_with("then dereference here"))
+                .map(|(location, _)| location.line_range.0)
+                .collect::<Vec<_>>()
+        };
+        let report = run("examples/use_after_free/iter_escape.rs", &[]);
+        assert_eq!(fns_of_kind(&report, "uaf"), ["escaped_map"]);
+        assert_eq!(deref_lines(&report), [19, 27]);
+
+        // the `next` calls consuming the escaped iterator are reported too
+        let report = run("examples/use_after_free/iter_escape.rs", &["--iter-escape"]);
+        assert_eq!(fns_of_kind(&report, "uaf"), ["escaped_map"]);
+        assert_eq!(deref_lines(&report), [18, 19, 26, 27]);
+    }
+
+    #[test]
+    fn test_nested_wrapper() {
+        // the drop of `x`, read through `px`, and through `py` too when the calls of `id` share one context
+        let deref_lines = |context_depth: &str| {
+            let report = run("examples/use_after_free/nested_wrapper.rs", &[context_depth]);
+            assert_eq!(kinds_and_fns(&report), [("uaf", "main")]);
+            report.findings[0]
+                .labels
+                .iter()
+                .filter(|(_, label)| label.starts_with("then dereference here"))
+                .map(|(location, _)| location.line_range.0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(deref_lines("--context-depth=1"), [19, 20]);
+        assert_eq!(deref_lines("--context-depth=2"), [20]);
+    }
+
+    #[test]
+    fn test_mutual_recursion() {
+        // the contexts along the cycle are bounded, a deep context still finishes and finds the read in `even`
+        let report = run("examples/use_after_free/mutual_recursion.rs", &["--context-depth=8"]);
+        assert!(!report.timed_out);
+        assert_eq!(kinds_and_fns(&report), [("uaf", "main")]);
+    }
+
+    #[test]
+    fn test_cjk_source() {
+        // the read through `p` is on the line with the wide characters
+        let report = run("examples/use_after_free/cjk_source.rs", &[]);
+        assert_eq!(kinds_and_fns(&report), [("uaf", "main")]);
+        assert!(report.findings[0]
+            .labels
+            .iter()
+            .any(|(location, label)| label.starts_with("then dereference here") && location.line_range == (8, 8)));
+    }
+
+    #[test]
+    fn test_list_entries() {
+        // the entries are only listed, nothing is analyzed
+        let report = run("examples/use_after_free/rc_clone.rs", &["--list-entries=1"]);
+        assert_eq!(report.outcome(), crate::AnalysisOutcome::Clean);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        use crate::core::check::{CheckResult, UnitResult};
+        use crate::AnalysisOutcome;
+
+        let mut check_result = CheckResult::new();
+        assert!(check_result.is_empty());
+        assert_eq!(AnalysisOutcome::Clean.exit_code(), 0);
+
+        let span = rustc_span::DUMMY_SP;
+        check_result
+            .leak_results
+            .insert(span, UnitResult::new(span, None, "main".to_owned()));
+        assert!(!check_result.is_empty());
+        assert_eq!(AnalysisOutcome::Findings.exit_code(), 2);
+        assert_eq!(crate::EXIT_ERROR, 1);
+        assert_eq!(crate::AnalysisError::TimedOut.exit_code(), 3);
+
+        let err = crate::AnalysisError::Aborted { reason: "out of memory".to_owned() };
+        assert_eq!(err.to_string(), "the analysis was aborted: out of memory");
+    }
 }